@@ -72,8 +72,8 @@ impl Parse for ParamSection {
     }
 }
 
-/// Whether the endpoint returns a paged result, and generates an iterator
-/// function for the items.
+/// Whether the endpoint returns a paged result (whose type must implement `PagedResponse`), and
+/// generates a cursor function and an iterator function for the items.
 struct Paged {
     #[cfg_attr(not(feature = "stream"), expect(dead_code))]
     field: Ident,
@@ -114,7 +114,6 @@ struct ApiEndpoint {
     attrs: Vec<Attribute>,
     name: Ident,
     return_type: Type,
-    #[cfg_attr(not(feature = "stream"), expect(dead_code))]
     paged: Option<Paged>,
     method: Ident,
     url: LitStr,
@@ -225,7 +224,9 @@ impl Parse for ApiEndpoints {
 /// `/// doc? name -> ReturnType (paged @next_url? field: ItemType)? { GET|POST|DELETE "path", params [ ... ]? data [ ... ]? }`
 ///
 /// - Params: `name: Type = default => transmute`; use `name @ "key": Type` to override query/form key.
-/// - Paged: `(paged illusts: IllustrationInfo)` generates a method returning a struct with `illusts` and `next_url`.
+/// - Paged: `(paged illusts: IllustrationInfo)` marks a return type with `illusts` and `next_url` (deriving `PagedResponse`), and also generates `name_cursor` and, with `stream`, `name_iter`.
+/// - Every endpoint also gets a `name_with_extra` variant taking `extra: KVPairs` appended to the form data (if any) or the query.
+/// - Every endpoint also gets a `name_with_meta` variant returning a `ResponseEnvelope` with status, headers and timing.
 ///
 /// 根据端点定义在 `AppPixivAPI` 上生成异步 API 方法。语法：多个端点用 `;` 分隔；每条可含 doc、返回类型、可选 paged、方法、路径及 params/data。
 #[proc_macro]
@@ -247,7 +248,6 @@ pub fn api_endpoints(input: TokenStream) -> TokenStream {
         let mut fn_params = Vec::new();
         let mut section_inits = Vec::new();
         let mut section_bodies = Vec::new();
-        #[cfg(feature = "stream")]
         let mut fn_args = Vec::new();
         let mut folder = ExplicitLifetimeFolder::new();
 
@@ -286,7 +286,6 @@ pub fn api_endpoints(input: TokenStream) -> TokenStream {
 
                 section_bodies.push(quote! { { #body_for_this } });
 
-                #[cfg(feature = "stream")]
                 fn_args.push(quote! { #name, });
             }
 
@@ -296,17 +295,49 @@ pub fn api_endpoints(input: TokenStream) -> TokenStream {
             });
         }
 
-        let params = if endpoint.find_section("params").is_some() {
+        // Extra pairs go to the form body if the endpoint has one, to the query otherwise.
+        let has_data = endpoint.find_section("data").is_some();
+        let has_params = endpoint.find_section("params").is_some() || !has_data;
+        if endpoint.find_section("params").is_none() && has_params {
+            section_inits.push(quote! {
+                let mut params: kv_pairs::KVPairs<'_> = kv_pairs::kv_pairs![];
+            });
+        }
+        let extra_target = if has_data {
+            quote! { data }
+        } else {
+            quote! { params }
+        };
+
+        let params = if has_params {
             quote! { Some(params) }
         } else {
             quote! { None }
         };
-        let data = if endpoint.find_section("data").is_some() {
+        let data = if has_data {
             quote! { Some(data) }
         } else {
             quote! { None }
         };
 
+        let with_extra_fn_name = quote::format_ident!("{}_with_extra", name);
+        let with_meta_fn_name = quote::format_ident!("{}_with_meta", name);
+        let impl_fn_name = quote::format_ident!("{}_impl", name);
+        let with_meta_doc_comment = format!(
+            "Same as [`Self::{0}`], but also returns the HTTP status, response headers and elapsed time.\n\n同 [`Self::{0}`]，同时返回 HTTP 状态码、响应头与耗时。",
+            name,
+        );
+        let with_extra_doc_comment = format!(
+            "Same as [`Self::{0}`], with `extra` pairs appended to the {1}.\n\n同 [`Self::{0}`]，并将 `extra` 附加到{2}。",
+            name,
+            if has_data { "form data" } else { "query" },
+            if has_data {
+                "表单数据"
+            } else {
+                "查询参数"
+            },
+        );
+
         let lifetimes = &folder.lifetimes;
         let expanded_endpoint = quote! {
             #(#attrs)*
@@ -316,17 +347,82 @@ pub fn api_endpoints(input: TokenStream) -> TokenStream {
                 #(#fn_params)*
                 with_auth: bool,
             ) -> Result<#return_type, crate::error::PixivError> {
-                let url = format!("{}{}", self.hosts, #url);
-                #(#section_inits)*
-                #(#section_bodies)*
-                crate::debug!("calling {} at {}", stringify!(#name), #url);
-                let r = self.do_api_request(crate::aapi::HttpMethod::#method, &url, None, #params, #data, with_auth).await?;
-                crate::models::parse_response_into::<#return_type>(r).await
+                Ok(self.#impl_fn_name(#(#fn_args)* kv_pairs::KVPairs::new(), with_auth).await?.data)
+            }
+
+            #[doc = #with_extra_doc_comment]
+            #[allow(clippy::too_many_arguments)]
+            pub async fn #with_extra_fn_name<'a0 #(, #lifetimes)*>(
+                &'a0 self,
+                #(#fn_params)*
+                extra: kv_pairs::KVPairs<'_>,
+                with_auth: bool,
+            ) -> Result<#return_type, crate::error::PixivError> {
+                Ok(self.#impl_fn_name(#(#fn_args)* extra, with_auth).await?.data)
+            }
+
+            #[doc = #with_meta_doc_comment]
+            #[allow(clippy::too_many_arguments)]
+            pub async fn #with_meta_fn_name<'a0 #(, #lifetimes)*>(
+                &'a0 self,
+                #(#fn_params)*
+                with_auth: bool,
+            ) -> Result<crate::models::ResponseEnvelope<#return_type>, crate::error::PixivError> {
+                self.#impl_fn_name(#(#fn_args)* kv_pairs::KVPairs::new(), with_auth).await
+            }
+
+            #[allow(clippy::too_many_arguments)]
+            async fn #impl_fn_name<'a0 #(, #lifetimes)*>(
+                &'a0 self,
+                #(#fn_params)*
+                extra: kv_pairs::KVPairs<'_>,
+                with_auth: bool,
+            ) -> Result<crate::models::ResponseEnvelope<#return_type>, crate::error::PixivError> {
+                let _operation = self.begin_operation()?;
+                let url = self.api_url(#url);
+                let result: Result<_, crate::error::PixivError> = async {
+                    #(#section_inits)*
+                    #(#section_bodies)*
+                    #extra_target.extend(extra);
+                    crate::debug!("calling {} at {}", stringify!(#name), #url);
+                    self.api_call::<#return_type>(crate::aapi::HttpMethod::#method, &url, None, #params, #data, with_auth).await
+                }
+                .await;
+                result.map_err(|e| e.in_endpoint(stringify!(#name), &url))
             }
         };
 
         expanded.extend(expanded_endpoint);
 
+        if endpoint.paged.is_some() {
+            let cursor_fn_name = quote::format_ident!("{}_cursor", name);
+            let cursor_doc_comment = format!(
+                "Page through the results of {0} with [`PageCursor::next_page`](crate::cursor::PageCursor::next_page).\n\n以游标方式逐页获取{0}的结果。",
+                name
+            );
+
+            let cursor_fn = quote! {
+                #[allow(clippy::too_many_arguments)]
+                #[doc = #cursor_doc_comment]
+                pub fn #cursor_fn_name<'a0 #(, #lifetimes)*>(
+                    &'a0 self,
+                    #(#fn_params)*
+                    with_auth: bool,
+                ) -> crate::cursor::PageCursor<'a0, #return_type>
+                where
+                    #(#lifetimes: 'a0,)*
+                {
+                    crate::cursor::PageCursor::new(
+                        self,
+                        Box::new(move || Box::pin(self.#name(#(#fn_args)* with_auth))),
+                        with_auth,
+                    )
+                }
+            };
+
+            expanded.extend(cursor_fn);
+        }
+
         #[cfg(feature = "stream")]
         if let Some(paged) = &endpoint.paged {
             use quote::format_ident;
@@ -357,7 +453,7 @@ pub fn api_endpoints(input: TokenStream) -> TokenStream {
 
                     async_stream::try_stream! {
                         crate::debug!("{} first request to {}", stringify!(#iter_fn_name), #url);
-                        let mut result = self.#name(#(#fn_args)* with_auth).await?;
+                        let mut result = self.fetch_page(|| self.#name(#(#fn_args)* with_auth)).await?;
                         let mut next_url = result.#next_url_field;
 
                         loop {
@@ -368,7 +464,8 @@ pub fn api_endpoints(input: TokenStream) -> TokenStream {
                             match &next_url {
                                 Some(url) => {
                                     crate::debug!("{} next request to {}", stringify!(#iter_fn_name), url);
-                                    result = self.visit_next_url::<#return_type>(url, with_auth).await?;
+                                    self.pace_page().await;
+                                    result = self.fetch_page(|| self.visit_next_url::<#return_type>(url, with_auth)).await?;
                                     next_url = result.#next_url_field;
                                 }
                                 None => {
@@ -388,6 +485,53 @@ pub fn api_endpoints(input: TokenStream) -> TokenStream {
     TokenStream::from(expanded)
 }
 
+/// Derive `crate::models::PagedResponse` for a paged response struct, reading the next page URL
+/// from its `next_url: Option<String>` field, or from the field marked `#[next_url]`. Only usable
+/// inside `pixiv3-rs`.
+///
+/// 为分页响应结构体派生 `crate::models::PagedResponse`，从 `next_url: Option<String>` 字段（或标记了
+/// `#[next_url]` 的字段）读取下一页 URL；仅限 `pixiv3-rs` 内部使用。
+#[proc_macro_derive(PagedResponse, attributes(next_url))]
+pub fn derive_paged_response(input: TokenStream) -> TokenStream {
+    let input = match syn::parse::<syn::DeriveInput>(input) {
+        Ok(input) => input,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let name = &input.ident;
+    let syn::Data::Struct(data) = &input.data else {
+        return syn::Error::new(name.span(), "PagedResponse can only be derived for structs")
+            .to_compile_error()
+            .into();
+    };
+    let field = data
+        .fields
+        .iter()
+        .find(|f| f.attrs.iter().any(|a| a.path().is_ident("next_url")))
+        .or_else(|| {
+            data.fields
+                .iter()
+                .find(|f| f.ident.as_ref().is_some_and(|i| i == "next_url"))
+        })
+        .and_then(|f| f.ident.as_ref());
+    let Some(field) = field else {
+        return syn::Error::new(
+            name.span(),
+            "PagedResponse needs a `next_url` field or one marked `#[next_url]`",
+        )
+        .to_compile_error()
+        .into();
+    };
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    TokenStream::from(quote! {
+        impl #impl_generics crate::models::PagedResponse for #name #ty_generics #where_clause {
+            fn next_url(&self) -> Option<&str> {
+                self.#field.as_deref()
+            }
+        }
+    })
+}
+
 /// A no-op macro that does nothing. Used for placeholder or conditional compilation.
 #[proc_macro]
 pub fn no_op_macro(_: TokenStream) -> TokenStream {
@@ -1,20 +1,43 @@
 //! App Pixiv API (6.x app-api.pixiv.net) - port of pixivpy3.aapi.AppPixivAPI.
 //! Includes base logic: auth, HTTP client, download (from BasePixivAPI).
 
-use std::{sync::LazyLock, time::Duration};
+use std::{
+    sync::{
+        Arc, LazyLock,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
 
+use arc_swap::{ArcSwap, ArcSwapOption};
+use chrono::{DateTime, Utc};
 use kv_pairs::{KVPairs, kv_pairs};
-use reqwest::header::{AUTHORIZATION, HOST, HeaderMap, HeaderName, HeaderValue as HV, USER_AGENT};
+use reqwest::header::{
+    ACCEPT_LANGUAGE, AUTHORIZATION, HOST, HeaderMap, HeaderValue as HV, USER_AGENT,
+};
 use serde::de::DeserializeOwned;
-use tokio::io::AsyncWriteExt;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 
 use pixiv3_rs_proc::api_endpoints;
 
-use crate::debug;
-use crate::error::PixivError;
+use crate::builder::{AppPixivAPIBuilder, DEFAULT_API_HOSTS};
+use crate::circuit_breaker::{CircuitBreaker, CircuitBreakerConfig};
+use crate::device::DevicePreset;
+use crate::error::{DEFAULT_ERROR_BODY_LIMIT, PixivApiError, PixivError};
+use crate::interceptor::{Interceptor, RequestParts, ResponseMeta};
 use crate::models::*;
 use crate::params::*;
-use crate::token_manager::TokenManager;
+use crate::request::RequestOptions;
+use crate::retry::RetryPolicy;
+use crate::shutdown::{OperationGuard, Shutdown};
+use crate::token_manager::{TokenManager, TokenRefresher, TokenState, env_var};
+use crate::webapi::WebSession;
+use crate::{debug, info, warn};
+
+/// Environment variable read by [`AppPixivAPI::from_env`] for the API hosts (comma-separated).
+///
+/// [`AppPixivAPI::from_env`] 读取 API 主机（逗号分隔）的环境变量。
+pub const ENV_API_HOSTS: &str = "PIXIV_API_HOSTS";
 
 /// Simple HTTP method enum for internal requests.
 ///
@@ -30,11 +53,86 @@ pub enum HttpMethod {
     DELETE,
 }
 
+/// Result of the authentication part of [`AppPixivAPI::ping`].
+///
+/// [`AppPixivAPI::ping`] 中认证检查的结果。
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AuthStatus {
+    /// No authentication configured.
+    ///
+    /// 未配置认证。
+    NotConfigured,
+    /// An authenticated request succeeded.
+    ///
+    /// 认证请求成功。
+    Valid,
+    /// Obtaining a token or the authenticated request failed.
+    ///
+    /// 获取 token 或认证请求失败。
+    Failed(String),
+}
+
+/// Connectivity report returned by [`AppPixivAPI::ping`].
+///
+/// [`AppPixivAPI::ping`] 返回的连通性报告。
+#[derive(Debug, Clone)]
+pub struct PingReport {
+    /// Whether the API host answered at all (any HTTP status).
+    pub reachable: bool,
+    /// HTTP status of the unauthenticated probe, if any response arrived.
+    pub status: Option<reqwest::StatusCode>,
+    /// Round-trip time of the unauthenticated probe.
+    pub latency: Duration,
+    /// Error of the unauthenticated probe, if it failed.
+    pub error: Option<String>,
+    /// Whether authentication works.
+    pub auth: AuthStatus,
+}
+
 /// App-API (6.x) client. Port of `AppPixivAPI` (with base auth/HTTP/download inlined).
+///
+/// Cloning is cheap: clones share the configuration, token and connection pool, and settings
+/// changed through one clone apply to all of them.
+///
+/// App-API (6.x) 客户端。克隆开销很小：各克隆共享配置、token 与连接池，通过任一克隆修改的设置对全部生效。
+#[derive(Clone)]
 pub struct AppPixivAPI {
-    hosts: String,
-    client: reqwest::Client,
-    token_manager: TokenManager,
+    inner: Arc<ApiInner>,
+    options: Option<Arc<RequestOptions>>,
+}
+
+/// HTTP client used for API requests and downloads.
+#[cfg(not(feature = "middleware"))]
+pub(crate) type HttpClient = reqwest::Client;
+/// HTTP client used for API requests and downloads.
+#[cfg(feature = "middleware")]
+pub(crate) type HttpClient = reqwest_middleware::ClientWithMiddleware;
+
+/// Shared state of an [`AppPixivAPI`] and its clones.
+struct ApiInner {
+    hosts: ArcSwap<Vec<String>>,
+    /// Index into `hosts` of the host that last answered.
+    current_host: AtomicUsize,
+    client: HttpClient,
+    token_manager: Arc<TokenManager>,
+    max_response_size: ArcSwapOption<usize>,
+    error_body_limit: ArcSwapOption<usize>,
+    #[cfg(feature = "error-capture")]
+    error_capture_dir: ArcSwapOption<std::path::PathBuf>,
+    circuit_breaker: ArcSwapOption<CircuitBreaker>,
+    device_headers: ArcSwap<HeaderMap>,
+    shutdown: Shutdown,
+    stream_retry: ArcSwapOption<RetryPolicy>,
+    page_interval: ArcSwapOption<Duration>,
+    request_retry: ArcSwapOption<RetryPolicy>,
+    accept_language: ArcSwapOption<HV>,
+    web_session: ArcSwapOption<WebSession>,
+    interceptors: ArcSwap<Vec<Arc<dyn Interceptor>>>,
+    api_permits: ArcSwapOption<Semaphore>,
+    download_permits: ArcSwapOption<Semaphore>,
+    #[cfg(feature = "cache")]
+    response_cache: ArcSwapOption<crate::cache::ResponseCache>,
 }
 
 impl AppPixivAPI {
@@ -62,26 +160,574 @@ impl AppPixivAPI {
         Self::new_with(TokenManager::new_from_refresh_token(refresh_token))
     }
 
+    /// Create an API client from a saved refresh-token state (see [`TokenManager::save_state`]).
+    ///
+    /// 使用保存的 refresh token 状态创建 API 客户端（参见 [`TokenManager::save_state`]）。
+    pub fn new_from_token_state(state: TokenState) -> Self {
+        debug!("Creating AppPixivAPI with saved token state");
+        Self::new_with(TokenManager::load_state(state))
+    }
+
+    /// Create an API client from the environment: the token from `PIXIV_REFRESH_TOKEN` or
+    /// `PIXIV_ACCESS_TOKEN` (see [`TokenManager::from_env`]), and the API hosts from the optional,
+    /// comma-separated [`ENV_API_HOSTS`].
+    ///
+    /// 从环境变量创建 API 客户端：token 取自 `PIXIV_REFRESH_TOKEN` 或 `PIXIV_ACCESS_TOKEN`（参见
+    /// [`TokenManager::from_env`]），API 主机取自可选的、以逗号分隔的 [`ENV_API_HOSTS`]。
+    pub fn from_env() -> Result<Self, PixivError> {
+        let mut builder = AppPixivAPIBuilder::new().token_manager(TokenManager::from_env()?);
+        if let Some(hosts) = env_var(ENV_API_HOSTS) {
+            let mut hosts = hosts.split(',').map(str::trim).filter(|h| !h.is_empty());
+            if let Some(first) = hosts.next() {
+                builder = builder.hosts(first);
+            }
+            for host in hosts {
+                builder = builder.fallback_host(host);
+            }
+        }
+        builder.build()
+    }
+
     fn new_with(token_manager: TokenManager) -> Self {
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(60))
+        AppPixivAPIBuilder::new()
+            .token_manager(token_manager)
             .build()
-            .expect("reqwest client");
+            .expect("default AppPixivAPI config")
+    }
+
+    /// Create a builder to configure the HTTP client, headers and hosts before creating the client.
+    ///
+    /// 创建构建器，可在创建客户端前配置 HTTP 客户端、请求头与主机。
+    pub fn builder() -> AppPixivAPIBuilder {
+        AppPixivAPIBuilder::new()
+    }
+
+    pub(crate) fn from_parts(
+        client: HttpClient,
+        token_manager: TokenManager,
+        hosts: Vec<String>,
+        device_headers: HeaderMap,
+    ) -> Self {
         Self {
-            hosts: "https://app-api.pixiv.net".to_string(),
-            client,
-            token_manager,
+            inner: Arc::new(ApiInner {
+                hosts: ArcSwap::from_pointee(hosts),
+                current_host: AtomicUsize::new(0),
+                client,
+                token_manager: Arc::new(token_manager),
+                max_response_size: ArcSwapOption::empty(),
+                error_body_limit: ArcSwapOption::from_pointee(DEFAULT_ERROR_BODY_LIMIT),
+                #[cfg(feature = "error-capture")]
+                error_capture_dir: ArcSwapOption::empty(),
+                circuit_breaker: ArcSwapOption::empty(),
+                device_headers: ArcSwap::from_pointee(device_headers),
+                shutdown: Shutdown::default(),
+                stream_retry: ArcSwapOption::empty(),
+                page_interval: ArcSwapOption::empty(),
+                request_retry: ArcSwapOption::empty(),
+                accept_language: ArcSwapOption::empty(),
+                web_session: ArcSwapOption::empty(),
+                interceptors: ArcSwap::from_pointee(Vec::new()),
+                api_permits: ArcSwapOption::empty(),
+                download_permits: ArcSwapOption::empty(),
+                #[cfg(feature = "cache")]
+                response_cache: ArcSwapOption::empty(),
+            }),
+            options: None,
         }
     }
 
     /// Require that auth has been set; otherwise return error.
     pub async fn get_access_token(&self) -> Result<String, PixivError> {
-        self.token_manager.get_access_token().await
+        self.inner.token_manager.get_access_token().await
+    }
+
+    /// Refresh the access token now, ignoring the cached one; see [`TokenManager::force_refresh`].
+    ///
+    /// 忽略已缓存的 access token 立即刷新，参见 [`TokenManager::force_refresh`]。
+    pub async fn force_refresh_token(&self) -> Result<String, PixivError> {
+        self.inner.token_manager.force_refresh().await
+    }
+
+    /// The authenticated user, as reported by the OAuth token endpoint. Refreshes the token if no
+    /// refresh has happened yet.
+    ///
+    /// Only known when authenticating with a refresh token; fails with `PixivError::NoAuth`
+    /// without authentication and with `PixivError::InvalidArgument` for a fixed access token.
+    ///
+    /// 当前登录用户（来自 OAuth token 接口）；尚未刷新过时会先刷新 token。仅 refresh token 模式可用：
+    /// 无认证时返回 `PixivError::NoAuth`，固定 access token 时返回 `PixivError::InvalidArgument`。
+    pub async fn me(&self) -> Result<AuthUser, PixivError> {
+        let token_manager = &self.inner.token_manager;
+        match &**token_manager {
+            TokenManager::NoAuth => return Err(PixivError::NoAuth),
+            TokenManager::AccessToken { .. } => {
+                return Err(PixivError::InvalidArgument {
+                    message: "the authenticated user is only known with a refresh token".into(),
+                });
+            }
+            TokenManager::RefreshToken { .. } => {}
+        }
+        if let Some(user) = token_manager.user() {
+            return Ok(user);
+        }
+        token_manager.force_refresh().await?;
+        token_manager
+            .user()
+            .ok_or_else(|| PixivError::UnintelligibleResponse {
+                body: "token response carries no user".into(),
+            })
+    }
+
+    /// When the cached access token expires; see [`TokenManager::expires_at`].
+    ///
+    /// 已缓存 access token 的过期时间，参见 [`TokenManager::expires_at`]。
+    pub fn token_expires_at(&self) -> Option<DateTime<Utc>> {
+        self.inner.token_manager.expires_at()
+    }
+
+    /// Snapshot the token state for later [`Self::new_from_token_state`]; `None` unless created from a refresh token.
+    ///
+    /// 保存 token 状态以便之后使用 [`Self::new_from_token_state`] 恢复；仅 refresh token 模式返回 `Some`。
+    pub fn save_token_state(&self) -> Option<TokenState> {
+        self.inner.token_manager.save_state()
+    }
+
+    /// Start refreshing the access token in the background shortly before it expires; `None` unless created from a refresh token.
+    ///
+    /// Keep the returned handle alive for as long as the refresh should run.
+    ///
+    /// 启动后台任务在 access token 过期前主动刷新；仅 refresh token 模式返回 `Some`。需持有返回的句柄以保持任务运行。
+    pub fn spawn_token_refresher(&self) -> Option<TokenRefresher> {
+        self.inner.token_manager.spawn_refresher()
     }
 
     /// Set proxy hosts (e.g. pixivlite.com). Port of `set_api_proxy`.
-    pub fn set_api_proxy(&mut self, proxy_hosts: &str) {
-        self.hosts = proxy_hosts.to_string();
+    pub fn set_api_proxy(&self, proxy_hosts: &str) {
+        self.inner
+            .hosts
+            .store(Arc::new(vec![proxy_hosts.to_string()]));
+        self.inner.current_host.store(0, Ordering::Relaxed);
+    }
+
+    /// Set several API hosts (e.g. mirrors) with automatic failover.
+    ///
+    /// Requests go to the host that last answered; when it fails with a connect, TLS or timeout
+    /// error, the request is retried against the next host. Include [`DEFAULT_API_HOSTS`] to fall
+    /// back to the canonical host. Fails with `PixivError::InvalidArgument` if `hosts` is empty.
+    ///
+    /// 设置多个 API 主机（如镜像）并自动故障转移：请求发往最近一次正常响应的主机，遇到连接、TLS 或超时错误时
+    /// 依次改用下一个主机重试。`hosts` 为空时返回 `PixivError::InvalidArgument`。
+    pub fn set_api_hosts<I, S>(&self, hosts: I) -> Result<(), PixivError>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let hosts: Vec<String> = hosts.into_iter().map(Into::into).collect();
+        if hosts.is_empty() {
+            return Err(PixivError::InvalidArgument {
+                message: "at least one API host is required".to_string(),
+            });
+        }
+        self.inner.hosts.store(Arc::new(hosts));
+        self.inner.current_host.store(0, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// A handle to this client whose requests use `options`; the client itself is unaffected.
+    ///
+    /// 返回使用 `options` 发送请求的客户端句柄，原客户端不受影响。
+    pub fn with_options(&self, options: RequestOptions) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            options: Some(Arc::new(options)),
+        }
+    }
+
+    /// The underlying HTTP client.
+    pub(crate) fn client(&self) -> &HttpClient {
+        &self.inner.client
+    }
+
+    /// Full URL of an API `path` on the current host.
+    pub(crate) fn api_url(&self, path: &str) -> String {
+        let hosts = self.inner.hosts.load();
+        let current = self.inner.current_host.load(Ordering::Relaxed) % hosts.len();
+        format!("{}{}", hosts[current], path)
+    }
+
+    /// URLs to try for `url`, starting with the current host, with their host indices.
+    ///
+    /// URLs not on any configured host (e.g. the web site) are tried as is, with no host index.
+    fn failover_urls(&self, url: &str) -> Vec<(Option<usize>, String)> {
+        let hosts = self.inner.hosts.load();
+        let current = self.inner.current_host.load(Ordering::Relaxed) % hosts.len();
+        let path = hosts
+            .iter()
+            .filter(|host| url.starts_with(host.as_str()))
+            .max_by_key(|host| host.len())
+            .map(|host| &url[host.len()..]);
+        match path {
+            Some(path) => (0..hosts.len())
+                .map(|k| (current + k) % hosts.len())
+                .map(|i| (Some(i), format!("{}{}", hosts[i], path)))
+                .collect(),
+            None => vec![(None, url.to_string())],
+        }
+    }
+
+    /// The configured maximum response body size.
+    pub(crate) fn max_response_size(&self) -> Option<usize> {
+        self.inner.max_response_size.load().as_deref().copied()
+    }
+
+    /// Set the maximum accepted response body size in bytes; `None` (the default) disables the limit.
+    ///
+    /// 设置可接受的最大响应体字节数；`None`（默认）表示不限制。
+    pub fn set_max_response_size(&self, max_response_size: Option<usize>) {
+        self.inner
+            .max_response_size
+            .store(max_response_size.map(Arc::new));
+    }
+
+    /// Set how many bytes of a failed response's body are kept in the returned error (default
+    /// [`DEFAULT_ERROR_BODY_LIMIT`]); `None` keeps whole bodies.
+    ///
+    /// 设置失败响应的响应体在错误中最多保留的字节数（默认 [`DEFAULT_ERROR_BODY_LIMIT`]）；`None` 表示保留完整响应体。
+    pub fn set_error_body_limit(&self, limit: Option<usize>) {
+        self.inner.error_body_limit.store(limit.map(Arc::new));
+    }
+
+    /// Save the full body of every failed response into `dir`, recording the file in the error's
+    /// [`crate::error::ResponseContext::body_path`]; `None` (the default) disables capturing.
+    ///
+    /// 将每个失败响应的完整响应体保存到 `dir`，文件路径记录在错误的 [`crate::error::ResponseContext::body_path`] 中；`None`（默认）表示不保存。
+    #[cfg(feature = "error-capture")]
+    pub fn set_error_capture_dir(&self, dir: Option<std::path::PathBuf>) {
+        self.inner.error_capture_dir.store(dir.map(Arc::new));
+    }
+
+    /// Enable a circuit breaker with the given config, or disable it with `None` (the default).
+    ///
+    /// While the circuit is open, requests fail immediately with `PixivError::CircuitOpen`;
+    /// after each cooldown a single trial request is let through to probe the host.
+    ///
+    /// 启用（或以 `None` 关闭，默认关闭）熔断器；熔断期间请求直接返回 `PixivError::CircuitOpen`，
+    /// 每次冷却结束后仅放行一个试探请求。
+    pub fn set_circuit_breaker(&self, config: Option<CircuitBreakerConfig>) {
+        self.inner
+            .circuit_breaker
+            .store(config.map(|c| Arc::new(CircuitBreaker::new(c))));
+    }
+
+    /// Cache successful GET responses per URL, query parameters and headers (`Accept-Language`,
+    /// cookies, ...) with the given config, or disable the cache with `None` (the default).
+    /// Replacing the config drops cached responses.
+    ///
+    /// Every successful POST or DELETE through this client (bookmarking an illust, following a
+    /// user, ...) drops all cached responses, so details like `is_bookmarked` are not served
+    /// stale. Changes made elsewhere (another client, the web site) are only seen once the TTL
+    /// expires; use [`Self::clear_response_cache`] when that matters.
+    ///
+    /// 按 URL、查询参数与请求头（`Accept-Language`、Cookie 等）缓存成功的 GET 响应，`None`（默认）表示关闭；
+    /// 更换配置会清空缓存。通过本客户端成功发送的 POST 或 DELETE 请求（收藏作品、关注用户等）会清空全部缓存，
+    /// 避免返回过期的 `is_bookmarked` 等信息；其他途径（其他客户端、网站）造成的变化需等到 TTL 到期，必要时请调用
+    /// [`Self::clear_response_cache`]。
+    #[cfg(feature = "cache")]
+    pub fn set_response_cache(&self, config: Option<crate::cache::CacheConfig>) {
+        self.inner
+            .response_cache
+            .store(config.map(|c| Arc::new(crate::cache::ResponseCache::new(c))));
+    }
+
+    /// Drop all cached responses.
+    ///
+    /// 清空响应缓存。
+    #[cfg(feature = "cache")]
+    pub fn clear_response_cache(&self) {
+        if let Some(cache) = self.inner.response_cache.load().as_deref() {
+            cache.clear();
+        }
+    }
+
+    /// Select the spoofed device/app headers (`app-os`, `app-os-version`, `app-version`, `User-Agent`).
+    ///
+    /// Fails with `PixivError::InvalidArgument` if a custom preset contains an invalid header value.
+    ///
+    /// 选择伪装的设备 / App 请求头；自定义预设含非法取值时返回 `PixivError::InvalidArgument`。
+    pub fn set_device_preset(&self, preset: DevicePreset) -> Result<(), PixivError> {
+        self.inner
+            .device_headers
+            .store(Arc::new(preset.profile().to_headers()?));
+        Ok(())
+    }
+
+    /// Set the `Accept-Language` header (e.g. `en-us`, `zh-cn`) so `translated_name` of tags comes back in that language. Port of `set_accept_language`.
+    ///
+    /// Fails with `PixivError::InvalidArgument` if `language` is not a valid header value.
+    ///
+    /// 设置 `Accept-Language` 请求头（如 `en-us`、`zh-cn`），使标签的 `translated_name` 以该语言返回。
+    pub fn set_accept_language(&self, language: &str) -> Result<(), PixivError> {
+        let value = HV::from_str(language).map_err(|e| PixivError::InvalidArgument {
+            message: format!("invalid accept-language {language:?}: {e}"),
+        })?;
+        self.inner.accept_language.store(Some(Arc::new(value)));
+        Ok(())
+    }
+
+    /// The web session set with [`set_web_session`](Self::set_web_session).
+    pub(crate) fn web_session(&self) -> Option<Arc<WebSession>> {
+        self.inner.web_session.load_full()
+    }
+
+    pub(crate) fn store_web_session(&self, session: Option<WebSession>) {
+        self.inner.web_session.store(session.map(Arc::new));
+    }
+
+    /// Retry failed page fetches of the generated `_iter` streams with `policy`; `None` (the default) ends the stream on the first error.
+    ///
+    /// Only errors selected by the policy's `retry_on` (by default network failures, rate limits
+    /// and server errors) are retried.
+    ///
+    /// 设置生成的 `_iter` 流在获取分页失败时的重试策略；`None`（默认）表示首次出错即结束。仅重试策略 `retry_on` 选中的错误（默认为网络故障、限流、服务端错误）。
+    pub fn set_stream_retry(&self, policy: Option<RetryPolicy>) {
+        self.inner.stream_retry.store(policy.map(Arc::new));
+    }
+
+    /// Wait `interval` before every `next_url` fetch of the generated `_iter` streams, so long
+    /// crawls are throttled without sleeping between items by hand; `None` (the default) fetches
+    /// the next page right away. The first page of a stream is not delayed.
+    ///
+    /// 设置生成的 `_iter` 流每次通过 `next_url` 获取下一页前的等待时间，使长时间爬取自动限速而无需手动休眠；
+    /// `None`（默认）表示立即获取。流的第一页不受影响。
+    pub fn set_page_interval(&self, interval: Option<Duration>) {
+        self.inner.page_interval.store(interval.map(Arc::new));
+    }
+
+    /// Retry every API call that fails with an error selected by `policy.retry_on`, waiting with
+    /// exponential backoff (or the server's `Retry-After`) between attempts; `None` (the default)
+    /// disables it.
+    ///
+    /// Requests other than GET are not retried after network errors that may have reached the
+    /// server, only after connect failures; see [`RetryOn::matches_request`](crate::retry::RetryOn::matches_request).
+    /// Downloads are not affected. Pages of `_iter` streams go through API calls, so with both
+    /// this and [`set_stream_retry`](Self::set_stream_retry) set the attempts multiply.
+    ///
+    /// 为所有 API 调用设置重试策略：出现 `policy.retry_on` 选中的错误时按指数退避（或服务端的
+    /// `Retry-After`）等待后重试；`None`（默认）表示不重试。非 GET 请求的网络错误仅在连接失败时重试。
+    /// 不影响下载；与流重试同时设置时重试次数会叠加。
+    pub fn set_retry_policy(&self, policy: Option<RetryPolicy>) {
+        self.inner.request_retry.store(policy.map(Arc::new));
+    }
+
+    /// Allow at most `limit` requests in flight at once, shared by all clones; further requests
+    /// wait for a slot. `None` (the default) removes the limit.
+    ///
+    /// Downloads count against this limit too, unless
+    /// [`set_download_concurrency_limit`](Self::set_download_concurrency_limit) gives them their
+    /// own. Fails with `PixivError::InvalidArgument` if `limit` is zero.
+    ///
+    /// 限制同时进行的请求数（所有克隆共享），超出的请求排队等待；`None`（默认）表示不限制。未单独设置下载并发上限时，
+    /// 下载也计入该上限。`limit` 为 0 时返回 `PixivError::InvalidArgument`。
+    pub fn set_concurrency_limit(&self, limit: Option<usize>) -> Result<(), PixivError> {
+        self.inner.api_permits.store(semaphore(limit)?);
+        Ok(())
+    }
+
+    /// Allow at most `limit` downloads in flight at once, independently of the API request limit;
+    /// `None` (the default) makes downloads share [the API request limit](Self::set_concurrency_limit).
+    /// Fails with `PixivError::InvalidArgument` if `limit` is zero.
+    ///
+    /// 单独限制同时进行的下载数；`None`（默认）表示与 API 请求共用并发上限。`limit` 为 0 时返回
+    /// `PixivError::InvalidArgument`。
+    pub fn set_download_concurrency_limit(&self, limit: Option<usize>) -> Result<(), PixivError> {
+        self.inner.download_permits.store(semaphore(limit)?);
+        Ok(())
+    }
+
+    /// Wait for a slot under the API request concurrency limit, if any.
+    async fn acquire_api_permit(&self) -> Option<OwnedSemaphorePermit> {
+        let permits = self.inner.api_permits.load_full()?;
+        permits.acquire_owned().await.ok()
+    }
+
+    /// Wait for a slot under the download concurrency limit, if any.
+    pub(crate) async fn acquire_download_permit(&self) -> Option<OwnedSemaphorePermit> {
+        match self.inner.download_permits.load_full() {
+            Some(permits) => permits.acquire_owned().await.ok(),
+            None => self.acquire_api_permit().await,
+        }
+    }
+
+    /// Register `interceptor` to run around every HTTP request; see [`Interceptor`].
+    ///
+    /// 注册在每个 HTTP 请求前后执行的拦截器，参见 [`Interceptor`]。
+    pub fn add_interceptor(&self, interceptor: impl Interceptor + 'static) {
+        self.push_interceptor(Arc::new(interceptor));
+    }
+
+    pub(crate) fn push_interceptor(&self, interceptor: Arc<dyn Interceptor>) {
+        self.inner.interceptors.rcu(|interceptors| {
+            let mut interceptors = Vec::clone(interceptors);
+            interceptors.push(interceptor.clone());
+            interceptors
+        });
+    }
+
+    /// Remove all registered interceptors.
+    ///
+    /// 移除所有已注册的拦截器。
+    pub fn clear_interceptors(&self) {
+        self.inner.interceptors.store(Arc::new(Vec::new()));
+    }
+
+    /// Run a page fetch of a stream, retrying it per the stream retry policy.
+    #[cfg(feature = "stream")]
+    pub(crate) async fn fetch_page<T, F, Fut>(&self, op: F) -> Result<T, PixivError>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T, PixivError>>,
+    {
+        let policy = self.inner.stream_retry.load_full();
+        match policy.as_deref() {
+            Some(policy) => policy.run(op).await,
+            None => {
+                let mut op = op;
+                op().await
+            }
+        }
+    }
+
+    /// Wait the configured page interval, if any, before fetching a stream's next page.
+    #[cfg(feature = "stream")]
+    pub(crate) async fn pace_page(&self) {
+        if let Some(interval) = self.inner.page_interval.load_full() {
+            tokio::time::sleep(*interval).await;
+        }
+    }
+
+    /// Stop accepting new requests and downloads, then wait up to `deadline` for in-flight ones.
+    ///
+    /// Afterwards every call fails with `PixivError::ShuttingDown`. Returns whether all in-flight
+    /// operations finished before the deadline.
+    ///
+    /// 停止接受新的请求与下载，并在 `deadline` 内等待进行中的操作完成；之后所有调用都返回
+    /// `PixivError::ShuttingDown`。返回是否在期限内全部完成。
+    pub async fn shutdown(&self, deadline: Duration) -> bool {
+        info!("Shutting down AppPixivAPI");
+        self.inner.shutdown.close(deadline).await
+    }
+
+    /// Whether [`Self::shutdown`] has been called.
+    ///
+    /// 是否已调用 [`Self::shutdown`]。
+    pub fn is_shutting_down(&self) -> bool {
+        self.inner.shutdown.is_closed()
+    }
+
+    /// Register an in-flight operation for graceful shutdown.
+    pub(crate) fn begin_operation(&self) -> Result<OperationGuard<'_>, PixivError> {
+        self.inner.shutdown.begin()
+    }
+
+    /// Parse a response with the configured body size limit.
+    pub(crate) async fn parse_response<T: DeserializeOwned>(
+        &self,
+        response: reqwest::Response,
+    ) -> Result<T, PixivError> {
+        match parse_response_into_limited(response, self.max_response_size()).await {
+            Err(error) => Err(self.bound_error_body(error).await),
+            ok => ok,
+        }
+    }
+
+    /// Capture the body of a failed response if configured, then cut it to the error body limit.
+    #[cfg_attr(not(feature = "error-capture"), allow(unused_variables))]
+    async fn bound_error_body(&self, mut error: PixivError) -> PixivError {
+        let Some((body, context)) = error.body_and_context_mut() else {
+            return error;
+        };
+        #[cfg(feature = "error-capture")]
+        if let (Some(dir), Some(context)) = (self.inner.error_capture_dir.load_full(), context) {
+            match capture_body(&dir, context.status, body).await {
+                Ok(path) => context.body_path = Some(path),
+                Err(e) => warn!(
+                    "Failed to save error response body to {}: {e}",
+                    dir.display()
+                ),
+            }
+        }
+        if let Some(limit) = self.inner.error_body_limit.load().as_deref() {
+            error.truncate_body(*limit);
+        }
+        error
+    }
+
+    /// Parse a response like [`Self::parse_response`], keeping status, headers and the time elapsed since `started`.
+    pub(crate) async fn parse_response_with_meta<T: DeserializeOwned>(
+        &self,
+        response: reqwest::Response,
+        started: std::time::Instant,
+    ) -> Result<ResponseEnvelope<T>, PixivError> {
+        let status = response.status();
+        let headers = response.headers().clone();
+        let data = self.parse_response(response).await?;
+        Ok(ResponseEnvelope {
+            data,
+            status,
+            headers,
+            elapsed: started.elapsed(),
+        })
+    }
+
+    /// Send an API request and parse the response into `T`, retrying it per the request retry
+    /// policy.
+    pub(crate) async fn api_call<T: DeserializeOwned>(
+        &self,
+        method: HttpMethod,
+        url: &str,
+        headers: Option<HeaderMap>,
+        params: Option<KVPairs<'_>>,
+        data: Option<KVPairs<'_>>,
+        with_auth: bool,
+    ) -> Result<ResponseEnvelope<T>, PixivError> {
+        let policy = self.inner.request_retry.load_full();
+        match policy.as_deref() {
+            Some(policy) => {
+                policy
+                    .run_request(method, || {
+                        self.api_call_once(
+                            method,
+                            url,
+                            headers.clone(),
+                            params.clone(),
+                            data.clone(),
+                            with_auth,
+                        )
+                    })
+                    .await
+            }
+            None => {
+                self.api_call_once(method, url, headers, params, data, with_auth)
+                    .await
+            }
+        }
+    }
+
+    /// A single attempt of [`api_call`](Self::api_call).
+    async fn api_call_once<T: DeserializeOwned>(
+        &self,
+        method: HttpMethod,
+        url: &str,
+        headers: Option<HeaderMap>,
+        params: Option<KVPairs<'_>>,
+        data: Option<KVPairs<'_>>,
+        with_auth: bool,
+    ) -> Result<ResponseEnvelope<T>, PixivError> {
+        let started = std::time::Instant::now();
+        let r = self
+            .do_api_request(method, url, headers, params, data, with_auth)
+            .await?;
+        self.parse_response_with_meta(r, started).await
     }
 
     /// Low-level HTTP call (port of `requests_call`).
@@ -93,14 +739,26 @@ impl AppPixivAPI {
         params: Option<KVPairs<'_>>,
         data: Option<KVPairs<'_>>,
     ) -> Result<reqwest::Response, PixivError> {
+        #[cfg(feature = "http-trace")]
+        crate::http_trace::trace_request(
+            &format!("{method:?}"),
+            url,
+            headers.as_ref().unwrap_or(&HeaderMap::new()),
+            params.as_ref(),
+            data.as_ref(),
+        );
+
         let mut req = match method {
-            HttpMethod::GET => self.client.get(url),
-            HttpMethod::POST => self.client.post(url),
-            HttpMethod::DELETE => self.client.delete(url),
+            HttpMethod::GET => self.client().get(url),
+            HttpMethod::POST => self.client().post(url),
+            HttpMethod::DELETE => self.client().delete(url),
         };
         if let Some(h) = headers {
             req = req.headers(h);
         }
+        if let Some(timeout) = self.options.as_ref().and_then(|o| o.timeout) {
+            req = req.timeout(timeout);
+        }
         if let Some(p) = params {
             req = req.query(&p.content);
         }
@@ -108,12 +766,72 @@ impl AppPixivAPI {
             req = req.form(&d.content);
         }
         let res = req.send().await?;
+        if let Some(limit) = self.max_response_size() {
+            if res.content_length().is_some_and(|len| len > limit as u64) {
+                return Err(PixivError::ResponseTooLarge { limit });
+            }
+        }
         Ok(res)
     }
 
+    /// [`do_http_request`](Self::do_http_request) wrapped in the registered interceptors.
+    async fn intercepted_http_request(
+        &self,
+        method: HttpMethod,
+        url: &str,
+        headers: HeaderMap,
+        params: Option<KVPairs<'_>>,
+        data: Option<KVPairs<'_>>,
+    ) -> Result<reqwest::Response, PixivError> {
+        let interceptors = self.inner.interceptors.load_full();
+        if interceptors.is_empty() {
+            return self
+                .do_http_request(method, url, Some(headers), params, data)
+                .await;
+        }
+        let mut request = RequestParts {
+            method,
+            url: url.to_string(),
+            headers,
+            params,
+            data,
+        };
+        for interceptor in interceptors.iter() {
+            interceptor.before(&mut request);
+        }
+        let started = std::time::Instant::now();
+        let result = self
+            .do_http_request(
+                request.method,
+                &request.url,
+                Some(request.headers),
+                request.params,
+                request.data,
+            )
+            .await;
+        let meta = ResponseMeta {
+            method: request.method,
+            url: &request.url,
+            status: result.as_ref().ok().map(|res| res.status()),
+            headers: result.as_ref().ok().map(|res| res.headers()),
+            error: result.as_ref().err(),
+            elapsed: started.elapsed(),
+        };
+        for interceptor in interceptors.iter().rev() {
+            interceptor.after(&meta);
+        }
+        result
+    }
+
     /// Performs an API request with optional auth and app headers. Use for custom endpoints.
     ///
+    /// If an authenticated request is rejected for its access token (HTTP 401, or Pixiv's OAuth
+    /// error), a refresh-token client refreshes the token and sends the request once more.
+    /// Requests rejected with a token another task has already refreshed reuse its new token.
+    ///
     /// 执行 API 请求，可附加认证头与 App 头；在需要调用未由生成方法覆盖的接口时使用。
+    /// 若带认证的请求因 access token 被拒（HTTP 401 或 Pixiv 的 OAuth 错误），refresh token 模式的客户端会刷新
+    /// token 并重发一次请求；若 token 已被其他任务刷新，则直接使用新 token。
     pub async fn do_api_request(
         &self,
         method: HttpMethod,
@@ -123,22 +841,132 @@ impl AppPixivAPI {
         data: Option<KVPairs<'_>>,
         with_auth: bool,
     ) -> Result<reqwest::Response, PixivError> {
-        let mut headers = headers.unwrap_or_default();
-        if self.hosts != "https://app-api.pixiv.net" {
-            headers.insert(HOST, HV::from_static("app-api.pixiv.net"));
+        let _operation = self.begin_operation()?;
+        let headers = self.request_headers(headers);
+        if !with_auth || !matches!(*self.inner.token_manager, TokenManager::RefreshToken { .. }) {
+            return self
+                .cached_api_request(method, url, headers, params, data, with_auth)
+                .await;
         }
 
-        if !headers.contains_key("user-agent") {
-            headers.insert(HeaderName::from_static("app-os"), HV::from_static("ios"));
-            headers.insert(
-                HeaderName::from_static("app-os-version"),
-                HV::from_static("14.6"),
-            );
-            headers.insert(
-                USER_AGENT,
-                HV::from_static("PixivIOSApp/7.13.3 (iOS 14.6; iPhone13,2)"),
-            );
+        // The token the request is sent with, to tell a rejection of it from one of a token
+        // another task has already replaced.
+        let access_token = self.get_access_token().await?;
+        let response = self
+            .cached_api_request(
+                method,
+                url,
+                headers.clone(),
+                params.clone(),
+                data.clone(),
+                with_auth,
+            )
+            .await?;
+        if let Some(response) = self.unless_token_rejected(response).await? {
+            return Ok(response);
+        }
+        warn!("Access token rejected, refreshing and retrying");
+        self.inner
+            .token_manager
+            .refresh_rejected(&access_token)
+            .await?;
+        self.cached_api_request(method, url, headers, params, data, with_auth)
+            .await
+    }
+
+    /// `response`, or `None` if the server rejected the access token of the request: HTTP 401,
+    /// or a 400 carrying Pixiv's OAuth error.
+    async fn unless_token_rejected(
+        &self,
+        response: reqwest::Response,
+    ) -> Result<Option<reqwest::Response>, PixivError> {
+        match response.status() {
+            reqwest::StatusCode::UNAUTHORIZED => Ok(None),
+            status @ reqwest::StatusCode::BAD_REQUEST => {
+                let url = response.url().clone();
+                let headers = response.headers().clone();
+                let body = read_response_bytes(response, self.max_response_size()).await?;
+                if is_oauth_rejection(&body) {
+                    return Ok(None);
+                }
+                rebuild_response(status, url, headers, body).map(Some)
+            }
+            _ => Ok(Some(response)),
+        }
+    }
+
+    /// [`do_api_request`](Self::do_api_request) without the re-authentication, sending the
+    /// [`request_headers`](Self::request_headers) `headers` and consulting the response cache.
+    async fn cached_api_request(
+        &self,
+        method: HttpMethod,
+        url: &str,
+        headers: HeaderMap,
+        params: Option<KVPairs<'_>>,
+        data: Option<KVPairs<'_>>,
+        with_auth: bool,
+    ) -> Result<reqwest::Response, PixivError> {
+        #[cfg(feature = "cache")]
+        if let (HttpMethod::GET, Some(cache)) = (method, self.inner.response_cache.load_full()) {
+            let key = crate::cache::CacheKey::new(url, params.as_ref(), &headers, with_auth);
+            if let Some(response) = cache.get(&key) {
+                debug!("Serving {url} from the response cache");
+                return Ok(response);
+            }
+            let response = self
+                .send_api_request(method, url, headers, params, data, with_auth)
+                .await?;
+            if !response.status().is_success() {
+                return Ok(response);
+            }
+            return cache.store(key, response, self.max_response_size()).await;
+        }
+        let response = self
+            .send_api_request(method, url, headers, params, data, with_auth)
+            .await?;
+        #[cfg(feature = "cache")]
+        if let Some(cache) = self.inner.response_cache.load().as_deref() {
+            if response.status().is_success() {
+                // The change may show up in any cached response.
+                cache.clear();
+            }
+        }
+        Ok(response)
+    }
+
+    /// `headers` completed with the device headers, `Accept-Language` and the headers of the
+    /// request options; everything but the authorization.
+    fn request_headers(&self, headers: Option<HeaderMap>) -> HeaderMap {
+        let mut headers = headers.unwrap_or_default();
+
+        if !headers.contains_key(USER_AGENT) {
+            for (name, value) in self.inner.device_headers.load().iter() {
+                headers.insert(name, value.clone());
+            }
+        }
+        if let Some(language) = self.inner.accept_language.load().as_deref() {
+            if !headers.contains_key(ACCEPT_LANGUAGE) {
+                headers.insert(ACCEPT_LANGUAGE, language.clone());
+            }
+        }
+        if let Some(options) = &self.options {
+            for (name, value) in &options.headers {
+                headers.insert(name, value.clone());
+            }
         }
+        headers
+    }
+
+    /// [`cached_api_request`](Self::cached_api_request) without the response cache.
+    async fn send_api_request(
+        &self,
+        method: HttpMethod,
+        url: &str,
+        mut headers: HeaderMap,
+        params: Option<KVPairs<'_>>,
+        data: Option<KVPairs<'_>>,
+        with_auth: bool,
+    ) -> Result<reqwest::Response, PixivError> {
         if with_auth {
             let access_token = self.get_access_token().await?;
             headers.insert(
@@ -151,11 +979,87 @@ impl AppPixivAPI {
                 })?,
             );
         }
-        self.do_http_request(method, url, Some(headers), params, data)
-            .await
+        let _permit = self.acquire_api_permit().await;
+        let breaker = self.inner.circuit_breaker.load_full();
+        if let Some(breaker) = &breaker {
+            breaker.check()?;
+        }
+        let candidates = self.failover_urls(url);
+        let hosts = self.inner.hosts.load_full();
+        let mut attempt = 0;
+        let result = loop {
+            let (index, url) = &candidates[attempt];
+            let mut headers = headers.clone();
+            if index
+                .and_then(|index| hosts.get(index))
+                .is_some_and(|host| host != DEFAULT_API_HOSTS)
+            {
+                headers.insert(HOST, HV::from_static("app-api.pixiv.net"));
+            }
+            let result = self
+                .intercepted_http_request(method, url, headers, params.clone(), data.clone())
+                .await;
+            match &result {
+                Err(e @ (PixivError::Connect(_) | PixivError::Timeout(_) | PixivError::Tls(_)))
+                    if attempt + 1 < candidates.len() =>
+                {
+                    warn!("Request to {url} failed: {e}, failing over to the next host");
+                    attempt += 1;
+                }
+                Ok(_) => {
+                    if let Some(index) = *index {
+                        self.inner.current_host.store(index, Ordering::Relaxed);
+                    }
+                    break result;
+                }
+                Err(_) => break result,
+            }
+        };
+        if let Some(breaker) = &breaker {
+            breaker.record(&result);
+        }
+        result
     }
 }
 
+/// A semaphore with `limit` permits; `None` for no limit.
+fn semaphore(limit: Option<usize>) -> Result<Option<Arc<Semaphore>>, PixivError> {
+    match limit {
+        Some(0) => Err(PixivError::InvalidArgument {
+            message: "concurrency limit must be at least 1".to_string(),
+        }),
+        limit => Ok(limit.map(|limit| Arc::new(Semaphore::new(limit)))),
+    }
+}
+
+/// Whether a 400 response `body` is Pixiv's rejection of the access token, "Error occurred at
+/// the OAuth process...".
+fn is_oauth_rejection(body: &[u8]) -> bool {
+    PixivApiError::from_body(&String::from_utf8_lossy(body))
+        .and_then(|error| error.message)
+        .is_some_and(|message| message.contains("OAuth"))
+}
+
+/// Write a failed response body to a new file in `dir`, named after the time and status.
+#[cfg(feature = "error-capture")]
+async fn capture_body(
+    dir: &std::path::Path,
+    status: reqwest::StatusCode,
+    body: &str,
+) -> std::io::Result<std::path::PathBuf> {
+    static SEQUENCE: AtomicUsize = AtomicUsize::new(0);
+    tokio::fs::create_dir_all(dir).await?;
+    let name = format!(
+        "{}-{}-{}.body",
+        Utc::now().format("%Y%m%dT%H%M%S%.3fZ"),
+        status.as_u16(),
+        SEQUENCE.fetch_add(1, Ordering::Relaxed),
+    );
+    let path = dir.join(name);
+    tokio::fs::write(&path, body).await?;
+    Ok(path)
+}
+
 /// Structured API calls (generated by `pixiv3-rs-proc`).
 impl AppPixivAPI {
     api_endpoints!(
@@ -265,6 +1169,35 @@ impl AppPixivAPI {
             ]
         };
 
+        /// Post a comment on an illust, or a reply to `parent_comment_id`; returns the created
+        /// comment.
+        ///
+        /// 发表作品评论，或回复 `parent_comment_id`；返回新建的评论。
+        illust_comment_add -> CommentDetail {
+            POST "/v1/illust/comment/add",
+            data [
+                illust_id: u64,
+                comment: &str,
+                parent_comment_id: Option<u64>,
+            ]
+        };
+
+        /// Delete one of the authenticated user's comments.
+        ///
+        /// 删除当前用户的评论。
+        illust_comment_delete -> EmptyObject {
+            POST "/v1/illust/comment/delete",
+            data [ comment_id: u64 ]
+        };
+
+        /// Comment emoji, for rendering the `(slug)` placeholders in comments.
+        ///
+        /// 评论表情，用于渲染评论中的 `(slug)` 占位符。
+        emoji_list -> EmojiList {
+            GET "/v1/emoji",
+            params []
+        };
+
         /// Illust ranking. Port of `illust_ranking`.
         ///
         /// 作品排行。
@@ -337,6 +1270,23 @@ impl AppPixivAPI {
             ]
         };
 
+        /// Bookmark-count buckets of an illust search, for narrowing it by bookmark count as
+        /// the official app does for premium users.
+        ///
+        /// 插画搜索的收藏数区间，可像官方应用（高级会员）那样按收藏数筛选搜索结果。
+        search_bookmark_ranges_illust -> SearchBookmarkRanges {
+            GET "/v1/search/bookmark-ranges/illust",
+            params [
+                word: &str,
+                search_target: Option<SearchTarget> = SearchTarget::PartialMatchForTags,
+                duration: Option<&str>,
+                start_date: Option<&str>,
+                end_date: Option<&str>,
+                filter: Option<Filter> = Filter::ForIos,
+                search_ai_type: Option<u8>,
+            ]
+        };
+
         /// Illust bookmark detail. Port of `illust_bookmark_detail`.
         ///
         /// 作品收藏详情。
@@ -345,6 +1295,48 @@ impl AppPixivAPI {
             params [ illust_id: u64 ]
         };
 
+        /// Illust series published by a user.
+        ///
+        /// 用户发布的插画系列列表。
+        user_illust_series -> UserIllustSeries (paged illust_series_details: IllustSeriesDetail) {
+            GET "/v1/user/illust-series",
+            params [
+                user_id: u64,
+                filter: Option<Filter> = Filter::ForIos,
+                offset: Option<&str>,
+            ]
+        };
+
+        /// State of the authenticated account (mail authorization, restrictions, whether the
+        /// pixiv id can be changed...). Requires auth.
+        ///
+        /// 当前登录账号的状态（邮箱验证、限制、能否修改 pixiv id 等）；需要登录。
+        user_me_state -> UserMeState {
+            GET "/v1/user/me/state",
+            params []
+        };
+
+        /// Tags and users muted by the authenticated account. Requires auth.
+        ///
+        /// 当前账号屏蔽的标签与用户；需要登录。
+        mute_list -> MuteListResponse {
+            GET "/v1/mute/list",
+            params []
+        };
+
+        /// Mute and unmute users and tags in one request. Requires auth.
+        ///
+        /// 在一次请求中屏蔽或解除屏蔽用户与标签；需要登录。
+        mute_edit -> ParsedJson {
+            POST "/v1/mute/edit",
+            data [
+                add_user_ids @ "add_user_ids[]": Option<&[String]> => add_user_ids.unwrap_or(&[]),
+                delete_user_ids @ "delete_user_ids[]": Option<&[String]> => delete_user_ids.unwrap_or(&[]),
+                add_tags @ "add_tags[]": Option<&[String]> => add_tags.unwrap_or(&[]),
+                delete_tags @ "delete_tags[]": Option<&[String]> => delete_tags.unwrap_or(&[]),
+            ]
+        };
+
         /// User bookmark tags for illust. Port of `user_bookmark_tags_illust`.
         ///
         /// 用户收藏标签列表。
@@ -357,6 +1349,18 @@ impl AppPixivAPI {
             ]
         };
 
+        /// User bookmark tags for novel. Counterpart of `user_bookmark_tags_illust`.
+        ///
+        /// 用户小说收藏标签列表。
+        user_bookmark_tags_novel -> ParsedJson {
+            GET "/v1/user/bookmark-tags/novel",
+            params [
+                user_id: u64,
+                restrict: Option<Restrict> = Restrict::Public,
+                offset: Option<&str>,
+            ]
+        };
+
         /// User following list. Port of `user_following`.
         ///
         /// Following 用户列表。
@@ -372,7 +1376,7 @@ impl AppPixivAPI {
         /// User followers. Port of `user_follower`.
         ///
         /// Followers 用户列表。
-        user_follower -> ParsedJson {
+        user_follower -> UserFollower (paged user_previews: UserPreview) {
             GET "/v1/user/follower",
             params [
                 user_id: u64,
@@ -401,10 +1405,98 @@ impl AppPixivAPI {
             ]
         };
 
+        /// Block a user (access block); blocked users are listed by `user_list`. Requires auth.
+        ///
+        /// 屏蔽（拉黑）用户，已拉黑的用户见 `user_list`；需要登录。
+        user_block_add -> ParsedJson {
+            POST "/v1/user/access-block/add",
+            data [ user_id: u64 ]
+        };
+
+        /// Unblock a user blocked with `user_block_add`. Requires auth.
+        ///
+        /// 解除对用户的拉黑；需要登录。
+        user_block_delete -> ParsedJson {
+            POST "/v1/user/access-block/delete",
+            data [ user_id: u64 ]
+        };
+
+        /// Pixivision (spotlight) articles, newest first; see `showcase_article` for an
+        /// article's content.
+        ///
+        /// pixivision（特辑）文章列表（由新到旧），文章内容见 `showcase_article`。
+        spotlight_articles -> SpotlightArticles (paged spotlight_articles: SpotlightArticle) {
+            GET "/v1/spotlight/articles",
+            params [
+                category: Option<SpotlightCategory> = SpotlightCategory::All,
+                filter: Option<Filter> = Filter::ForIos,
+                offset: Option<&str>,
+            ]
+        };
+
+        /// Active pixiv Sketch lives; `list_type` is `popular` (the default) or `following`.
+        ///
+        /// 正在进行的 pixiv Sketch 直播；`list_type` 为 `popular`（默认）或 `following`。
+        live_list -> LiveList (paged lives: SketchLive) {
+            GET "/v1/live/list",
+            params [
+                list_type: Option<&str> = "popular",
+                offset: Option<&str>,
+            ]
+        };
+
+        /// New works from followed users, polled by the app for its notifications. Requires
+        /// auth.
+        ///
+        /// 关注用户的新作品（应用据此推送通知）；需要登录。
+        notification_new_from_following -> NewFromFollowing {
+            GET "/v1/notification/new-from-following",
+            params [ offset: Option<&str> ]
+        };
+
+        /// Notification settings of the authenticated account. Requires auth.
+        ///
+        /// 当前账号的通知设置；需要登录。
+        notification_settings -> NotificationSettings {
+            GET "/v1/notification/settings",
+            params []
+        };
+
+        /// Turn the notification kind `type_id` (see `notification_settings`) on or off.
+        /// Requires auth.
+        ///
+        /// 开启或关闭通知类型 `type_id`（见 `notification_settings`）；需要登录。
+        notification_settings_edit -> ParsedJson {
+            POST "/v1/notification/settings/edit",
+            data [
+                type_id: u64,
+                enabled: bool,
+            ]
+        };
+
+        /// Latest version of the official Android app; feed it to
+        /// [`DeviceProfile::with_app_version`](crate::device::DeviceProfile::with_app_version) to
+        /// keep the spoofed headers current.
+        ///
+        /// 官方 Android 应用的最新版本，可传给
+        /// [`DeviceProfile::with_app_version`](crate::device::DeviceProfile::with_app_version) 以更新伪装的请求头。
+        application_info_android -> ApplicationInfoResponse {
+            GET "/v1/application-info/android",
+            params []
+        };
+
+        /// Latest version of the official iOS app; see `application_info_android`.
+        ///
+        /// 官方 iOS 应用的最新版本，参见 `application_info_android`。
+        application_info_ios -> ApplicationInfoResponse {
+            GET "/v1/application-info/ios",
+            params []
+        };
+
         /// Ugoira metadata. Port of `ugoira_metadata`.
         ///
         /// 获取 ugoira 信息。
-        ugoira_metadata -> ParsedJson {
+        ugoira_metadata -> UgoiraMetadataResponse {
             GET "/v1/ugoira/metadata",
             params [ illust_id: u64 ]
         };
@@ -441,6 +1533,15 @@ impl AppPixivAPI {
             params [ novel_id: u64 ]
         };
 
+        /// Novel text. Port of the legacy `novel_text`; a fallback for when the page scraped by
+        /// `webview_novel` changes.
+        ///
+        /// 小说正文（旧版 `novel_text` 接口），可在 `webview_novel` 解析的页面变化时作为备用。
+        novel_text -> NovelText {
+            GET "/v1/novel/text",
+            params [ novel_id: u64 ]
+        };
+
         /// Novel comments. Port of `novel_comments`.
         ///
         /// 小说评论。
@@ -453,6 +1554,63 @@ impl AppPixivAPI {
             ]
         };
 
+        /// Replies to the novel comment `comment_id`.
+        ///
+        /// 小说评论的回复。
+        novel_comment_replies -> CommentReplies (paged comments: Comment) {
+            GET "/v2/novel/comment/replies",
+            params [ comment_id: u64 ]
+        };
+
+        /// Post a comment on a novel, or a reply to `parent_comment_id`; returns the created
+        /// comment. Counterpart of `illust_comment_add`.
+        ///
+        /// 发表小说评论，或回复 `parent_comment_id`；返回新建的评论。
+        novel_comment_add -> CommentDetail {
+            POST "/v1/novel/comment/add",
+            data [
+                novel_id: u64,
+                comment: &str,
+                parent_comment_id: Option<u64>,
+            ]
+        };
+
+        /// Delete one of the authenticated user's novel comments. Counterpart of
+        /// `illust_comment_delete`.
+        ///
+        /// 删除当前用户的小说评论。
+        novel_comment_delete -> EmptyObject {
+            POST "/v1/novel/comment/delete",
+            data [ comment_id: u64 ]
+        };
+
+        /// Novels the authenticated user has put a reading marker in.
+        ///
+        /// 当前用户加了阅读书签的小说。
+        novel_markers -> NovelMarkers (paged marked_novels: MarkedNovel) {
+            GET "/v2/novel/markers",
+            params [ offset: Option<&str> ]
+        };
+
+        /// Put a reading marker at `page` of a novel, replacing the previous one.
+        ///
+        /// 在小说的第 `page` 页添加阅读书签（替换原有书签）。
+        novel_marker_add -> ParsedJson {
+            POST "/v1/novel/marker/add",
+            data [
+                novel_id: u64,
+                page: u32,
+            ]
+        };
+
+        /// Remove the reading marker of a novel.
+        ///
+        /// 删除小说的阅读书签。
+        novel_marker_delete -> ParsedJson {
+            POST "/v1/novel/marker/delete",
+            data [ novel_id: u64 ]
+        };
+
         /// New novels. Port of `novel_new`.
         ///
         /// 小说新作。
@@ -548,6 +1706,67 @@ impl AppPixivAPI {
             ]
         };
 
+        /// Add novel bookmark. Counterpart of `illust_bookmark_add`.
+        ///
+        /// 新增小说收藏。
+        novel_bookmark_add -> ParsedJson {
+            POST "/v2/novel/bookmark/add",
+            data [
+                novel_id: u64,
+                restrict: Option<Restrict> = Restrict::Public,
+                tags @ "tags[]": Option<&[String]> => tags.map(|t| t.join(" ")),
+            ]
+        };
+
+        /// Delete novel bookmark. Counterpart of `illust_bookmark_delete`.
+        ///
+        /// 删除小说收藏。
+        novel_bookmark_delete -> EmptyObject {
+            POST "/v1/novel/bookmark/delete",
+            data [ novel_id: u64 ]
+        };
+
+        /// Manga series on the authenticated user's watchlist.
+        ///
+        /// 当前用户追更的漫画系列。
+        watchlist_manga -> WatchlistManga (paged series: WatchlistSeries) {
+            GET "/v1/watchlist/manga",
+            params [ offset: Option<&str> ]
+        };
+
+        /// Add a manga series to the watchlist.
+        ///
+        /// 追更漫画系列。
+        watchlist_manga_add -> ParsedJson {
+            POST "/v1/watchlist/manga/add",
+            data [ series_id: u64 ]
+        };
+
+        /// Remove a manga series from the watchlist.
+        ///
+        /// 取消追更漫画系列。
+        watchlist_manga_delete -> ParsedJson {
+            POST "/v1/watchlist/manga/delete",
+            data [ series_id: u64 ]
+        };
+
+        /// Recommended manga. Manga counterpart of `illust_recommended`, which mixes in a
+        /// different ranking when asked for `content_type=manga`. Requires auth.
+        ///
+        /// 漫画推荐；需要登录。
+        manga_recommended -> ParsedJson {
+            GET "/v1/manga/recommended",
+            params [
+                include_ranking_label: Option<bool> = true,
+                filter: Option<Filter> = Filter::ForIos,
+                max_bookmark_id_for_recommend: Option<&str>,
+                offset: Option<&str>,
+                include_ranking_illusts: Option<bool>,
+                include_privacy_policy: Option<&str>,
+                viewed @ "viewed[]": Option<&[String]> => viewed.unwrap_or(&[]),
+            ]
+        };
+
         /// Recommended novels. Port of `novel_recommended`. Python defaults: include_ranking_label=True, filter="for_ios".
         ///
         /// 小说推荐。
@@ -591,9 +1810,9 @@ impl AppPixivAPI {
         let include_ranking_label = include_ranking_label.unwrap_or(true);
         let filter = filter.unwrap_or(Filter::ForIos);
         let url = if with_auth {
-            format!("{}/v1/illust/recommended", self.hosts)
+            self.api_url("/v1/illust/recommended")
         } else {
-            format!("{}/v1/illust/recommended-nologin", self.hosts)
+            self.api_url("/v1/illust/recommended-nologin")
         };
         let mut params = kv_pairs!(
             "content_type" => content_type,
@@ -621,10 +1840,9 @@ impl AppPixivAPI {
             }
         }
         params.push("include_privacy_policy", include_privacy_policy);
-        let r = self
-            .do_api_request(HttpMethod::GET, &url, None, Some(params), None, with_auth)
-            .await?;
-        parse_response_into(r).await
+        self.api_call(HttpMethod::GET, &url, None, Some(params), None, with_auth)
+            .await
+            .map(|r| r.data)
     }
 
     /// Novel via webview, raw HTML. Port of `webview_novel(raw=True)`.
@@ -635,7 +1853,7 @@ impl AppPixivAPI {
         novel_id: u64,
         with_auth: bool,
     ) -> Result<String, PixivError> {
-        let url = format!("{}/webview/v2/novel", self.hosts);
+        let url = self.api_url("/webview/v2/novel");
         let params = kv_pairs!(
             "id" => novel_id,
             "viewer_version" => "20221031_ai",
@@ -643,7 +1861,7 @@ impl AppPixivAPI {
         let r = self
             .do_api_request(HttpMethod::GET, &url, None, Some(params), None, with_auth)
             .await?;
-        Ok(r.text().await?)
+        read_response_body(r, self.max_response_size()).await
     }
 
     /// Novel via webview. Port of `webview_novel(raw=False)`.
@@ -669,62 +1887,95 @@ impl AppPixivAPI {
 
     /// Showcase article detail (no login required). Port of `showcase_article`. Manual: custom headers / host.
     ///
-    /// 特辑详情（无需登录）。
+    /// Browse article ids, latest first or by category, with `spotlight_articles`.
+    ///
+    /// 特辑详情（无需登录）。可用 `spotlight_articles` 按时间或分类浏览文章 id。
     pub async fn showcase_article(&self, showcase_id: u64) -> Result<ParsedJson, PixivError> {
-        let url = "https://www.pixiv.net/ajax/showcase/article";
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            "User-Agent",
-            HV::from_static(
-                "Mozilla/5.0 (Windows NT 6.1; WOW64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/63.0.3239.132 Safari/537.36",
-            ),
-        );
-        headers.insert("Referer", HV::from_static("https://www.pixiv.net"));
+        let url = format!("{}/showcase/article", crate::webapi::WEB_AJAX_BASE);
+        let headers = self.web_headers();
         let params = kv_pairs!(
             "article_id" => showcase_id,
         );
         let r = self
             .do_api_request(
                 HttpMethod::GET,
-                url,
+                &url,
                 Some(headers),
                 Some(params),
                 None,
                 false,
             )
             .await?;
-        parse_response_into(r).await
+        self.parse_response(r).await
     }
 
-    /// Download URL to file. Port of `download`.
+    /// Download URL to file. Port of `download`. `replace` is a `bool` as in pixivpy3 or a
+    /// [`CollisionPolicy`](crate::download::CollisionPolicy); returns `false` if the file was
+    /// skipped. An empty `referer` stands for the one [`referer_for`](crate::download::referer_for)
+    /// derives from the URL. See [`Self::download_file`] for progress reporting.
     ///
-    /// 将 URL 下载到文件。
+    /// 将 URL 下载到文件。`replace` 可为 `bool`（同 pixivpy3）或 [`CollisionPolicy`](crate::download::CollisionPolicy)；
+    /// 跳过下载时返回 `false`。`referer` 为空时使用 [`referer_for`](crate::download::referer_for) 根据 URL 推断的值。
+    #[cfg(feature = "fs")]
     pub async fn download(
         &self,
         url: &str,
         path: &std::path::Path,
         name: Option<&str>,
-        replace: bool,
+        replace: impl Into<crate::download::CollisionPolicy>,
         referer: &str,
     ) -> Result<bool, PixivError> {
-        let filename = name.unwrap_or_else(|| url.split('/').next_back().unwrap_or("download"));
-        let filepath = path.join(filename);
-        if !replace && tokio::fs::try_exists(&filepath).await.unwrap_or(false) {
-            return Ok(false);
-        }
-        let mut res = self
-            .client
-            .get(url)
-            .header("Referer", referer)
-            .send()
+        let referer = (!referer.is_empty()).then_some(referer);
+        let file = self
+            .download_file(url, path, name, replace, referer, None)
             .await?;
+        Ok(!file.skipped)
+    }
+}
+
+/// Diagnostics (NOT port of `AppPixivAPI` methods).
+impl AppPixivAPI {
+    /// Check whether the API host is reachable, how long a cheap no-auth request takes, and whether authentication works.
+    ///
+    /// Never fails: problems are reported in the returned [`PingReport`].
+    ///
+    /// 检查 API 主机是否可达、无认证请求的延迟，以及认证是否有效；不会返回错误，问题记录在 [`PingReport`] 中。
+    pub async fn ping(&self) -> PingReport {
+        let url = self.api_url("/v1/application-info/ios");
+        let start = std::time::Instant::now();
+        let probe = self
+            .do_api_request(HttpMethod::GET, &url, None, None, None, false)
+            .await;
+        let latency = start.elapsed();
+        let (reachable, status, error) = match probe {
+            Ok(r) => (true, Some(r.status()), None),
+            Err(e) => (false, None, Some(e.to_string())),
+        };
+        debug!("ping: reachable={reachable}, status={status:?}, latency={latency:?}");
 
-        let mut file = tokio::fs::File::create(&filepath).await?;
-        while let Some(chunk) = res.chunk().await? {
-            file.write_all(&chunk).await?;
+        let auth = if matches!(*self.inner.token_manager, TokenManager::NoAuth) {
+            AuthStatus::NotConfigured
+        } else {
+            let url = self.api_url("/v1/user/me/state");
+            match self
+                .do_api_request(HttpMethod::GET, &url, None, None, None, true)
+                .await
+            {
+                Ok(r) if r.status().is_success() => AuthStatus::Valid,
+                Ok(r) => {
+                    AuthStatus::Failed(format!("authenticated request returned {}", r.status()))
+                }
+                Err(e) => AuthStatus::Failed(e.to_string()),
+            }
+        };
+
+        PingReport {
+            reachable,
+            status,
+            latency,
+            error,
+            auth,
         }
-        file.flush().await?;
-        Ok(true)
     }
 }
 
@@ -738,9 +1989,477 @@ impl AppPixivAPI {
         next_url: &str,
         with_auth: bool,
     ) -> Result<T, PixivError> {
-        let r = self
-            .do_api_request(HttpMethod::GET, next_url, None, None, None, with_auth)
-            .await?;
-        parse_response_into(r).await
+        self.visit_next_url_with_meta(next_url, with_auth)
+            .await
+            .map(|r| r.data)
+    }
+
+    /// Fetch the page following `prev`; `Ok(None)` if `prev` is the last page.
+    ///
+    /// 获取 `prev` 的下一页；`prev` 为最后一页时返回 `Ok(None)`。
+    pub async fn next_page<T: PagedResponse + DeserializeOwned>(
+        &self,
+        prev: &T,
+        with_auth: bool,
+    ) -> Result<Option<T>, PixivError> {
+        match prev.next_url() {
+            Some(url) => self.visit_next_url(url, with_auth).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Same as [`Self::visit_next_url`], but also returns the HTTP status, response headers and elapsed time.
+    ///
+    /// 同 [`Self::visit_next_url`]，同时返回 HTTP 状态码、响应头与耗时。
+    pub async fn visit_next_url_with_meta<T: DeserializeOwned>(
+        &self,
+        next_url: &str,
+        with_auth: bool,
+    ) -> Result<ResponseEnvelope<T>, PixivError> {
+        self.api_call(HttpMethod::GET, next_url, None, None, None, with_auth)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clones_share_settings() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<AppPixivAPI>();
+
+        let api = AppPixivAPI::new_no_auth();
+        let clone = api.clone();
+        clone.set_api_proxy("https://proxy.example.com");
+        clone.set_max_response_size(Some(1024));
+        assert_eq!(api.api_url("/v1"), "https://proxy.example.com/v1");
+        assert_eq!(api.max_response_size(), Some(1024));
+    }
+
+    #[test]
+    fn failover_urls_start_at_current_host() {
+        let api = AppPixivAPI::new_no_auth();
+        api.set_api_hosts(["https://a.example.com", "https://b.example.com"])
+            .unwrap();
+        api.inner.current_host.store(1, Ordering::Relaxed);
+        assert_eq!(api.api_url("/v1/x"), "https://b.example.com/v1/x");
+        assert_eq!(
+            api.failover_urls("https://b.example.com/v1/x?a=1"),
+            vec![
+                (Some(1), "https://b.example.com/v1/x?a=1".to_string()),
+                (Some(0), "https://a.example.com/v1/x?a=1".to_string()),
+            ]
+        );
+        assert_eq!(
+            api.failover_urls("https://other.example.com/y"),
+            vec![(None, "https://other.example.com/y".to_string())]
+        );
+        assert!(api.set_api_hosts(Vec::<String>::new()).is_err());
+    }
+
+    #[tokio::test]
+    async fn fails_over_on_tls_errors() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let garbage = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let broken = format!("https://{}", garbage.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut stream, _) = garbage.accept().await.unwrap();
+            let _ = stream.write_all(b"HTTP/1.1 400 Bad Request\r\n\r\n").await;
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let working = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request).await;
+            let _ = stream
+                .write_all(b"HTTP/1.1 204 No Content\r\nconnection: close\r\n\r\n")
+                .await;
+        });
+
+        let api = AppPixivAPI::new_no_auth();
+        api.set_api_hosts([broken, working.clone()]).unwrap();
+        let url = api.api_url("/v1/x");
+        api.do_api_request(HttpMethod::GET, &url, None, None, None, false)
+            .await
+            .unwrap();
+        assert_eq!(api.api_url("/v1/x"), format!("{working}/v1/x"));
+    }
+
+    #[tokio::test]
+    async fn keeps_host_of_urls_off_api_hosts() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = vec![0u8; 4096];
+            let n = stream.read(&mut request).await.unwrap();
+            let _ = stream
+                .write_all(b"HTTP/1.1 204 No Content\r\nconnection: close\r\n\r\n")
+                .await;
+            String::from_utf8_lossy(&request[..n]).to_lowercase()
+        });
+        let api = AppPixivAPI::new_no_auth();
+        api.set_api_proxy("https://proxy.example.com");
+        api.do_api_request(
+            HttpMethod::GET,
+            &format!("http://{addr}/ajax/x"),
+            None,
+            None,
+            None,
+            false,
+        )
+        .await
+        .unwrap();
+        let request = server.await.unwrap();
+        assert!(request.contains(&format!("host: {addr}")), "{request}");
+        assert!(!request.contains("app-api.pixiv.net"), "{request}");
+    }
+
+    #[test]
+    fn recognizes_oauth_rejections() {
+        assert!(is_oauth_rejection(
+            br#"{"error": {"message": "Error occurred at the OAuth process. Please check your Access Token to fix this. Error Message: invalid_grant"}}"#
+        ));
+        assert!(!is_oauth_rejection(
+            br#"{"error": {"message": "Invalid illust_id"}}"#
+        ));
+        assert!(!is_oauth_rejection(b"<html>"));
+    }
+
+    #[tokio::test]
+    async fn do_api_request_retries_rejected_token() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/v1/x", listener.local_addr().unwrap());
+        let api = AppPixivAPI::new_from_token_state(TokenState {
+            refresh_token: "refresh".into(),
+            access_token: Some("old".into()),
+            expires_at: Some(Utc::now() + Duration::from_secs(600)),
+        });
+        let server_api = api.clone();
+        let server = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            let rejection = r#"{"error": {"message": "Error occurred at the OAuth process."}}"#;
+            for body in [rejection, "{}"] {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = [0u8; 1024];
+                let n = stream.read(&mut request).await.unwrap();
+                requests.push(String::from_utf8_lossy(&request[..n]).into_owned());
+                // Another task refreshes the token meanwhile, so the retry needs no OAuth server.
+                if let TokenManager::RefreshToken {
+                    access_token_and_expires_at,
+                    ..
+                } = &*server_api.inner.token_manager
+                {
+                    access_token_and_expires_at.store(Some(Arc::new((
+                        "new".to_string(),
+                        Utc::now() + Duration::from_secs(600),
+                    ))));
+                }
+                let status = if body == rejection {
+                    "400 Bad Request"
+                } else {
+                    "200 OK"
+                };
+                let response = format!(
+                    "HTTP/1.1 {status}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+            requests
+        });
+
+        let response = api
+            .do_api_request(HttpMethod::GET, &url, None, None, None, true)
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+        let requests = server.await.unwrap();
+        assert!(requests[0].contains("Bearer old"), "{}", requests[0]);
+        assert!(requests[1].contains("Bearer new"), "{}", requests[1]);
+    }
+
+    #[cfg(feature = "stream")]
+    #[tokio::test]
+    async fn page_interval_paces_pages() {
+        let api = AppPixivAPI::new_no_auth();
+        let start = std::time::Instant::now();
+        api.pace_page().await;
+        assert!(start.elapsed() < Duration::from_millis(20));
+        api.set_page_interval(Some(Duration::from_millis(30)));
+        let start = std::time::Instant::now();
+        api.pace_page().await;
+        assert!(start.elapsed() >= Duration::from_millis(30));
+    }
+
+    #[tokio::test]
+    async fn retry_policy_retries_server_errors() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/v1/x", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            for response in [
+                "HTTP/1.1 503 Service Unavailable\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                "HTTP/1.1 200 OK\r\ncontent-length: 12\r\nconnection: close\r\n\r\n{\"ok\": true}",
+            ] {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = [0u8; 1024];
+                let _ = stream.read(&mut request).await;
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+
+        let api = AppPixivAPI::new_no_auth();
+        api.set_retry_policy(Some(RetryPolicy {
+            base_delay: Duration::ZERO,
+            ..RetryPolicy::default()
+        }));
+        let value = api
+            .api_call::<serde_json::Value>(HttpMethod::GET, &url, None, None, None, false)
+            .await
+            .unwrap()
+            .data;
+        assert_eq!(value["ok"], true);
+    }
+
+    #[tokio::test]
+    async fn interceptors_wrap_requests() {
+        use std::sync::Mutex;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        struct Tag(&'static str, Arc<Mutex<Vec<String>>>);
+        impl Interceptor for Tag {
+            fn before(&self, request: &mut RequestParts<'_>) {
+                request.headers.insert("x-tag", HV::from_static(self.0));
+                self.1.lock().unwrap().push(format!("before {}", self.0));
+            }
+            fn after(&self, response: &ResponseMeta<'_>) {
+                let status = response.status.map(|s| s.as_u16());
+                self.1
+                    .lock()
+                    .unwrap()
+                    .push(format!("after {} {status:?}", self.0));
+            }
+        }
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/v1/x", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let n = stream.read(&mut request).await.unwrap();
+            let _ = stream
+                .write_all(b"HTTP/1.1 204 No Content\r\nconnection: close\r\n\r\n")
+                .await;
+            String::from_utf8_lossy(&request[..n]).to_lowercase()
+        });
+
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let api = AppPixivAPI::new_no_auth();
+        api.add_interceptor(Tag("a", log.clone()));
+        api.add_interceptor(Tag("b", log.clone()));
+        api.do_api_request(HttpMethod::GET, &url, None, None, None, false)
+            .await
+            .unwrap();
+        assert!(server.await.unwrap().contains("x-tag: b"));
+        assert_eq!(
+            *log.lock().unwrap(),
+            [
+                "before a",
+                "before b",
+                "after b Some(204)",
+                "after a Some(204)"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn downloads_share_api_limit_unless_set() {
+        let api = AppPixivAPI::new_no_auth();
+        assert!(api.acquire_download_permit().await.is_none());
+        assert!(api.set_concurrency_limit(Some(0)).is_err());
+
+        api.set_concurrency_limit(Some(1)).unwrap();
+        let permit = api.acquire_download_permit().await.unwrap();
+        let waiting = tokio::time::timeout(Duration::from_millis(10), api.acquire_api_permit());
+        assert!(waiting.await.is_err());
+        drop(permit);
+
+        api.set_download_concurrency_limit(Some(2)).unwrap();
+        let _api_permit = api.acquire_api_permit().await.unwrap();
+        let _download_permits = (
+            api.acquire_download_permit().await.unwrap(),
+            api.acquire_download_permit().await.unwrap(),
+        );
+    }
+
+    #[cfg(feature = "cache")]
+    #[tokio::test]
+    async fn response_cache_serves_repeated_gets() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/v1/x", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request).await;
+            let _ = stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 12\r\nconnection: close\r\n\r\n{\"ok\": true}")
+                .await;
+        });
+
+        let api = AppPixivAPI::new_no_auth();
+        api.set_response_cache(Some(crate::cache::CacheConfig::default()));
+        for _ in 0..2 {
+            let value = api
+                .api_call::<serde_json::Value>(HttpMethod::GET, &url, None, None, None, false)
+                .await
+                .unwrap()
+                .data;
+            assert_eq!(value["ok"], true);
+        }
+        api.clear_response_cache();
+        assert!(
+            api.do_api_request(HttpMethod::GET, &url, None, None, None, false)
+                .await
+                .is_err()
+        );
+    }
+
+    #[cfg(feature = "cache")]
+    #[tokio::test]
+    async fn response_cache_separates_languages() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/v1/x", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            for body in ["{\"n\": 1}", "{\"n\": 2}"] {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = [0u8; 1024];
+                let _ = stream.read(&mut request).await;
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+
+        let api = AppPixivAPI::new_no_auth();
+        api.set_response_cache(Some(crate::cache::CacheConfig::default()));
+        api.set_accept_language("ja").unwrap();
+        let call = |api: AppPixivAPI| {
+            let url = url.clone();
+            async move {
+                api.api_call::<serde_json::Value>(HttpMethod::GET, &url, None, None, None, false)
+                    .await
+                    .unwrap()
+                    .data["n"]
+                    .clone()
+            }
+        };
+        assert_eq!(call(api.clone()).await, 1);
+        assert_eq!(call(api.clone()).await, 1);
+        let english =
+            api.with_options(RequestOptions::new().accept_language(HV::from_static("en-us")));
+        assert_eq!(call(english).await, 2);
+    }
+
+    #[cfg(feature = "cache")]
+    #[tokio::test]
+    async fn response_cache_dropped_after_post() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/v1/x", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            for body in ["{\"n\": 1}", "{}", "{\"n\": 2}"] {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = [0u8; 1024];
+                let _ = stream.read(&mut request).await;
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+
+        let api = AppPixivAPI::new_no_auth();
+        api.set_response_cache(Some(crate::cache::CacheConfig::default()));
+        let get = || async {
+            api.api_call::<serde_json::Value>(HttpMethod::GET, &url, None, None, None, false)
+                .await
+                .unwrap()
+                .data["n"]
+                .clone()
+        };
+        assert_eq!(get().await, 1);
+        assert_eq!(get().await, 1);
+        api.do_api_request(HttpMethod::POST, &url, None, None, None, false)
+            .await
+            .unwrap();
+        assert_eq!(get().await, 2);
+    }
+
+    #[cfg(feature = "cache")]
+    #[tokio::test]
+    async fn response_cache_limits_chunked_bodies() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/v1/x", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request).await;
+            let _ = stream
+                .write_all(b"HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\nconnection: close\r\n\r\n10\r\n0123456789abcdef\r\n0\r\n\r\n")
+                .await;
+        });
+
+        let api = AppPixivAPI::new_no_auth();
+        api.set_response_cache(Some(crate::cache::CacheConfig::default()));
+        api.set_max_response_size(Some(8));
+        let result = api
+            .do_api_request(HttpMethod::GET, &url, None, None, None, false)
+            .await;
+        assert!(matches!(
+            result,
+            Err(PixivError::ResponseTooLarge { limit: 8 })
+        ));
+    }
+
+    #[test]
+    fn with_options_does_not_affect_original() {
+        let api = AppPixivAPI::new_no_auth();
+        let slow = api.with_options(RequestOptions::new().timeout(Duration::from_secs(180)));
+        assert!(api.options.is_none());
+        assert_eq!(
+            slow.options.as_ref().and_then(|o| o.timeout),
+            Some(Duration::from_secs(180))
+        );
+        slow.set_api_proxy("https://proxy.example.com");
+        assert_eq!(api.api_url(""), "https://proxy.example.com");
+    }
+
+    #[cfg(feature = "error-capture")]
+    #[tokio::test]
+    async fn capture_body_writes_distinct_files() {
+        let dir =
+            std::env::temp_dir().join(format!("pixiv3-rs-test-{}-capture", std::process::id()));
+        let first = capture_body(&dir, reqwest::StatusCode::BAD_GATEWAY, "<html>")
+            .await
+            .unwrap();
+        let second = capture_body(&dir, reqwest::StatusCode::BAD_GATEWAY, "<html>")
+            .await
+            .unwrap();
+        assert_ne!(first, second);
+        assert!(first.to_string_lossy().contains("-502-"));
+        assert_eq!(std::fs::read_to_string(&first).unwrap(), "<html>");
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 }
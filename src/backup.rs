@@ -0,0 +1,368 @@
+//! Full offline backup of a user's works: metadata as JSON plus all media.
+//!
+//! 用户作品的完整离线备份：元数据（JSON）及全部媒体文件。
+
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::AppPixivAPI;
+use crate::PixivError;
+use crate::batch::join_bounded;
+use crate::download::{CollisionPolicy, DownloadedFile};
+use crate::models::{IllustrationInfo, ImageSize, NovelInfo};
+use crate::paging::collect_all;
+use crate::params::{IllustType, Restrict};
+use crate::{debug, warn};
+
+/// What [`AppPixivAPI::backup_user`] saves and how.
+///
+/// [`AppPixivAPI::backup_user`] 的备份内容与方式。
+#[derive(Debug, Clone)]
+pub struct BackupConfig {
+    /// Back up the user's illusts (and ugoira).
+    pub illusts: bool,
+    /// Back up the user's manga.
+    pub manga: bool,
+    /// Back up the user's novels with their embedded images.
+    pub novels: bool,
+    /// Back up the illusts the user bookmarked publicly.
+    pub bookmarks: bool,
+    /// Image size downloaded for every page.
+    pub image_size: ImageSize,
+    /// What to do with media files that already exist, e.g. from an earlier backup.
+    pub collision: CollisionPolicy,
+    /// Maximum number of works downloaded at the same time.
+    pub concurrency: usize,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            illusts: true,
+            manga: true,
+            novels: true,
+            bookmarks: false,
+            image_size: ImageSize::Original,
+            collision: CollisionPolicy::Skip,
+            concurrency: 4,
+        }
+    }
+}
+
+impl BackupConfig {
+    /// Create the default config: illusts, manga and novels in original size, no bookmarks,
+    /// existing files kept, four works at a time.
+    ///
+    /// 创建默认配置：备份插画、漫画与小说（原图），不含收藏；保留已存在的文件，同时下载四个作品。
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set whether illusts are backed up.
+    pub fn illusts(mut self, enabled: bool) -> Self {
+        self.illusts = enabled;
+        self
+    }
+
+    /// Set whether manga are backed up.
+    pub fn manga(mut self, enabled: bool) -> Self {
+        self.manga = enabled;
+        self
+    }
+
+    /// Set whether novels are backed up.
+    pub fn novels(mut self, enabled: bool) -> Self {
+        self.novels = enabled;
+        self
+    }
+
+    /// Set whether public bookmarks are backed up.
+    pub fn bookmarks(mut self, enabled: bool) -> Self {
+        self.bookmarks = enabled;
+        self
+    }
+
+    /// Set the image size downloaded for every page.
+    pub fn image_size(mut self, size: ImageSize) -> Self {
+        self.image_size = size;
+        self
+    }
+
+    /// Set the policy for media files that already exist.
+    pub fn collision(mut self, collision: CollisionPolicy) -> Self {
+        self.collision = collision;
+        self
+    }
+
+    /// Set the maximum number of works downloaded at the same time.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+}
+
+/// A work whose media could not be saved.
+///
+/// 媒体文件保存失败的作品。
+#[derive(Debug)]
+pub struct BackupFailure {
+    /// Illust or novel id.
+    pub id: u64,
+    /// Why it failed.
+    pub error: PixivError,
+}
+
+/// Outcome of [`AppPixivAPI::backup_user`].
+///
+/// [`AppPixivAPI::backup_user`] 的结果。
+#[derive(Debug, Default)]
+pub struct BackupReport {
+    /// Illusts listed.
+    pub illusts: usize,
+    /// Manga listed.
+    pub manga: usize,
+    /// Novels listed.
+    pub novels: usize,
+    /// Bookmarked illusts listed.
+    pub bookmarks: usize,
+    /// Works skipped because they are deleted, private or otherwise not visible.
+    pub limited: usize,
+    /// Media files downloaded by this run.
+    pub downloaded: usize,
+    /// Media files that already existed and were kept.
+    pub skipped: usize,
+    /// Works whose media could not be saved; the rest of the backup went on.
+    pub failures: Vec<BackupFailure>,
+}
+
+impl BackupReport {
+    fn record_files(&mut self, id: u64, result: Result<Vec<DownloadedFile>, PixivError>) {
+        match result {
+            Ok(files) => {
+                for file in files {
+                    if file.skipped {
+                        self.skipped += 1;
+                    } else {
+                        self.downloaded += 1;
+                    }
+                }
+            }
+            Err(error) => {
+                warn!("backup: work {id} failed: {error}");
+                self.failures.push(BackupFailure { id, error });
+            }
+        }
+    }
+}
+
+impl AppPixivAPI {
+    /// Back up user `user_id` into `dest`, as selected by `config`.
+    ///
+    /// Writes `user.json` with the user's profile, then for every enabled category a metadata
+    /// file (`illusts.json`, `manga.json`, `novels.json`, `bookmarks.json`) listing all works and
+    /// a directory of the same name holding their media: every page of illusts and manga
+    /// (ugoira also as frame zip), and novels via [`Self::download_novel`]. Metadata files are
+    /// rewritten on every run; media files follow `config.collision`, so an interrupted backup
+    /// can be resumed.
+    ///
+    /// Failing to list works aborts the backup. A work whose media cannot be saved is recorded in
+    /// [`BackupReport::failures`] and the backup goes on.
+    ///
+    /// 按 `config` 将用户 `user_id` 备份到 `dest`：写入用户资料 `user.json`，并对每个启用的类别写入列出全部作品的
+    /// 元数据文件（`illusts.json`、`manga.json`、`novels.json`、`bookmarks.json`）及存放其媒体文件的同名目录。
+    /// 元数据文件每次重写，媒体文件按 `config.collision` 处理，因此中断的备份可以继续。
+    /// 列举作品失败会中止备份；单个作品保存失败会记录在 [`BackupReport::failures`] 中并继续。
+    pub async fn backup_user(
+        &self,
+        user_id: u64,
+        dest: &Path,
+        config: &BackupConfig,
+        with_auth: bool,
+    ) -> Result<BackupReport, PixivError> {
+        tokio::fs::create_dir_all(dest).await?;
+        let user = self.user_detail(user_id, None, with_auth).await?;
+        write_json(&dest.join("user.json"), &user).await?;
+
+        let mut report = BackupReport::default();
+        if config.illusts {
+            let illusts = collect_all(
+                self.user_illusts_iter(user_id, Some(IllustType::Illust), None, None, with_auth),
+                None,
+            )
+            .await?;
+            report.illusts = illusts.len();
+            self.backup_illusts(
+                &illusts,
+                &dest.join("illusts"),
+                config,
+                with_auth,
+                &mut report,
+            )
+            .await?;
+            write_json(&dest.join("illusts.json"), &illusts).await?;
+        }
+        if config.manga {
+            let manga = collect_all(
+                self.user_illusts_iter(user_id, Some(IllustType::Manga), None, None, with_auth),
+                None,
+            )
+            .await?;
+            report.manga = manga.len();
+            self.backup_illusts(&manga, &dest.join("manga"), config, with_auth, &mut report)
+                .await?;
+            write_json(&dest.join("manga.json"), &manga).await?;
+        }
+        if config.novels {
+            let novels =
+                collect_all(self.user_novels_iter(user_id, None, None, with_auth), None).await?;
+            report.novels = novels.len();
+            self.backup_novels(
+                &novels,
+                &dest.join("novels"),
+                config,
+                with_auth,
+                &mut report,
+            )
+            .await?;
+            write_json(&dest.join("novels.json"), &novels).await?;
+        }
+        if config.bookmarks {
+            let bookmarks = collect_all(
+                self.user_bookmarks_illust_iter(
+                    user_id,
+                    Some(Restrict::Public),
+                    None,
+                    None,
+                    None,
+                    with_auth,
+                ),
+                None,
+            )
+            .await?;
+            report.bookmarks = bookmarks.len();
+            self.backup_illusts(
+                &bookmarks,
+                &dest.join("bookmarks"),
+                config,
+                with_auth,
+                &mut report,
+            )
+            .await?;
+            write_json(&dest.join("bookmarks.json"), &bookmarks).await?;
+        }
+        debug!(
+            "backup of user {user_id}: {} downloaded, {} kept, {} failed",
+            report.downloaded,
+            report.skipped,
+            report.failures.len()
+        );
+        Ok(report)
+    }
+
+    async fn backup_illusts(
+        &self,
+        illusts: &[IllustrationInfo],
+        dir: &Path,
+        config: &BackupConfig,
+        with_auth: bool,
+        report: &mut BackupReport,
+    ) -> Result<(), PixivError> {
+        tokio::fs::create_dir_all(dir).await?;
+        let visible: Vec<_> = illusts.iter().filter(|i| !i.is_limited()).collect();
+        report.limited += illusts.len() - visible.len();
+        let tasks = visible.into_iter().map(|illust| async move {
+            let files = self
+                .download_work_media(illust, dir, config.image_size, config.collision, with_auth)
+                .await;
+            (illust.id, files)
+        });
+        for (id, result) in join_bounded(tasks, config.concurrency).await {
+            report.record_files(id, result);
+        }
+        Ok(())
+    }
+
+    async fn backup_novels(
+        &self,
+        novels: &[NovelInfo],
+        dir: &Path,
+        config: &BackupConfig,
+        with_auth: bool,
+        report: &mut BackupReport,
+    ) -> Result<(), PixivError> {
+        tokio::fs::create_dir_all(dir).await?;
+        let visible: Vec<_> = novels.iter().filter(|n| !n.is_limited()).collect();
+        report.limited += novels.len() - visible.len();
+        let tasks = visible.into_iter().map(|novel| async move {
+            let result = self
+                .download_novel(novel.id, dir, config.collision, with_auth)
+                .await
+                .map(|saved| {
+                    saved
+                        .images
+                        .into_iter()
+                        .map(|image| DownloadedFile {
+                            path: image.path,
+                            bytes_written: 0,
+                            skipped: image.skipped,
+                        })
+                        .collect()
+                });
+            (novel.id, result)
+        });
+        for (id, result) in join_bounded(tasks, config.concurrency).await {
+            report.record_files(id, result);
+        }
+        Ok(())
+    }
+}
+
+/// Write `value` as pretty-printed JSON to `path`.
+async fn write_json<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<(), PixivError> {
+    let json = serde_json::to_vec_pretty(value).map_err(|error| PixivError::Serde {
+        error,
+        body: String::new(),
+        context: None,
+    })?;
+    tokio::fs::write(path, json).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn report_counts_files_and_failures() {
+        let file = |skipped| DownloadedFile {
+            path: PathBuf::from("1_p0.png"),
+            bytes_written: 0,
+            skipped,
+        };
+        let mut report = BackupReport::default();
+        report.record_files(1, Ok(vec![file(false), file(true), file(false)]));
+        report.record_files(
+            2,
+            Err(PixivError::InvalidArgument {
+                message: "gone".to_string(),
+            }),
+        );
+        assert_eq!((report.downloaded, report.skipped), (2, 1));
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].id, 2);
+    }
+
+    #[test]
+    fn config_builder() {
+        let config = BackupConfig::new()
+            .manga(false)
+            .bookmarks(true)
+            .collision(CollisionPolicy::Overwrite)
+            .concurrency(2);
+        assert!(config.illusts && !config.manga && config.novels && config.bookmarks);
+        assert_eq!(config.collision, CollisionPolicy::Overwrite);
+        assert_eq!(config.concurrency, 2);
+    }
+}
@@ -0,0 +1,108 @@
+//! Batched API calls with bounded concurrency.
+//!
+//! 限制并发数的批量 API 调用。
+
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::Poll;
+
+use crate::AppPixivAPI;
+use crate::PixivError;
+use crate::models::UserInfoDetailed;
+
+/// Run `tasks` with at most `limit` (at least 1) of them in flight, returning the outputs in
+/// completion order.
+pub(crate) async fn join_bounded<I, F>(tasks: I, limit: usize) -> Vec<F::Output>
+where
+    I: IntoIterator<Item = F>,
+    F: Future,
+{
+    let limit = limit.max(1);
+    let mut pending = tasks.into_iter().fuse();
+    let mut running: Vec<Pin<Box<F>>> = Vec::new();
+    let mut outputs = Vec::new();
+    std::future::poll_fn(|cx| {
+        loop {
+            while running.len() < limit {
+                match pending.next() {
+                    Some(task) => running.push(Box::pin(task)),
+                    None => break,
+                }
+            }
+            if running.is_empty() {
+                return Poll::Ready(());
+            }
+            let before = running.len();
+            let mut i = 0;
+            while i < running.len() {
+                if let Poll::Ready(output) = running[i].as_mut().poll(cx) {
+                    outputs.push(output);
+                    running.swap_remove(i);
+                } else {
+                    i += 1;
+                }
+            }
+            if running.len() == before {
+                return Poll::Pending;
+            }
+        }
+    })
+    .await;
+    outputs
+}
+
+impl AppPixivAPI {
+    /// Fetch the details of many users, with at most `concurrency` requests in flight.
+    ///
+    /// Duplicate ids are fetched once. A failure only affects the entry of its own id.
+    ///
+    /// 批量获取用户详情，同时进行的请求不超过 `concurrency` 个；重复 id 只请求一次，单个失败只影响对应条目。
+    pub async fn user_details_many(
+        &self,
+        ids: &[u64],
+        concurrency: usize,
+        with_auth: bool,
+    ) -> HashMap<u64, Result<UserInfoDetailed, PixivError>> {
+        let mut seen = HashSet::new();
+        let tasks = ids
+            .iter()
+            .copied()
+            .filter(|id| seen.insert(*id))
+            .map(|id| async move { (id, self.user_detail(id, None, with_auth).await) });
+        join_bounded(tasks, concurrency).await.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn join_bounded_limits_concurrency() {
+        let in_flight = Cell::new(0);
+        let peak = Cell::new(0);
+        let tasks = (0..10u64).map(|i| {
+            let (in_flight, peak) = (&in_flight, &peak);
+            async move {
+                in_flight.set(in_flight.get() + 1);
+                peak.set(peak.get().max(in_flight.get()));
+                tokio::time::sleep(Duration::from_millis(1 + i % 3)).await;
+                in_flight.set(in_flight.get() - 1);
+                i
+            }
+        });
+        let mut outputs = join_bounded(tasks, 3).await;
+        outputs.sort();
+        assert_eq!(outputs, (0..10).collect::<Vec<_>>());
+        assert_eq!(peak.get(), 3);
+    }
+
+    #[tokio::test]
+    async fn join_bounded_zero_limit_runs_sequentially() {
+        let outputs = join_bounded((0..3).map(|i| async move { i }), 0).await;
+        assert_eq!(outputs, vec![0, 1, 2]);
+    }
+}
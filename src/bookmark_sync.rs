@@ -0,0 +1,154 @@
+//! Bookmark snapshots, their differences between runs, and applying a difference to an account.
+//!
+//! 收藏快照、两次快照之间的差异，以及将差异应用到账号。
+
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::AppPixivAPI;
+use crate::PixivError;
+use crate::debug;
+use crate::paging::collect_all;
+use crate::params::Restrict;
+
+/// The ids of a user's bookmarked illusts and novels at one point in time.
+///
+/// Serializable, so a snapshot can be stored and compared with the next one.
+///
+/// 某一时刻用户收藏的插画与小说 id；可序列化，便于保存后与下一次快照比较。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BookmarkSnapshot {
+    /// Bookmarked illust (and manga) ids.
+    pub illusts: BTreeSet<u64>,
+    /// Bookmarked novel ids.
+    pub novels: BTreeSet<u64>,
+}
+
+impl BookmarkSnapshot {
+    /// What changed from `self` to the `newer` snapshot.
+    ///
+    /// 从 `self` 到较新快照 `newer` 的变化。
+    pub fn diff(&self, newer: &BookmarkSnapshot) -> BookmarkDiff {
+        BookmarkDiff {
+            added_illusts: newer.illusts.difference(&self.illusts).copied().collect(),
+            removed_illusts: self.illusts.difference(&newer.illusts).copied().collect(),
+            added_novels: newer.novels.difference(&self.novels).copied().collect(),
+            removed_novels: self.novels.difference(&newer.novels).copied().collect(),
+        }
+    }
+}
+
+/// Bookmarks added and removed between two snapshots, in ascending id order.
+///
+/// 两次快照之间新增与移除的收藏（按 id 升序）。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BookmarkDiff {
+    /// Illusts bookmarked since the older snapshot.
+    pub added_illusts: Vec<u64>,
+    /// Illusts no longer bookmarked.
+    pub removed_illusts: Vec<u64>,
+    /// Novels bookmarked since the older snapshot.
+    pub added_novels: Vec<u64>,
+    /// Novels no longer bookmarked.
+    pub removed_novels: Vec<u64>,
+}
+
+impl BookmarkDiff {
+    /// Whether nothing changed.
+    ///
+    /// 是否没有任何变化。
+    pub fn is_empty(&self) -> bool {
+        self.added_illusts.is_empty()
+            && self.removed_illusts.is_empty()
+            && self.added_novels.is_empty()
+            && self.removed_novels.is_empty()
+    }
+}
+
+impl AppPixivAPI {
+    /// Snapshot the `restrict` bookmarks of user `user_id` by walking `user_bookmarks_illust` and
+    /// `user_bookmarks_novel` to the end. Only the authenticated user can see private bookmarks.
+    ///
+    /// 遍历 `user_bookmarks_illust` 与 `user_bookmarks_novel`，生成用户 `user_id` 的 `restrict` 收藏快照；
+    /// 仅登录用户本人可见非公开收藏。
+    pub async fn bookmark_snapshot(
+        &self,
+        user_id: u64,
+        restrict: Restrict,
+        with_auth: bool,
+    ) -> Result<BookmarkSnapshot, PixivError> {
+        let illusts = collect_all(
+            self.user_bookmarks_illust_iter(user_id, Some(restrict), None, None, None, with_auth),
+            None,
+        )
+        .await?;
+        let novels = collect_all(
+            self.user_bookmarks_novel_iter(user_id, Some(restrict), None, None, None, with_auth),
+            None,
+        )
+        .await?;
+        Ok(BookmarkSnapshot {
+            illusts: illusts.into_iter().map(|illust| illust.id).collect(),
+            novels: novels.into_iter().map(|novel| novel.id).collect(),
+        })
+    }
+
+    /// Apply `diff` to the authenticated account: bookmark the added works with `restrict` and
+    /// remove the removed ones. Requests are sent one after another and the first failure stops
+    /// the sync; applying the same diff again is harmless.
+    ///
+    /// 将 `diff` 应用到当前登录账号：以 `restrict` 收藏新增作品并取消已移除的收藏。请求依次发送，首次失败即停止；
+    /// 重复应用同一差异不会产生副作用。
+    pub async fn apply_bookmark_diff(
+        &self,
+        diff: &BookmarkDiff,
+        restrict: Restrict,
+    ) -> Result<(), PixivError> {
+        debug!(
+            "applying bookmark diff: +{}/-{} illusts, +{}/-{} novels",
+            diff.added_illusts.len(),
+            diff.removed_illusts.len(),
+            diff.added_novels.len(),
+            diff.removed_novels.len()
+        );
+        for &illust_id in &diff.added_illusts {
+            self.illust_bookmark_add(illust_id, Some(restrict), None, true)
+                .await?;
+        }
+        for &illust_id in &diff.removed_illusts {
+            self.illust_bookmark_delete(illust_id, true).await?;
+        }
+        for &novel_id in &diff.added_novels {
+            self.novel_bookmark_add(novel_id, Some(restrict), None, true)
+                .await?;
+        }
+        for &novel_id in &diff.removed_novels {
+            self.novel_bookmark_delete(novel_id, true).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_between_snapshots() {
+        let older = BookmarkSnapshot {
+            illusts: [1, 2, 3].into(),
+            novels: [10].into(),
+        };
+        let newer = BookmarkSnapshot {
+            illusts: [2, 3, 4, 5].into(),
+            novels: [].into(),
+        };
+        let diff = older.diff(&newer);
+        assert_eq!(diff.added_illusts, [4, 5]);
+        assert_eq!(diff.removed_illusts, [1]);
+        assert!(diff.added_novels.is_empty());
+        assert_eq!(diff.removed_novels, [10]);
+        assert!(older.diff(&older).is_empty());
+    }
+}
@@ -0,0 +1,516 @@
+//! Builder for [`AppPixivAPI`] with configurable HTTP client, headers and hosts.
+//!
+//! [`AppPixivAPI`] 的构建器，可配置 HTTP 客户端、请求头与主机。
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, USER_AGENT};
+use reqwest::{Certificate, Proxy, Url, tls};
+
+use crate::AppPixivAPI;
+use crate::PixivError;
+use crate::circuit_breaker::CircuitBreakerConfig;
+use crate::device::DevicePreset;
+use crate::error::DEFAULT_ERROR_BODY_LIMIT;
+use crate::interceptor::Interceptor;
+use crate::retry::RetryPolicy;
+use crate::token_manager::{OAuthCredentials, TokenManager, TokenState};
+
+/// Default API host.
+///
+/// 默认 API 主机。
+pub const DEFAULT_API_HOSTS: &str = "https://app-api.pixiv.net";
+/// Default request timeout.
+///
+/// 默认请求超时时间。
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Proxy for all API requests and downloads: `http://`, `https://`, `socks5://` or `socks5h://`
+/// (the latter resolves host names on the proxy), with optional credentials.
+///
+/// 所有 API 请求与下载使用的代理：`http://`、`https://`、`socks5://` 或 `socks5h://`（由代理解析域名），可附带认证信息。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyConfig {
+    /// Proxy URL, e.g. `socks5h://127.0.0.1:1080`.
+    pub url: String,
+    /// Username and password for the proxy.
+    pub credentials: Option<(String, String)>,
+}
+
+impl ProxyConfig {
+    /// Create a proxy config for `url` without credentials.
+    ///
+    /// 以 `url` 创建不带认证信息的代理配置。
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            credentials: None,
+        }
+    }
+
+    /// Authenticate to the proxy with `username` and `password`.
+    ///
+    /// 使用 `username` 与 `password` 向代理认证。
+    pub fn credentials(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.credentials = Some((username.into(), password.into()));
+        self
+    }
+
+    /// Convert to a `reqwest` proxy; fails on an invalid URL or unsupported scheme.
+    fn to_proxy(&self) -> Result<Proxy, PixivError> {
+        let invalid = |message: String| PixivError::InvalidArgument { message };
+        let mut url = Url::parse(&self.url)
+            .map_err(|e| invalid(format!("invalid proxy URL {:?}: {e}", self.url)))?;
+        if !matches!(url.scheme(), "http" | "https" | "socks5" | "socks5h") {
+            return Err(invalid(format!(
+                "unsupported proxy scheme {:?}, expected http, https, socks5 or socks5h",
+                url.scheme()
+            )));
+        }
+        // Credentials in the URL work for both HTTP (Proxy-Authorization) and SOCKS5 proxies.
+        if let Some((username, password)) = &self.credentials {
+            url.set_username(username)
+                .and_then(|()| url.set_password(Some(password)))
+                .map_err(|()| {
+                    invalid(format!(
+                        "cannot set credentials on proxy URL {:?}",
+                        self.url
+                    ))
+                })?;
+        }
+        Ok(Proxy::all(url)?)
+    }
+}
+
+/// Configures and builds an [`AppPixivAPI`]. Obtain one with [`AppPixivAPI::builder`].
+///
+/// 配置并构建 [`AppPixivAPI`]，通过 [`AppPixivAPI::builder`] 获取。
+pub struct AppPixivAPIBuilder {
+    token_manager: TokenManager,
+    oauth_credentials: Option<OAuthCredentials>,
+    hosts: Vec<String>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    device_preset: DevicePreset,
+    user_agent: Option<String>,
+    headers: Vec<(HeaderName, HeaderValue)>,
+    proxy: Option<ProxyConfig>,
+    root_certificates: Vec<Certificate>,
+    min_tls_version: Option<tls::Version>,
+    accept_invalid_certs: bool,
+    max_response_size: Option<usize>,
+    error_body_limit: Option<usize>,
+    #[cfg(feature = "error-capture")]
+    error_capture_dir: Option<std::path::PathBuf>,
+    circuit_breaker: Option<CircuitBreakerConfig>,
+    stream_retry: Option<RetryPolicy>,
+    page_interval: Option<Duration>,
+    retry_policy: Option<RetryPolicy>,
+    accept_language: Option<String>,
+    web_session: Option<String>,
+    interceptors: Vec<Arc<dyn Interceptor>>,
+    concurrency_limit: Option<usize>,
+    #[cfg(feature = "cache")]
+    response_cache: Option<crate::cache::CacheConfig>,
+    download_concurrency_limit: Option<usize>,
+    #[cfg(feature = "middleware")]
+    middleware_client: Option<reqwest_middleware::ClientWithMiddleware>,
+}
+
+impl Default for AppPixivAPIBuilder {
+    fn default() -> Self {
+        Self {
+            token_manager: TokenManager::new_no_auth(),
+            oauth_credentials: None,
+            hosts: vec![DEFAULT_API_HOSTS.to_string()],
+            timeout: Some(DEFAULT_TIMEOUT),
+            connect_timeout: None,
+            device_preset: DevicePreset::default(),
+            user_agent: None,
+            headers: Vec::new(),
+            proxy: None,
+            root_certificates: Vec::new(),
+            min_tls_version: None,
+            accept_invalid_certs: false,
+            max_response_size: None,
+            error_body_limit: Some(DEFAULT_ERROR_BODY_LIMIT),
+            #[cfg(feature = "error-capture")]
+            error_capture_dir: None,
+            circuit_breaker: None,
+            stream_retry: None,
+            page_interval: None,
+            retry_policy: None,
+            accept_language: None,
+            web_session: None,
+            interceptors: Vec::new(),
+            concurrency_limit: None,
+            #[cfg(feature = "cache")]
+            response_cache: None,
+            download_concurrency_limit: None,
+            #[cfg(feature = "middleware")]
+            middleware_client: None,
+        }
+    }
+}
+
+impl AppPixivAPIBuilder {
+    /// Create a builder for an unauthenticated client with the default settings.
+    ///
+    /// 创建默认配置、无认证的客户端构建器。
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Authenticate with the given token manager.
+    ///
+    /// 使用指定的 token 管理器进行认证。
+    pub fn token_manager(mut self, token_manager: TokenManager) -> Self {
+        self.token_manager = token_manager;
+        self
+    }
+
+    /// Authenticate with a fixed access token.
+    pub fn access_token(self, access_token: String) -> Self {
+        self.token_manager(TokenManager::new_from_access_token(access_token))
+    }
+
+    /// Authenticate with a refresh token.
+    pub fn refresh_token(self, refresh_token: String) -> Self {
+        self.token_manager(TokenManager::new_from_refresh_token(refresh_token))
+    }
+
+    /// Authenticate with a saved refresh-token state.
+    pub fn token_state(self, state: TokenState) -> Self {
+        self.token_manager(TokenManager::load_state(state))
+    }
+
+    /// Refresh tokens with a custom OAuth client identity; applies to whichever refresh-token manager is configured.
+    ///
+    /// 使用自定义 OAuth 客户端身份刷新 token；对所配置的 refresh token 管理器生效。
+    pub fn oauth_credentials(mut self, credentials: OAuthCredentials) -> Self {
+        self.oauth_credentials = Some(credentials);
+        self
+    }
+
+    /// Set the API hosts (default [`DEFAULT_API_HOSTS`]), e.g. a reverse proxy.
+    ///
+    /// 设置 API 主机（默认 [`DEFAULT_API_HOSTS`]），例如反向代理。
+    pub fn hosts(mut self, hosts: impl Into<String>) -> Self {
+        self.hosts = vec![hosts.into()];
+        self
+    }
+
+    /// Add a fallback API host, tried when the others fail (see [`AppPixivAPI::set_api_hosts`]).
+    ///
+    /// 添加备用 API 主机，其他主机失败时使用（参见 [`AppPixivAPI::set_api_hosts`]）。
+    pub fn fallback_host(mut self, host: impl Into<String>) -> Self {
+        self.hosts.push(host.into());
+        self
+    }
+
+    /// Set the total request timeout (default [`DEFAULT_TIMEOUT`]); `None` disables it.
+    ///
+    /// 设置请求总超时（默认 [`DEFAULT_TIMEOUT`]）；`None` 表示不限制。
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the connect timeout (default none).
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Select the spoofed device/app headers (default [`DevicePreset::IPhone`]).
+    pub fn device_preset(mut self, preset: DevicePreset) -> Self {
+        self.device_preset = preset;
+        self
+    }
+
+    /// Override the `User-Agent` of the device preset.
+    ///
+    /// 覆盖设备预设中的 `User-Agent`。
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Add a header sent with every API request, alongside the device headers.
+    ///
+    /// 添加随每个 API 请求发送的请求头（与设备请求头一同发送）。
+    pub fn default_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.push((name, value));
+        self
+    }
+
+    /// Route all API requests and downloads through a proxy.
+    ///
+    /// 所有 API 请求与下载经由代理发送。
+    pub fn proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Trust an additional root certificate.
+    pub fn add_root_certificate(mut self, certificate: Certificate) -> Self {
+        self.root_certificates.push(certificate);
+        self
+    }
+
+    /// Set the minimum accepted TLS version.
+    pub fn min_tls_version(mut self, version: tls::Version) -> Self {
+        self.min_tls_version = Some(version);
+        self
+    }
+
+    /// Accept invalid TLS certificates. Only use this for debugging proxies.
+    ///
+    /// 接受无效的 TLS 证书，仅用于调试代理。
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.accept_invalid_certs = accept;
+        self
+    }
+
+    /// See [`AppPixivAPI::set_max_response_size`].
+    pub fn max_response_size(mut self, limit: usize) -> Self {
+        self.max_response_size = Some(limit);
+        self
+    }
+
+    /// See [`AppPixivAPI::set_error_body_limit`].
+    pub fn error_body_limit(mut self, limit: Option<usize>) -> Self {
+        self.error_body_limit = limit;
+        self
+    }
+
+    /// See [`AppPixivAPI::set_error_capture_dir`].
+    #[cfg(feature = "error-capture")]
+    pub fn error_capture_dir(mut self, dir: impl Into<std::path::PathBuf>) -> Self {
+        self.error_capture_dir = Some(dir.into());
+        self
+    }
+
+    /// See [`AppPixivAPI::set_circuit_breaker`].
+    pub fn circuit_breaker(mut self, config: CircuitBreakerConfig) -> Self {
+        self.circuit_breaker = Some(config);
+        self
+    }
+
+    /// See [`AppPixivAPI::set_stream_retry`].
+    pub fn stream_retry(mut self, policy: RetryPolicy) -> Self {
+        self.stream_retry = Some(policy);
+        self
+    }
+
+    /// See [`AppPixivAPI::set_page_interval`].
+    pub fn page_interval(mut self, interval: Duration) -> Self {
+        self.page_interval = Some(interval);
+        self
+    }
+
+    /// See [`AppPixivAPI::set_retry_policy`].
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    /// See [`AppPixivAPI::set_response_cache`].
+    #[cfg(feature = "cache")]
+    pub fn response_cache(mut self, config: crate::cache::CacheConfig) -> Self {
+        self.response_cache = Some(config);
+        self
+    }
+
+    /// See [`AppPixivAPI::set_concurrency_limit`].
+    pub fn concurrency_limit(mut self, limit: usize) -> Self {
+        self.concurrency_limit = Some(limit);
+        self
+    }
+
+    /// See [`AppPixivAPI::set_download_concurrency_limit`].
+    pub fn download_concurrency_limit(mut self, limit: usize) -> Self {
+        self.download_concurrency_limit = Some(limit);
+        self
+    }
+
+    /// Register an interceptor; may be called several times. See [`AppPixivAPI::add_interceptor`].
+    pub fn interceptor(mut self, interceptor: impl Interceptor + 'static) -> Self {
+        self.interceptors.push(Arc::new(interceptor));
+        self
+    }
+
+    /// Send API requests and downloads through `client`, so its middlewares (retry, tracing,
+    /// caching, ...) wrap every call.
+    ///
+    /// The timeout, proxy and TLS settings of this builder do not apply to `client`; configure
+    /// them on the `reqwest::Client` it wraps. OAuth token requests keep using a plain client
+    /// built from this builder's settings.
+    ///
+    /// 通过 `client` 发送 API 请求与下载，使其中间件包裹每次调用。本构建器的超时、代理与 TLS 设置不作用于
+    /// `client`，请在其包装的 `reqwest::Client` 上配置；OAuth token 请求仍使用按本构建器设置创建的普通客户端。
+    #[cfg(feature = "middleware")]
+    pub fn middleware_client(mut self, client: reqwest_middleware::ClientWithMiddleware) -> Self {
+        self.middleware_client = Some(client);
+        self
+    }
+
+    /// See [`AppPixivAPI::set_web_session`].
+    pub fn web_session(mut self, phpsessid: impl Into<String>) -> Self {
+        self.web_session = Some(phpsessid.into());
+        self
+    }
+
+    /// See [`AppPixivAPI::set_accept_language`].
+    pub fn accept_language(mut self, language: impl Into<String>) -> Self {
+        self.accept_language = Some(language.into());
+        self
+    }
+
+    fn build_headers(&self) -> Result<HeaderMap, PixivError> {
+        let mut headers = self.device_preset.profile().to_headers()?;
+        if let Some(user_agent) = &self.user_agent {
+            let value =
+                HeaderValue::from_str(user_agent).map_err(|e| PixivError::InvalidArgument {
+                    message: format!("invalid user-agent {user_agent:?}: {e}"),
+                })?;
+            headers.insert(USER_AGENT, value);
+        }
+        for (name, value) in &self.headers {
+            headers.insert(name, value.clone());
+        }
+        Ok(headers)
+    }
+
+    /// Build the client. Fails if a header value is invalid or the HTTP client cannot be created.
+    ///
+    /// 构建客户端；请求头取值非法或 HTTP 客户端创建失败时返回错误。
+    pub fn build(self) -> Result<AppPixivAPI, PixivError> {
+        let device_headers = self.build_headers()?;
+
+        let mut client = reqwest::Client::builder();
+        if let Some(timeout) = self.timeout {
+            client = client.timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            client = client.connect_timeout(timeout);
+        }
+        if let Some(proxy) = &self.proxy {
+            client = client.proxy(proxy.to_proxy()?);
+        }
+        for certificate in self.root_certificates {
+            client = client.add_root_certificate(certificate);
+        }
+        if let Some(version) = self.min_tls_version {
+            client = client.min_tls_version(version);
+        }
+        if self.accept_invalid_certs {
+            client = client.danger_accept_invalid_certs(true);
+        }
+
+        let client = client.build()?;
+        let token_manager = match self.oauth_credentials {
+            Some(credentials) => self.token_manager.with_credentials(credentials),
+            None => self.token_manager,
+        }
+        .or_http_client(client.clone());
+        #[cfg(feature = "middleware")]
+        let client = self.middleware_client.unwrap_or_else(|| client.into());
+        let api = AppPixivAPI::from_parts(client, token_manager, self.hosts, device_headers);
+        api.set_max_response_size(self.max_response_size);
+        api.set_error_body_limit(self.error_body_limit);
+        #[cfg(feature = "error-capture")]
+        api.set_error_capture_dir(self.error_capture_dir);
+        api.set_circuit_breaker(self.circuit_breaker);
+        api.set_stream_retry(self.stream_retry);
+        api.set_page_interval(self.page_interval);
+        api.set_retry_policy(self.retry_policy);
+        if let Some(language) = &self.accept_language {
+            api.set_accept_language(language)?;
+        }
+        api.set_web_session(self.web_session.as_deref())?;
+        #[cfg(feature = "cache")]
+        api.set_response_cache(self.response_cache);
+        api.set_concurrency_limit(self.concurrency_limit)?;
+        api.set_download_concurrency_limit(self.download_concurrency_limit)?;
+        for interceptor in self.interceptors {
+            api.push_interceptor(interceptor);
+        }
+        Ok(api)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_agent_overrides_device_preset() {
+        let headers = AppPixivAPIBuilder::new()
+            .device_preset(DevicePreset::Android)
+            .user_agent("custom/1.0")
+            .default_header(
+                HeaderName::from_static("x-test"),
+                HeaderValue::from_static("1"),
+            )
+            .build_headers()
+            .unwrap();
+        assert_eq!(headers[USER_AGENT], "custom/1.0");
+        assert_eq!(headers["app-os"], "android");
+        assert_eq!(headers["x-test"], "1");
+    }
+
+    #[test]
+    fn invalid_user_agent_is_rejected() {
+        let result = AppPixivAPIBuilder::new().user_agent("bad\nvalue").build();
+        assert!(matches!(result, Err(PixivError::InvalidArgument { .. })));
+    }
+
+    #[test]
+    fn invalid_accept_language_is_rejected() {
+        let result = AppPixivAPIBuilder::new().accept_language("en\n").build();
+        assert!(matches!(result, Err(PixivError::InvalidArgument { .. })));
+    }
+
+    #[test]
+    fn proxy_config_schemes() {
+        assert!(
+            ProxyConfig::new("socks5h://127.0.0.1:1080")
+                .to_proxy()
+                .is_ok()
+        );
+        assert!(
+            ProxyConfig::new("http://proxy.example.com:8080")
+                .credentials("user", "p@ss")
+                .to_proxy()
+                .is_ok()
+        );
+        assert!(matches!(
+            ProxyConfig::new("ftp://proxy.example.com").to_proxy(),
+            Err(PixivError::InvalidArgument { .. })
+        ));
+        assert!(matches!(
+            ProxyConfig::new("not a url").to_proxy(),
+            Err(PixivError::InvalidArgument { .. })
+        ));
+    }
+
+    #[test]
+    fn builds_with_socks_proxy() {
+        let result = AppPixivAPIBuilder::new()
+            .proxy(ProxyConfig::new("socks5://127.0.0.1:1080").credentials("u", "p"))
+            .build();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn builds_with_custom_hosts() {
+        let api = AppPixivAPIBuilder::new()
+            .hosts("https://proxy.example.com")
+            .timeout(None)
+            .build()
+            .unwrap();
+        assert_eq!(api.api_url("/x"), "https://proxy.example.com/x");
+    }
+}
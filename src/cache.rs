@@ -0,0 +1,167 @@
+//! Optional in-memory cache of successful GET responses.
+//!
+//! 成功 GET 响应的可选内存缓存。
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use kv_pairs::KVPairs;
+use reqwest::header::HeaderMap;
+use reqwest::{StatusCode, Url};
+
+use crate::PixivError;
+use crate::models::{read_response_bytes, rebuild_response};
+
+/// Response cache settings.
+///
+/// A cached response may be up to `ttl` old: successful POST and DELETE requests of the same
+/// client drop the cache, but changes made elsewhere are not noticed.
+///
+/// 响应缓存配置。缓存的响应最多可能过期 `ttl`：同一客户端成功的 POST 与 DELETE 请求会清空缓存，但其他途径造成的变化不会被察觉。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheConfig {
+    /// How long a cached response is served before it is fetched again.
+    pub ttl: Duration,
+    /// Maximum number of cached responses; the least recently used ones are evicted first.
+    pub max_entries: u64,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(300),
+            max_entries: 1000,
+        }
+    }
+}
+
+/// What identifies a cached request: its URL, query parameters, headers (language, cookies of the
+/// web session, ...) and whether it was authenticated.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct CacheKey {
+    url: String,
+    params: Vec<(String, String)>,
+    headers: Vec<(String, Vec<u8>)>,
+    with_auth: bool,
+}
+
+impl CacheKey {
+    /// `headers` are the ones sent, without the authorization.
+    pub(crate) fn new(
+        url: &str,
+        params: Option<&KVPairs<'_>>,
+        headers: &HeaderMap,
+        with_auth: bool,
+    ) -> Self {
+        let mut headers: Vec<_> = headers
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.as_bytes().to_vec()))
+            .collect();
+        headers.sort();
+        Self {
+            url: url.to_string(),
+            params: params
+                .map(|p| {
+                    p.content
+                        .iter()
+                        .map(|(k, v)| (k.to_string(), v.to_string()))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            headers,
+            with_auth,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CachedResponse {
+    status: StatusCode,
+    url: Url,
+    headers: HeaderMap,
+    body: Vec<u8>,
+}
+
+impl CachedResponse {
+    fn to_response(&self) -> Result<reqwest::Response, PixivError> {
+        rebuild_response(
+            self.status,
+            self.url.clone(),
+            self.headers.clone(),
+            self.body.clone(),
+        )
+    }
+}
+
+/// TTL cache of successful responses, consulted by `do_api_request` for GET requests.
+pub(crate) struct ResponseCache {
+    cache: moka::sync::Cache<CacheKey, Arc<CachedResponse>>,
+}
+
+impl ResponseCache {
+    pub(crate) fn new(config: CacheConfig) -> Self {
+        Self {
+            cache: moka::sync::Cache::builder()
+                .max_capacity(config.max_entries)
+                .time_to_live(config.ttl)
+                .build(),
+        }
+    }
+
+    /// A fresh copy of the cached response for `key`, if any.
+    pub(crate) fn get(&self, key: &CacheKey) -> Option<reqwest::Response> {
+        self.cache.get(key)?.to_response().ok()
+    }
+
+    /// Read the body of `response`, failing with `PixivError::ResponseTooLarge` past `max_size`
+    /// bytes, and cache it under `key`; returns an equivalent response.
+    pub(crate) async fn store(
+        &self,
+        key: CacheKey,
+        response: reqwest::Response,
+        max_size: Option<usize>,
+    ) -> Result<reqwest::Response, PixivError> {
+        let status = response.status();
+        let url = response.url().clone();
+        let headers = response.headers().clone();
+        let cached = Arc::new(CachedResponse {
+            status,
+            url,
+            headers,
+            body: read_response_bytes(response, max_size).await?,
+        });
+        let copy = cached.to_response()?;
+        self.cache.insert(key, cached);
+        Ok(copy)
+    }
+
+    pub(crate) fn clear(&self) {
+        self.cache.invalidate_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn keys_differ_by_headers() {
+        use reqwest::header::{ACCEPT_LANGUAGE, COOKIE};
+        let url = "https://www.pixiv.net/ajax/follow_latest/illust";
+        let with = |name, value| {
+            let mut headers = HeaderMap::new();
+            headers.insert(name, HeaderValue::from_static(value));
+            headers
+        };
+        let anonymous = CacheKey::new(url, None, &HeaderMap::new(), false);
+        let alice = CacheKey::new(url, None, &with(COOKIE, "PHPSESSID=alice"), false);
+        let bob = CacheKey::new(url, None, &with(COOKIE, "PHPSESSID=bob"), false);
+        assert_ne!(anonymous, alice);
+        assert_ne!(alice, bob);
+        assert_ne!(
+            CacheKey::new(url, None, &with(ACCEPT_LANGUAGE, "en-us"), false),
+            CacheKey::new(url, None, &with(ACCEPT_LANGUAGE, "zh-cn"), false)
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! Circuit breaker around the API host: fail fast after repeated failures.
+//!
+//! API 主机熔断器：连续失败后在冷却期内快速失败。
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use reqwest::StatusCode;
+
+use crate::PixivError;
+use crate::warn;
+
+/// Circuit breaker settings.
+///
+/// 熔断器配置。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failures (network errors, 429 or 5xx responses) that open the circuit.
+    pub failure_threshold: u32,
+    /// How long the circuit stays open before a single trial request is let through.
+    pub cooldown: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            cooldown: Duration::from_secs(60),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+/// Shared breaker state consulted by `do_api_request`.
+#[derive(Debug)]
+pub(crate) struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    pub(crate) fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(BreakerState::default()),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BreakerState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Returns `PixivError::CircuitOpen` while the circuit is open.
+    ///
+    /// Once the cooldown has passed the circuit is half-open: a single trial request goes
+    /// through while the others keep failing. Its success closes the circuit, its failure opens
+    /// it again; if it reports neither, the next trial follows another cooldown later.
+    pub(crate) fn check(&self) -> Result<(), PixivError> {
+        let mut state = self.lock();
+        if let Some(open_until) = state.open_until {
+            let now = Instant::now();
+            if open_until > now {
+                return Err(PixivError::CircuitOpen {
+                    retry_in: open_until - now,
+                });
+            }
+            // Keep the circuit open for everyone but the trial until it reports back.
+            state.open_until = Some(now + self.config.cooldown);
+            state.consecutive_failures = self.config.failure_threshold.saturating_sub(1);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn record_success(&self) {
+        let mut state = self.lock();
+        state.consecutive_failures = 0;
+        state.open_until = None;
+    }
+
+    pub(crate) fn record_failure(&self) {
+        let mut state = self.lock();
+        state.consecutive_failures += 1;
+        if state.consecutive_failures >= self.config.failure_threshold {
+            warn!(
+                "circuit opened after {} consecutive failures, cooling down for {:?}",
+                state.consecutive_failures, self.config.cooldown
+            );
+            state.open_until = Some(Instant::now() + self.config.cooldown);
+        }
+    }
+
+    /// Record the outcome of a request.
+    pub(crate) fn record(&self, result: &Result<reqwest::Response, PixivError>) {
+        match result {
+            Ok(res) if is_failure_status(res.status()) => self.record_failure(),
+            Ok(_) => self.record_success(),
+            Err(
+                PixivError::Reqwest(_)
+                | PixivError::Timeout(_)
+                | PixivError::Connect(_)
+                | PixivError::Tls(_),
+            ) => self.record_failure(),
+            Err(_) => {}
+        }
+    }
+}
+
+fn is_failure_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker(threshold: u32, cooldown: Duration) -> CircuitBreaker {
+        CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: threshold,
+            cooldown,
+        })
+    }
+
+    #[test]
+    fn opens_after_threshold() {
+        let cb = breaker(2, Duration::from_secs(60));
+        cb.record_failure();
+        assert!(cb.check().is_ok());
+        cb.record_failure();
+        assert!(matches!(cb.check(), Err(PixivError::CircuitOpen { .. })));
+    }
+
+    #[test]
+    fn success_resets_failures() {
+        let cb = breaker(2, Duration::from_secs(60));
+        cb.record_failure();
+        cb.record_success();
+        cb.record_failure();
+        assert!(cb.check().is_ok());
+    }
+
+    #[test]
+    fn half_open_after_cooldown() {
+        let cb = breaker(3, Duration::ZERO);
+        for _ in 0..3 {
+            cb.record_failure();
+        }
+        assert!(cb.check().is_ok());
+        cb.record_failure();
+        assert!(cb.lock().open_until.is_some());
+    }
+
+    #[test]
+    fn half_open_lets_one_trial_through() {
+        let cb = breaker(1, Duration::from_secs(60));
+        cb.lock().open_until = Some(Instant::now());
+        assert!(cb.check().is_ok());
+        assert!(matches!(cb.check(), Err(PixivError::CircuitOpen { .. })));
+        cb.record_success();
+        assert!(cb.check().is_ok());
+        assert!(cb.check().is_ok());
+    }
+
+    #[test]
+    fn failure_statuses() {
+        assert!(is_failure_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_failure_status(StatusCode::BAD_GATEWAY));
+        assert!(!is_failure_status(StatusCode::NOT_FOUND));
+    }
+}
@@ -0,0 +1,295 @@
+//! Bounded-depth social graph crawler over `user_following` / `user_follower`.
+//!
+//! 基于 `user_following` / `user_follower` 的有限深度社交关系爬虫。
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use futures_core::stream::Stream;
+
+use crate::AppPixivAPI;
+use crate::PixivError;
+use crate::debug;
+use crate::models::{UserFollower, UserFollowing, UserPreview};
+use crate::paging::with_deadline;
+use crate::params::Restrict;
+
+/// Which relations to follow when expanding a user.
+///
+/// 展开用户时沿哪些关系前进。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CrawlDirection {
+    /// Users the current user follows (`user_following`).
+    ///
+    /// 当前用户关注的用户。
+    Following,
+    /// Users following the current user (`user_follower`).
+    ///
+    /// 关注当前用户的用户。
+    Followers,
+    /// Both directions.
+    ///
+    /// 双向。
+    Both,
+}
+
+/// A directed follow relation: `follower` follows `followed`.
+///
+/// 有向关注关系：`follower` 关注了 `followed`。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FollowEdge {
+    /// The user who follows.
+    pub follower: u64,
+    /// The user being followed.
+    pub followed: u64,
+    /// Depth of the expanded user that produced this edge (seeds are depth 0).
+    pub depth: u32,
+}
+
+/// Configuration of a social graph crawl.
+///
+/// 社交关系爬取的配置。
+#[derive(Debug, Clone)]
+pub struct CrawlConfig {
+    /// Users to start from.
+    pub seeds: Vec<u64>,
+    /// How many levels to expand; `1` only expands the seeds.
+    pub max_depth: u32,
+    /// Relations to follow.
+    pub direction: CrawlDirection,
+    /// Maximum number of users expanded on each level; `None` for unlimited.
+    pub max_users_per_level: Option<usize>,
+    /// Maximum number of edges collected per user and direction; `None` for unlimited.
+    pub max_edges_per_user: Option<usize>,
+    /// Minimum delay before every page request.
+    pub request_interval: Duration,
+    /// Restrict used for `user_following` (only the authenticated user can see private follows).
+    pub restrict: Restrict,
+    /// Overall time limit of the crawl; the stream fails with `DeadlineExceeded` once it passes.
+    pub deadline: Option<Duration>,
+}
+
+impl CrawlConfig {
+    /// Create a config crawling the followings of `seeds` one level deep, one request per second.
+    ///
+    /// 创建默认配置：从 `seeds` 出发沿关注关系爬取一层，每秒一个请求。
+    pub fn new(seeds: impl Into<Vec<u64>>) -> Self {
+        Self {
+            seeds: seeds.into(),
+            max_depth: 1,
+            direction: CrawlDirection::Following,
+            max_users_per_level: None,
+            max_edges_per_user: None,
+            request_interval: Duration::from_secs(1),
+            restrict: Restrict::Public,
+            deadline: None,
+        }
+    }
+
+    /// Set the number of levels to expand.
+    pub fn max_depth(mut self, max_depth: u32) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Set the relations to follow.
+    pub fn direction(mut self, direction: CrawlDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Limit the number of users expanded on each level.
+    pub fn max_users_per_level(mut self, limit: usize) -> Self {
+        self.max_users_per_level = Some(limit);
+        self
+    }
+
+    /// Limit the number of edges collected per user and direction.
+    pub fn max_edges_per_user(mut self, limit: usize) -> Self {
+        self.max_edges_per_user = Some(limit);
+        self
+    }
+
+    /// Set the minimum delay before every page request.
+    pub fn request_interval(mut self, interval: Duration) -> Self {
+        self.request_interval = interval;
+        self
+    }
+
+    /// Set the restrict used for `user_following`.
+    pub fn restrict(mut self, restrict: Restrict) -> Self {
+        self.restrict = restrict;
+        self
+    }
+
+    /// Limit the total duration of the crawl.
+    pub fn deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// The single relations (`Following` and/or `Followers`) expanded for every user.
+    fn relations(&self) -> &'static [CrawlDirection] {
+        match self.direction {
+            CrawlDirection::Following => &[CrawlDirection::Following],
+            CrawlDirection::Followers => &[CrawlDirection::Followers],
+            CrawlDirection::Both => &[CrawlDirection::Following, CrawlDirection::Followers],
+        }
+    }
+}
+
+/// Records visited users and emitted edges, so cycles are neither re-expanded nor re-emitted.
+#[derive(Default)]
+struct CrawlState {
+    visited: HashSet<u64>,
+    edges: HashSet<(u64, u64)>,
+    next_level: Vec<u64>,
+}
+
+impl CrawlState {
+    /// Registers a neighbour and returns whether the edge is new.
+    fn discover(&mut self, edge: (u64, u64), neighbour: u64) -> bool {
+        if self.visited.insert(neighbour) {
+            self.next_level.push(neighbour);
+        }
+        self.edges.insert(edge)
+    }
+}
+
+impl AppPixivAPI {
+    /// One page of the users `user_id` relates to by `relation` (`Following` or `Followers`) with
+    /// the URL of the next page: the first page, or the one at `next_url`.
+    async fn follow_page(
+        &self,
+        relation: CrawlDirection,
+        user_id: u64,
+        next_url: Option<&str>,
+        config: &CrawlConfig,
+        with_auth: bool,
+    ) -> Result<(Vec<UserPreview>, Option<String>), PixivError> {
+        Ok(match (relation, next_url) {
+            (CrawlDirection::Followers, None) => {
+                let page = self.user_follower(user_id, None, None, with_auth).await?;
+                (page.user_previews, page.next_url)
+            }
+            (CrawlDirection::Followers, Some(url)) => {
+                let page = self.visit_next_url::<UserFollower>(url, with_auth).await?;
+                (page.user_previews, page.next_url)
+            }
+            (_, None) => {
+                let page = self
+                    .user_following(user_id, Some(config.restrict), None, with_auth)
+                    .await?;
+                (page.user_previews, page.next_url)
+            }
+            (_, Some(url)) => {
+                let page = self.visit_next_url::<UserFollowing>(url, with_auth).await?;
+                (page.user_previews, page.next_url)
+            }
+        })
+    }
+
+    /// Crawl the follow graph breadth-first from `config.seeds`, yielding each edge once.
+    ///
+    /// Users already seen are never expanded twice, so cycles in the graph terminate. If
+    /// `config.deadline` is set, the stream yields [`PixivError::DeadlineExceeded`] and ends once
+    /// it passes.
+    ///
+    /// 从 `config.seeds` 出发广度优先爬取关注关系，每条边只产出一次；已访问用户不会被重复展开。
+    pub fn crawl_social_graph<'a>(
+        &'a self,
+        config: CrawlConfig,
+        with_auth: bool,
+    ) -> impl Stream<Item = Result<FollowEdge, PixivError>> + use<'a> {
+        // `Duration::MAX` never elapses, see `with_deadline`.
+        let deadline = config.deadline.unwrap_or(Duration::MAX);
+        let crawl = async_stream::try_stream! {
+            let mut state = CrawlState::default();
+            let mut level: Vec<u64> = Vec::new();
+            for seed in &config.seeds {
+                if state.visited.insert(*seed) {
+                    level.push(*seed);
+                }
+            }
+
+            for depth in 0..config.max_depth {
+                if let Some(limit) = config.max_users_per_level {
+                    level.truncate(limit);
+                }
+                debug!("crawl_social_graph expanding {} users at depth {}", level.len(), depth);
+
+                for user_id in level.drain(..) {
+                    for &relation in config.relations() {
+                        let mut taken = 0;
+                        let mut next_url: Option<String> = None;
+                        loop {
+                            tokio::time::sleep(config.request_interval).await;
+                            let (users, next) = self
+                                .follow_page(relation, user_id, next_url.as_deref(), &config, with_auth)
+                                .await?;
+                            for UserPreview { user, .. } in &users {
+                                if config.max_edges_per_user.is_some_and(|limit| taken >= limit) {
+                                    break;
+                                }
+                                taken += 1;
+                                let (follower, followed) = match relation {
+                                    CrawlDirection::Followers => (user.id, user_id),
+                                    _ => (user_id, user.id),
+                                };
+                                if state.discover((follower, followed), user.id) {
+                                    yield FollowEdge { follower, followed, depth };
+                                }
+                            }
+                            if config.max_edges_per_user.is_some_and(|limit| taken >= limit) {
+                                break;
+                            }
+                            match next {
+                                Some(url) => next_url = Some(url),
+                                None => break,
+                            }
+                        }
+                    }
+                }
+
+                level = std::mem::take(&mut state.next_level);
+                if level.is_empty() {
+                    break;
+                }
+            }
+        };
+        with_deadline(crawl, deadline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_defaults() {
+        let config = CrawlConfig::new(vec![11]);
+        assert_eq!(config.seeds, vec![11]);
+        assert_eq!(config.max_depth, 1);
+        assert_eq!(config.relations(), &[CrawlDirection::Following]);
+    }
+
+    #[test]
+    fn config_both_directions() {
+        let config = CrawlConfig::new(vec![11]).direction(CrawlDirection::Both);
+        assert_eq!(
+            config.relations(),
+            &[CrawlDirection::Following, CrawlDirection::Followers]
+        );
+    }
+
+    #[test]
+    fn state_detects_cycles() {
+        let mut state = CrawlState::default();
+        state.visited.insert(1);
+        assert!(state.discover((1, 2), 2));
+        assert!(state.discover((2, 1), 1));
+        assert!(!state.discover((1, 2), 2));
+        assert_eq!(state.next_level, vec![2]);
+    }
+}
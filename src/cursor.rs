@@ -0,0 +1,251 @@
+//! Page-by-page access to paged endpoints that can be saved and resumed, available without the
+//! `stream` feature.
+//!
+//! 分页接口的逐页访问，可保存并续传，无需启用 `stream` 特性。
+
+use std::future::Future;
+use std::pin::Pin;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::AppPixivAPI;
+use crate::PixivError;
+use crate::models::PagedResponse;
+use crate::next_url::parse_qs;
+
+type PageFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, PixivError>> + Send + 'a>>;
+
+/// Cursor over the pages of a paged endpoint, created by the generated `*_cursor` methods
+/// (e.g. [`AppPixivAPI::user_illusts_cursor`]).
+///
+/// Each [`Self::next_page`] call fetches one page: the first with the endpoint's own
+/// parameters, the following ones from the previous page's `next_url`. A failed fetch leaves the
+/// cursor where it was, so calling `next_page` again retries the same page.
+///
+/// 分页接口的游标，由生成的 `*_cursor` 方法（如 [`AppPixivAPI::user_illusts_cursor`]）创建。每次调用
+/// [`Self::next_page`] 获取一页：第一页使用接口参数，其后使用上一页的 `next_url`。获取失败时游标不前进，
+/// 再次调用 `next_page` 即重试同一页。
+pub struct PageCursor<'a, T> {
+    api: &'a AppPixivAPI,
+    first: Option<Box<dyn FnMut() -> PageFuture<'a, T> + Send + 'a>>,
+    next_url: Option<String>,
+    with_auth: bool,
+}
+
+impl<'a, T: PagedResponse + DeserializeOwned> PageCursor<'a, T> {
+    pub(crate) fn new(
+        api: &'a AppPixivAPI,
+        first: Box<dyn FnMut() -> PageFuture<'a, T> + Send + 'a>,
+        with_auth: bool,
+    ) -> Self {
+        Self {
+            api,
+            first: Some(first),
+            next_url: None,
+            with_auth,
+        }
+    }
+
+    /// Fetch the next page; `Ok(None)` once the last page has been returned.
+    ///
+    /// 获取下一页；最后一页之后返回 `Ok(None)`。
+    pub async fn next_page(&mut self) -> Result<Option<T>, PixivError> {
+        let page = if let Some(first) = self.first.as_mut() {
+            let page = first().await?;
+            self.first = None;
+            page
+        } else if let Some(url) = &self.next_url {
+            self.api.visit_next_url(url, self.with_auth).await?
+        } else {
+            return Ok(None);
+        };
+        self.next_url = page.next_url().map(str::to_string);
+        Ok(Some(page))
+    }
+
+    /// URL of the page the next [`Self::next_page`] call fetches after the first one; `None`
+    /// before the first page and after the last.
+    ///
+    /// 第一页之后下一次 [`Self::next_page`] 将获取的 URL；获取第一页之前及最后一页之后为 `None`。
+    pub fn next_url(&self) -> Option<&str> {
+        self.next_url.as_deref()
+    }
+
+    /// Turn the cursor into a stream of the remaining pages; combine with
+    /// [`paging::take`](crate::paging::take) to fetch at most a number of pages.
+    ///
+    /// 将游标转换为剩余分页的流；配合 [`paging::take`](crate::paging::take) 可限制获取的页数。
+    #[cfg(feature = "stream")]
+    pub fn into_stream(
+        mut self,
+    ) -> impl futures_core::stream::Stream<Item = Result<T, PixivError>> + 'a
+    where
+        T: 'a,
+    {
+        async_stream::try_stream! {
+            while let Some(page) = self.next_page().await? {
+                yield page;
+            }
+        }
+    }
+
+    /// Save the cursor's position so that a later run can continue with
+    /// [`AppPixivAPI::resume_cursor`]; `None` before the first page, when there is nothing to save
+    /// yet (call the endpoint again instead).
+    ///
+    /// 保存游标位置，之后可用 [`AppPixivAPI::resume_cursor`] 继续；获取第一页之前返回 `None`（此时重新调用接口即可）。
+    pub fn save(&self) -> Option<SavedCursor> {
+        self.first.is_none().then(|| SavedCursor {
+            next_url: self.next_url.clone(),
+        })
+    }
+
+    /// Whether every page has been returned.
+    ///
+    /// 是否已返回全部分页。
+    pub fn is_done(&self) -> bool {
+        self.first.is_none() && self.next_url.is_none()
+    }
+}
+
+/// Serializable position of a [`PageCursor`] or of a page, for crawls that must survive a
+/// restart.
+///
+/// The `next_url` carries the endpoint and all of its parameters, so it is all that needs to be
+/// stored; [`Self::endpoint`] and [`Self::params`] decode it for inspection.
+///
+/// [`PageCursor`] 或某一页的可序列化位置，用于需要在重启后继续的爬取。`next_url` 已包含接口及其全部参数，
+/// 因此只需保存它；[`Self::endpoint`] 与 [`Self::params`] 可将其解码以便查看。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedCursor {
+    /// URL of the next page to fetch; `None` once every page has been returned.
+    pub next_url: Option<String>,
+}
+
+impl SavedCursor {
+    /// Position after `page`, e.g. one returned by [`AppPixivAPI::next_page`].
+    ///
+    /// `page` 之后的位置（如 [`AppPixivAPI::next_page`] 返回的页）。
+    pub fn after<T: PagedResponse>(page: &T) -> Self {
+        Self {
+            next_url: page.next_url().map(str::to_string),
+        }
+    }
+
+    /// Path of the endpoint the next page comes from, e.g. `/v1/user/illusts`.
+    ///
+    /// 下一页所属接口的路径，如 `/v1/user/illusts`。
+    pub fn endpoint(&self) -> Option<String> {
+        let url = reqwest::Url::parse(self.next_url.as_deref()?).ok()?;
+        Some(url.path().to_string())
+    }
+
+    /// Query parameters of the next page, see [`parse_qs`].
+    ///
+    /// 下一页的查询参数，参见 [`parse_qs`]。
+    pub fn params(&self) -> Vec<(String, String)> {
+        self.next_url.as_deref().map(parse_qs).unwrap_or_default()
+    }
+
+    /// Whether every page has been returned.
+    ///
+    /// 是否已返回全部分页。
+    pub fn is_done(&self) -> bool {
+        self.next_url.is_none()
+    }
+}
+
+impl AppPixivAPI {
+    /// Continue paging from `saved`, as returned by [`PageCursor::save`] or [`SavedCursor::after`]
+    /// in an earlier run. `T` must be the response type of the endpoint the cursor came from.
+    ///
+    /// 从之前运行中 [`PageCursor::save`] 或 [`SavedCursor::after`] 保存的 `saved` 继续翻页；`T` 必须是该游标所属接口的响应类型。
+    pub fn resume_cursor<T: PagedResponse + DeserializeOwned>(
+        &self,
+        saved: &SavedCursor,
+        with_auth: bool,
+    ) -> PageCursor<'_, T> {
+        PageCursor {
+            api: self,
+            first: None,
+            next_url: saved.next_url.clone(),
+            with_auth,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::UserFollowing;
+
+    #[tokio::test]
+    async fn cursor_walks_pages() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let second = format!(r#"{{"user_previews": [], "next_url": "{base}/page2"}}"#);
+        let bodies = [
+            second.clone(),
+            r#"{"user_previews": [], "next_url": null}"#.to_string(),
+            second,
+        ];
+        let server = tokio::spawn(async move {
+            let mut paths = Vec::new();
+            for body in bodies {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = [0u8; 2048];
+                let n = stream.read(&mut request).await.unwrap();
+                let request = String::from_utf8_lossy(&request[..n]).to_string();
+                paths.push(request.split(' ').nth(1).unwrap_or_default().to_string());
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+            paths
+        });
+
+        let api = AppPixivAPI::new_no_auth();
+        api.set_api_proxy(&base);
+        let mut cursor = api.user_following_cursor(11, None, None, false);
+        assert!(!cursor.is_done());
+        assert!(cursor.save().is_none());
+        let first: UserFollowing = cursor.next_page().await.unwrap().unwrap();
+        assert!(first.next_url.is_some());
+        assert_eq!(cursor.next_url(), Some(format!("{base}/page2").as_str()));
+        let saved: SavedCursor =
+            serde_json::from_str(&serde_json::to_string(&cursor.save().unwrap()).unwrap()).unwrap();
+        assert_eq!(saved.endpoint().as_deref(), Some("/page2"));
+        let mut cursor = api.resume_cursor::<UserFollowing>(&saved, false);
+        assert!(cursor.next_page().await.unwrap().is_some());
+        assert!(cursor.is_done());
+        assert!(cursor.save().unwrap().is_done());
+        assert!(cursor.next_page().await.unwrap().is_none());
+
+        #[cfg(feature = "stream")]
+        {
+            let pages = crate::paging::take(
+                api.user_following_cursor(11, None, None, false)
+                    .into_stream(),
+                1,
+            );
+            let pages = crate::paging::collect_all(pages, None).await.unwrap();
+            assert_eq!(pages.len(), 1);
+        }
+        #[cfg(not(feature = "stream"))]
+        assert!(
+            api.user_following_cursor(11, None, None, false)
+                .next_page()
+                .await
+                .is_ok()
+        );
+
+        let paths = server.await.unwrap();
+        assert!(paths[0].starts_with("/v1/user/following?"));
+        assert_eq!(paths[1], "/page2");
+    }
+}
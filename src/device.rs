@@ -0,0 +1,177 @@
+//! Spoofed device/app header presets sent with API requests.
+//!
+//! API 请求所伪装的设备 / App 请求头预设。
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, USER_AGENT};
+
+use crate::PixivError;
+
+/// Header values identifying the client device and app.
+///
+/// 标识客户端设备与 App 的请求头取值。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceProfile {
+    /// `app-os` header, e.g. `ios` or `android`.
+    pub app_os: String,
+    /// `app-os-version` header, e.g. `14.6`.
+    pub app_os_version: String,
+    /// `app-version` header, e.g. `7.13.3`.
+    pub app_version: String,
+    /// `User-Agent` header.
+    pub user_agent: String,
+}
+
+impl DeviceProfile {
+    /// Build the header map for this profile; fails if any value is not a valid header value.
+    ///
+    /// 生成该配置对应的请求头；若有非法取值则返回错误。
+    pub fn to_headers(&self) -> Result<HeaderMap, PixivError> {
+        let value = |name: &str, v: &str| {
+            HeaderValue::from_str(v).map_err(|e| PixivError::InvalidArgument {
+                message: format!("invalid {name} header value {v:?}: {e}"),
+            })
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            HeaderName::from_static("app-os"),
+            value("app-os", &self.app_os)?,
+        );
+        headers.insert(
+            HeaderName::from_static("app-os-version"),
+            value("app-os-version", &self.app_os_version)?,
+        );
+        headers.insert(
+            HeaderName::from_static("app-version"),
+            value("app-version", &self.app_version)?,
+        );
+        headers.insert(USER_AGENT, value("user-agent", &self.user_agent)?);
+        Ok(headers)
+    }
+
+    /// This profile claiming app version `version` instead, in both the `app-version` header and
+    /// the `User-Agent`; pair with `application_info_ios` / `application_info_android` and
+    /// [`DevicePreset::Custom`].
+    ///
+    /// 将该配置声明的 App 版本改为 `version`（同时更新 `app-version` 与 `User-Agent`）；可配合
+    /// `application_info_ios` / `application_info_android` 与 [`DevicePreset::Custom`] 使用。
+    pub fn with_app_version(&self, version: &str) -> Self {
+        Self {
+            app_version: version.to_string(),
+            user_agent: self.user_agent.replacen(&self.app_version, version, 1),
+            ..self.clone()
+        }
+    }
+}
+
+/// Device presets for the spoofed app headers. Defaults to [`DevicePreset::IPhone`].
+///
+/// 伪装 App 请求头的设备预设，默认为 [`DevicePreset::IPhone`]。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DevicePreset {
+    /// Pixiv iOS app on an iPhone.
+    ///
+    /// iPhone 上的 Pixiv iOS 应用。
+    #[default]
+    IPhone,
+    /// Pixiv iOS app on an iPad.
+    ///
+    /// iPad 上的 Pixiv iOS 应用。
+    IPad,
+    /// Pixiv Android app.
+    ///
+    /// Pixiv Android 应用。
+    Android,
+    /// User-provided header values.
+    ///
+    /// 用户自定义的请求头取值。
+    Custom(DeviceProfile),
+}
+
+impl DevicePreset {
+    /// The header values of this preset.
+    ///
+    /// 该预设的请求头取值。
+    pub fn profile(&self) -> DeviceProfile {
+        let (app_os, app_os_version, app_version, user_agent) = match self {
+            Self::IPhone => (
+                "ios",
+                "14.6",
+                "7.13.3",
+                "PixivIOSApp/7.13.3 (iOS 14.6; iPhone13,2)",
+            ),
+            Self::IPad => (
+                "ios",
+                "14.6",
+                "7.13.3",
+                "PixivIOSApp/7.13.3 (iPadOS 14.6; iPad13,1)",
+            ),
+            Self::Android => (
+                "android",
+                "11",
+                "5.0.234",
+                "PixivAndroidApp/5.0.234 (Android 11; Pixel 5)",
+            ),
+            Self::Custom(profile) => return profile.clone(),
+        };
+        DeviceProfile {
+            app_os: app_os.to_string(),
+            app_os_version: app_os_version.to_string(),
+            app_version: app_version.to_string(),
+            user_agent: user_agent.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_presets_are_valid_headers() {
+        for preset in [
+            DevicePreset::IPhone,
+            DevicePreset::IPad,
+            DevicePreset::Android,
+        ] {
+            let headers = preset.profile().to_headers().unwrap();
+            assert_eq!(headers.len(), 4);
+        }
+    }
+
+    #[test]
+    fn android_preset_headers() {
+        let headers = DevicePreset::Android.profile().to_headers().unwrap();
+        assert_eq!(headers["app-os"], "android");
+        assert!(
+            headers[USER_AGENT]
+                .to_str()
+                .unwrap()
+                .starts_with("PixivAndroidApp/")
+        );
+    }
+
+    #[test]
+    fn with_app_version_updates_user_agent() {
+        let profile = DevicePreset::IPhone.profile().with_app_version("7.20.1");
+        assert_eq!(profile.app_version, "7.20.1");
+        assert_eq!(
+            profile.user_agent,
+            "PixivIOSApp/7.20.1 (iOS 14.6; iPhone13,2)"
+        );
+    }
+
+    #[test]
+    fn custom_preset_rejects_invalid_value() {
+        let preset = DevicePreset::Custom(DeviceProfile {
+            app_os: "ios".into(),
+            app_os_version: "1\n2".into(),
+            app_version: "1.0".into(),
+            user_agent: "test".into(),
+        });
+        assert!(matches!(
+            preset.profile().to_headers(),
+            Err(PixivError::InvalidArgument { .. })
+        ));
+    }
+}
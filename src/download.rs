@@ -0,0 +1,1109 @@
+//! Download helpers: probing remote files before transferring them, downloading into files,
+//! writers or memory, ugoira archives.
+//!
+//! 下载辅助：传输前探测远程文件信息、下载到文件 / writer / 内存、ugoira 压缩包。
+
+#[cfg(feature = "fs")]
+use std::path::{Path, PathBuf};
+
+use bytes::Bytes;
+#[cfg(feature = "stream")]
+use futures_core::stream::Stream;
+use reqwest::StatusCode;
+use reqwest::header::{ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_TYPE, REFERER};
+#[cfg(feature = "fs")]
+use reqwest::header::{CONTENT_RANGE, ETAG, LAST_MODIFIED};
+use reqwest::header::{IF_RANGE, RANGE};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::AppPixivAPI;
+use crate::PixivError;
+use crate::debug;
+use crate::error::ResponseContext;
+use crate::models::read_response_bytes;
+#[cfg(feature = "fs")]
+use crate::models::{IllustrationInfo, ImageSize, UgoiraMetadata};
+
+/// Metadata of a remote file obtained with a `HEAD` request.
+///
+/// 通过 `HEAD` 请求获得的远程文件元信息。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadProbe {
+    /// Size in bytes, from `Content-Length`.
+    pub content_length: Option<u64>,
+    /// MIME type, from `Content-Type`.
+    pub content_type: Option<String>,
+    /// Whether the server advertises byte range support (`Accept-Ranges: bytes`).
+    pub accept_ranges: bool,
+}
+
+impl DownloadProbe {
+    fn from_headers(headers: &reqwest::header::HeaderMap) -> Self {
+        let header_str = |name| headers.get(name).and_then(|v| v.to_str().ok());
+        Self {
+            content_length: header_str(CONTENT_LENGTH).and_then(|v| v.parse().ok()),
+            content_type: header_str(CONTENT_TYPE).map(str::to_string),
+            accept_ranges: header_str(ACCEPT_RANGES)
+                .is_some_and(|v| v.split(',').any(|unit| unit.trim() == "bytes")),
+        }
+    }
+}
+
+/// Progress of a download, reported after every received chunk.
+///
+/// 下载进度，每收到一块数据报告一次。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DownloadProgress {
+    /// Bytes received so far.
+    pub downloaded: u64,
+    /// Total size, from `Content-Length`; `None` if the server did not send it.
+    pub total: Option<u64>,
+}
+
+/// Callback receiving [`DownloadProgress`] updates.
+///
+/// 接收 [`DownloadProgress`] 的回调。
+pub type ProgressCallback<'a> = &'a mut (dyn FnMut(DownloadProgress) + Send);
+
+/// What to do when the target file of a download already exists.
+///
+/// Converts from the `replace: bool` of the older helpers: `true` is [`Overwrite`](Self::Overwrite),
+/// `false` is [`Skip`](Self::Skip).
+///
+/// 下载目标文件已存在时的处理方式。可由旧接口的 `replace: bool` 转换而来：`true` 对应
+/// [`Overwrite`](Self::Overwrite)，`false` 对应 [`Skip`](Self::Skip)。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CollisionPolicy {
+    /// Keep the existing file and do not download.
+    #[default]
+    Skip,
+    /// Download again and replace the existing file.
+    Overwrite,
+    /// Keep the existing file and save the download as `name (1).ext`, `name (2).ext`, ...
+    RenameWithSuffix,
+    /// Keep the existing file if its size matches the remote one, otherwise fail with
+    /// `PixivError::DownloadConflict`.
+    ErrorIfDifferentSize,
+}
+
+impl From<bool> for CollisionPolicy {
+    fn from(replace: bool) -> Self {
+        if replace { Self::Overwrite } else { Self::Skip }
+    }
+}
+
+/// Result of [`AppPixivAPI::download_file`].
+///
+/// [`AppPixivAPI::download_file`] 的结果。
+#[cfg(feature = "fs")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadedFile {
+    /// Where the file was saved.
+    pub path: PathBuf,
+    /// Bytes written by this call, not counting a resumed partial file; `0` if skipped.
+    pub bytes_written: u64,
+    /// Whether the file already existed and was not downloaded again.
+    pub skipped: bool,
+}
+
+/// Referer accepted by `i.pximg.net` for app-api image URLs.
+pub(crate) const APP_API_REFERER: &str = "https://app-api.pixiv.net/";
+
+/// Referer sent with requests to the web site (`www.pixiv.net`).
+const WEB_REFERER: &str = "https://www.pixiv.net/";
+
+/// The Referer the download helpers send for `url` when none is given: the app-api one for
+/// `pximg.net` image hosts, the web site for `pixiv.net` hosts, and none for other hosts.
+///
+/// 未指定 Referer 时下载辅助函数为 `url` 使用的值：`pximg.net` 图片服务器使用 app-api 的 Referer，
+/// `pixiv.net` 使用网站首页，其他主机不发送。
+pub fn referer_for(url: &str) -> Option<&'static str> {
+    let url = reqwest::Url::parse(url).ok()?;
+    let host = url.host_str()?;
+    let under = |domain: &str| {
+        host == domain
+            || host
+                .strip_suffix(domain)
+                .is_some_and(|sub| sub.ends_with('.'))
+    };
+    if under("pximg.net") {
+        Some(APP_API_REFERER)
+    } else if under("pixiv.net") {
+        Some(WEB_REFERER)
+    } else {
+        None
+    }
+}
+
+/// `referer`, or the one derived by [`referer_for`] if `None`.
+fn resolve_referer<'a>(url: &str, referer: Option<&'a str>) -> Option<&'a str> {
+    referer.or_else(|| referer_for(url))
+}
+
+/// One work handled by [`AppPixivAPI::download_all`].
+///
+/// [`AppPixivAPI::download_all`] 处理的一个作品。
+#[cfg(all(feature = "stream", feature = "fs"))]
+#[derive(Debug)]
+pub struct WorkDownload {
+    /// The work, as received from the source stream.
+    pub illust: IllustrationInfo,
+    /// The saved files, or why the work could not be saved.
+    pub result: Result<Vec<DownloadedFile>, PixivError>,
+}
+
+/// Files written by [`AppPixivAPI::download_ugoira_archive`].
+///
+/// [`AppPixivAPI::download_ugoira_archive`] 写入的文件。
+#[cfg(feature = "fs")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UgoiraArchive {
+    /// The original zip of frames, as served by Pixiv.
+    pub zip_path: PathBuf,
+    /// JSON sidecar holding the [`UgoiraMetadata`] (frame files and delays).
+    pub metadata_path: PathBuf,
+    /// Whether the zip already existed and was not downloaded again.
+    pub skipped: bool,
+}
+
+impl AppPixivAPI {
+    /// Download `url` into `dir` as `name` (default: the last segment of the URL), reporting
+    /// progress to `progress`. `collision` decides what happens if the file already exists.
+    /// Like all download helpers, it sends `referer`, or the one [`referer_for`] derives from
+    /// the URL if `None`.
+    ///
+    /// Data is written to `{name}.part` first and renamed once complete, with the remote file's
+    /// `ETag` or `Last-Modified` kept in `{name}.part.validator` meanwhile. If a `.part` file is
+    /// left over from an interrupted download, the download resumes from its end with a `Range`
+    /// request conditioned on that validator (`If-Range`). It starts over if the validator is
+    /// missing, the remote file changed, or the server ignores the range or answers another one.
+    /// Fails with
+    /// `PixivError::IncompleteDownload`, keeping the `.part` file, if the connection ends before
+    /// the announced size was received.
+    ///
+    /// Fails with `PixivError::NotFound` or a status error if the server does not answer with
+    /// success.
+    ///
+    /// 将 `url` 下载到 `dir` 下的 `name`（默认取 URL 最后一段），并向 `progress` 报告进度；文件已存在时按
+    /// `collision` 处理。与其他下载函数一样发送 `referer`，为 `None` 时使用 [`referer_for`] 推断的值。数据先写入 `{name}.part`，完成后再重命名，期间远程文件的
+    /// `ETag` 或 `Last-Modified` 保存在 `{name}.part.validator` 中；若存在上次中断留下的 `.part` 文件，则以带
+    /// `If-Range` 的 `Range` 请求续传（缺少校验值、远程文件已变化、服务端忽略或返回其他范围时重新下载）。接收的数据少于服务端声明的大小时返回
+    /// `PixivError::IncompleteDownload` 并保留 `.part` 文件。服务端未返回成功状态时报错。
+    #[cfg(feature = "fs")]
+    pub async fn download_file(
+        &self,
+        url: &str,
+        dir: &Path,
+        name: Option<&str>,
+        collision: impl Into<CollisionPolicy>,
+        referer: Option<&str>,
+        mut progress: Option<ProgressCallback<'_>>,
+    ) -> Result<DownloadedFile, PixivError> {
+        let _operation = self.begin_operation()?;
+        let _permit = self.acquire_download_permit().await;
+        let filename = name.unwrap_or_else(|| url.split('/').next_back().unwrap_or("download"));
+        let mut path = dir.join(filename);
+        let skipped = |path| DownloadedFile {
+            path,
+            bytes_written: 0,
+            skipped: true,
+        };
+        let mut expected_size = None;
+        if let Ok(existing) = tokio::fs::metadata(&path).await {
+            match collision.into() {
+                CollisionPolicy::Skip => return Ok(skipped(path)),
+                CollisionPolicy::Overwrite => {}
+                CollisionPolicy::RenameWithSuffix => path = free_path(&path).await,
+                CollisionPolicy::ErrorIfDifferentSize => expected_size = Some(existing.len()),
+            }
+        }
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let part = path.with_file_name(format!("{file_name}.part"));
+        let validator_path = path.with_file_name(format!("{file_name}.part.validator"));
+        // Only a `.part` file whose validator is known can be resumed safely.
+        let mut resume = match tokio::fs::read_to_string(&validator_path).await {
+            Ok(validator) if !validator.is_empty() => {
+                let offset = tokio::fs::metadata(&part).await.map_or(0, |m| m.len());
+                (offset > 0).then_some((offset, validator))
+            }
+            _ => None,
+        };
+        let res = loop {
+            let range = resume
+                .as_ref()
+                .map(|(offset, validator)| (*offset, validator.as_str()));
+            let res = self.get_download(url, referer, range).await?;
+            if let Some((offset, _)) = range {
+                let mismatched = match res.status() {
+                    StatusCode::RANGE_NOT_SATISFIABLE => true,
+                    StatusCode::PARTIAL_CONTENT => {
+                        content_range_start(res.headers()) != Some(offset)
+                    }
+                    _ => false,
+                };
+                if mismatched {
+                    // The partial file does not match the remote one; start over.
+                    debug!("cannot resume {url} from byte {offset}, starting over");
+                    resume = None;
+                    continue;
+                }
+            }
+            break res;
+        };
+        let mut res = check_download_status(res)?;
+
+        let offset = resume.map_or(0, |(offset, _)| offset);
+        let resumed = offset > 0 && res.status() == StatusCode::PARTIAL_CONTENT;
+        let total = if resumed {
+            content_range_total(res.headers())
+                .or_else(|| res.content_length().map(|len| offset + len))
+        } else {
+            res.content_length()
+        };
+        if let Some(local_size) = expected_size {
+            if total == Some(local_size) {
+                return Ok(skipped(path));
+            }
+            return Err(PixivError::DownloadConflict {
+                path,
+                local_size,
+                remote_size: total,
+            });
+        }
+        let (mut file, start) = if resumed {
+            let file = tokio::fs::OpenOptions::new()
+                .append(true)
+                .open(&part)
+                .await?;
+            (file, offset)
+        } else {
+            debug!("downloading {url} to {}", path.display());
+            let file = tokio::fs::File::create(&part).await?;
+            match download_validator(res.headers()) {
+                Some(validator) => tokio::fs::write(&validator_path, validator).await?,
+                None => {
+                    let _ = tokio::fs::remove_file(&validator_path).await;
+                }
+            }
+            (file, 0)
+        };
+        let mut downloaded = start;
+        while let Some(chunk) = res.chunk().await? {
+            file.write_all(&chunk).await?;
+            downloaded += chunk.len() as u64;
+            if let Some(progress) = progress.as_mut() {
+                progress(DownloadProgress { downloaded, total });
+            }
+        }
+        file.flush().await?;
+        drop(file);
+        if let Some(expected) = total.filter(|&total| total != downloaded) {
+            return Err(PixivError::IncompleteDownload {
+                expected,
+                received: downloaded,
+            });
+        }
+        tokio::fs::rename(&part, &path).await?;
+        let _ = tokio::fs::remove_file(&validator_path).await;
+        Ok(DownloadedFile {
+            path,
+            bytes_written: downloaded - start,
+            skipped: false,
+        })
+    }
+
+    /// Download `url` into `writer` (a file, an upload stream, ...) without touching the file
+    /// system; returns the number of bytes written. The writer is flushed but not shut down.
+    ///
+    /// 将 `url` 下载到 `writer`（文件、上传流等），不经过文件系统；返回写入的字节数。会刷新但不会关闭 `writer`。
+    pub async fn download_to_writer<W>(
+        &self,
+        url: &str,
+        referer: Option<&str>,
+        writer: &mut W,
+    ) -> Result<u64, PixivError>
+    where
+        W: AsyncWrite + Unpin + Send + ?Sized,
+    {
+        let _operation = self.begin_operation()?;
+        let _permit = self.acquire_download_permit().await;
+        let mut res = check_download_status(self.get_download(url, referer, None).await?)?;
+        let mut written = 0;
+        while let Some(chunk) = res.chunk().await? {
+            writer.write_all(&chunk).await?;
+            written += chunk.len() as u64;
+        }
+        writer.flush().await?;
+        Ok(written)
+    }
+
+    /// Download `url` into memory. Fails with `PixivError::ResponseTooLarge` once more than the
+    /// [maximum response size](Self::set_max_response_size) arrives.
+    ///
+    /// 将 `url` 下载到内存；接收的数据超过[最大响应大小](Self::set_max_response_size)时返回
+    /// `PixivError::ResponseTooLarge`。
+    pub async fn download_bytes(
+        &self,
+        url: &str,
+        referer: Option<&str>,
+    ) -> Result<Bytes, PixivError> {
+        let _operation = self.begin_operation()?;
+        let _permit = self.acquire_download_permit().await;
+        let res = check_download_status(self.get_download(url, referer, None).await?)?;
+        Ok(read_response_bytes(res, self.max_response_size())
+            .await?
+            .into())
+    }
+
+    /// Download `url` as a stream of chunks, for piping image data through custom processing
+    /// (hashing, transcoding, chunked uploads) without intermediate files.
+    ///
+    /// 以数据块流的形式下载 `url`，便于在不落盘的情况下自行处理数据（计算哈希、转码、分块上传等）。
+    #[cfg(feature = "stream")]
+    pub fn download_stream<'a>(
+        &'a self,
+        url: &'a str,
+        referer: Option<&'a str>,
+    ) -> impl Stream<Item = Result<Bytes, PixivError>> + use<'a> {
+        async_stream::try_stream! {
+            let _operation = self.begin_operation()?;
+            let _permit = self.acquire_download_permit().await;
+            let mut res = check_download_status(self.get_download(url, referer, None).await?)?;
+            while let Some(chunk) = res.chunk().await? {
+                yield chunk;
+            }
+        }
+    }
+
+    /// Send a download request with `referer` (derived from the URL if `None`). With
+    /// `Some((offset, validator))`, ask for the bytes from `offset` on if the remote file still
+    /// matches `validator` (an `ETag` or `Last-Modified` value), and for the whole file otherwise.
+    async fn get_download(
+        &self,
+        url: &str,
+        referer: Option<&str>,
+        range: Option<(u64, &str)>,
+    ) -> Result<reqwest::Response, PixivError> {
+        let mut req = self.client().get(url);
+        if let Some(referer) = resolve_referer(url, referer) {
+            req = req.header(REFERER, referer);
+        }
+        if let Some((offset, validator)) = range {
+            debug!("requesting {url} from byte {offset}");
+            req = req
+                .header(RANGE, format!("bytes={offset}-"))
+                .header(IF_RANGE, validator);
+        }
+        Ok(req.send().await?)
+    }
+
+    /// Download every page of illust `illust_id` at `size` into `dir` (created if missing), named
+    /// as on Pixiv's image server (e.g. `12345_p0.png`) and sent with the referer it requires.
+    ///
+    /// Fails with `PixivError::InvalidArgument` if the work is deleted, private or otherwise not
+    /// visible. Pages are downloaded one after another; existing files are handled per
+    /// `collision`.
+    ///
+    /// 将作品 `illust_id` 的所有页以 `size` 尺寸下载到 `dir`（不存在则创建），文件名与 Pixiv 图片服务器一致
+    /// （如 `12345_p0.png`），并附带所需的 Referer。作品已删除、非公开或不可见时返回
+    /// `PixivError::InvalidArgument`；各页依次下载，已存在的文件按 `collision` 处理。
+    #[cfg(feature = "fs")]
+    pub async fn download_illust(
+        &self,
+        illust_id: u64,
+        dir: &Path,
+        size: ImageSize,
+        collision: impl Into<CollisionPolicy>,
+        with_auth: bool,
+    ) -> Result<Vec<DownloadedFile>, PixivError> {
+        let collision = collision.into();
+        let illust = self.illust_detail(illust_id, with_auth).await?.illust;
+        if illust.is_limited() {
+            return Err(PixivError::InvalidArgument {
+                message: format!("illust {illust_id} is deleted, private or not visible"),
+            });
+        }
+        tokio::fs::create_dir_all(dir).await?;
+        self.download_pages(&illust, dir, size, collision).await
+    }
+
+    /// Download every page of `illust` at `size` into the existing directory `dir`.
+    #[cfg(feature = "fs")]
+    pub(crate) async fn download_pages(
+        &self,
+        illust: &IllustrationInfo,
+        dir: &Path,
+        size: ImageSize,
+        collision: CollisionPolicy,
+    ) -> Result<Vec<DownloadedFile>, PixivError> {
+        let mut files = Vec::new();
+        for url in illust.urls_for(size) {
+            files.push(
+                self.download_file(url, dir, None, collision, None, None)
+                    .await?,
+            );
+        }
+        Ok(files)
+    }
+
+    /// Download every page of `illust` into `dir`, plus the frame zip if it is an ugoira.
+    #[cfg(feature = "fs")]
+    pub(crate) async fn download_work_media(
+        &self,
+        illust: &IllustrationInfo,
+        dir: &Path,
+        size: ImageSize,
+        collision: CollisionPolicy,
+        with_auth: bool,
+    ) -> Result<Vec<DownloadedFile>, PixivError> {
+        let mut files = self.download_pages(illust, dir, size, collision).await?;
+        if illust.type_ == "ugoira" {
+            let archive = self
+                .download_ugoira_archive(illust.id, dir, collision, with_auth)
+                .await?;
+            files.push(DownloadedFile {
+                path: archive.zip_path,
+                bytes_written: 0,
+                skipped: archive.skipped,
+            });
+        }
+        Ok(files)
+    }
+
+    /// Download the works of `illusts` (e.g. `search_illust_iter`) into `dir` (created if
+    /// missing) like [`Self::download_illust`], with up to `concurrency` works in flight, and
+    /// yield one [`WorkDownload`] per work as it finishes, so not in source order.
+    ///
+    /// A failed work, including a deleted or private one, is reported in its
+    /// [`WorkDownload::result`] and does not stop the stream. An error from `illusts` itself is
+    /// yielded as is and ends the stream, abandoning the downloads in flight.
+    ///
+    /// 以最多 `concurrency` 个作品并发，将 `illusts`（如 `search_illust_iter`）中的作品按
+    /// [`Self::download_illust`] 的方式下载到 `dir`（不存在则创建），每完成一个作品产出一个 [`WorkDownload`]
+    /// （顺序与来源不同）。单个作品失败（包括已删除或非公开作品）记录在 [`WorkDownload::result`] 中，不影响后续；
+    /// `illusts` 本身出错时原样产出该错误并结束，正在进行的下载将被放弃。
+    #[cfg(feature = "stream")]
+    pub fn download_all<'a, S>(
+        &'a self,
+        illusts: S,
+        dir: &'a Path,
+        size: ImageSize,
+        collision: impl Into<CollisionPolicy>,
+        concurrency: usize,
+        with_auth: bool,
+    ) -> impl Stream<Item = Result<WorkDownload, PixivError>> + 'a
+    where
+        S: Stream<Item = Result<IllustrationInfo, PixivError>> + 'a,
+    {
+        use std::future::Future;
+        use std::task::Poll;
+
+        let collision = collision.into();
+        let limit = concurrency.max(1);
+        let start = move |illust: IllustrationInfo| async move {
+            let result = if illust.is_limited() {
+                Err(PixivError::InvalidArgument {
+                    message: format!("illust {} is deleted, private or not visible", illust.id),
+                })
+            } else {
+                self.download_work_media(&illust, dir, size, collision, with_auth)
+                    .await
+            };
+            WorkDownload { illust, result }
+        };
+        async_stream::try_stream! {
+            tokio::fs::create_dir_all(dir).await?;
+            let mut source = std::pin::pin!(illusts);
+            let mut running = Vec::new();
+            let mut exhausted = false;
+            loop {
+                // `None` once the source and all downloads are done.
+                let event = std::future::poll_fn(|cx| {
+                    while !exhausted && running.len() < limit {
+                        match source.as_mut().poll_next(cx) {
+                            Poll::Ready(Some(Ok(illust))) => running.push(Box::pin(start(illust))),
+                            Poll::Ready(Some(Err(error))) => return Poll::Ready(Some(Err(error))),
+                            Poll::Ready(None) => exhausted = true,
+                            Poll::Pending => break,
+                        }
+                    }
+                    for i in 0..running.len() {
+                        if let Poll::Ready(work) = running[i].as_mut().poll(cx) {
+                            drop(running.swap_remove(i));
+                            return Poll::Ready(Some(Ok(work)));
+                        }
+                    }
+                    if exhausted && running.is_empty() {
+                        Poll::Ready(None)
+                    } else {
+                        Poll::Pending
+                    }
+                })
+                .await;
+                match event {
+                    Some(work) => yield work?,
+                    None => break,
+                }
+            }
+        }
+    }
+
+    /// Save an ugoira losslessly: the original frame zip (see
+    /// [`UgoiraZipUrls::original`](crate::models::UgoiraZipUrls::original)) plus a
+    /// `{illust_id}_ugoira.json` sidecar with frame files and delays. Nothing is re-encoded. An
+    /// existing zip is handled per `collision`.
+    ///
+    /// 无损保存 ugoira：原始帧压缩包及记录帧文件与延时的 `{illust_id}_ugoira.json`，不做任何重新编码。
+    /// 已存在的压缩包按 `collision` 处理。
+    #[cfg(feature = "fs")]
+    pub async fn download_ugoira_archive(
+        &self,
+        illust_id: u64,
+        dir: &Path,
+        collision: impl Into<CollisionPolicy>,
+        with_auth: bool,
+    ) -> Result<UgoiraArchive, PixivError> {
+        let metadata = self
+            .ugoira_metadata(illust_id, with_auth)
+            .await?
+            .ugoira_metadata;
+        let zip_url = &metadata.zip_urls.original();
+        let zip_name = zip_url
+            .split('/')
+            .next_back()
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{illust_id}_ugoira.zip"));
+        let zip = self
+            .download_file(zip_url, dir, Some(&zip_name), collision, None, None)
+            .await?;
+
+        let metadata_path = dir.join(format!("{illust_id}_ugoira.json"));
+        write_ugoira_sidecar(&metadata_path, &metadata).await?;
+
+        Ok(UgoiraArchive {
+            zip_path: zip.path,
+            metadata_path,
+            skipped: zip.skipped,
+        })
+    }
+
+    /// Issue a `HEAD` request for `url` and report its size, content type and range support.
+    ///
+    /// 对 `url` 发送 `HEAD` 请求，返回文件大小、类型及是否支持断点续传。
+    pub async fn probe_download(
+        &self,
+        url: &str,
+        referer: Option<&str>,
+    ) -> Result<DownloadProbe, PixivError> {
+        let _operation = self.begin_operation()?;
+        let _permit = self.acquire_download_permit().await;
+        debug!("probing download {url}");
+        let mut req = self.client().head(url);
+        if let Some(referer) = resolve_referer(url, referer) {
+            req = req.header(REFERER, referer);
+        }
+        let res = req.send().await?;
+        let res = check_download_status(res)?;
+        Ok(DownloadProbe::from_headers(res.headers()))
+    }
+}
+
+/// The first of `name (1).ext`, `name (2).ext`, ... next to `path` that does not exist yet.
+#[cfg(feature = "fs")]
+async fn free_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    let mut n = 1;
+    loop {
+        let candidate = path.with_file_name(format!("{stem} ({n}){extension}"));
+        if !tokio::fs::try_exists(&candidate).await.unwrap_or(false) {
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+/// Complete size from a `Content-Range: bytes a-b/total` header.
+#[cfg(feature = "fs")]
+fn content_range_total(headers: &reqwest::header::HeaderMap) -> Option<u64> {
+    let value = headers.get(CONTENT_RANGE)?.to_str().ok()?;
+    value.rsplit_once('/')?.1.trim().parse().ok()
+}
+
+/// First byte of a `Content-Range: bytes start-end/total` header.
+#[cfg(feature = "fs")]
+fn content_range_start(headers: &reqwest::header::HeaderMap) -> Option<u64> {
+    let value = headers.get(CONTENT_RANGE)?.to_str().ok()?;
+    let range = value.trim().strip_prefix("bytes")?.trim_start();
+    range.split_once('-')?.0.trim().parse().ok()
+}
+
+/// A validator identifying this version of a remote file for `If-Range`: its strong `ETag`, or
+/// else its `Last-Modified` date.
+#[cfg(feature = "fs")]
+fn download_validator(headers: &reqwest::header::HeaderMap) -> Option<&str> {
+    let etag = headers
+        .get(ETAG)
+        .and_then(|v| v.to_str().ok())
+        .filter(|etag| !etag.starts_with("W/"));
+    etag.or_else(|| headers.get(LAST_MODIFIED)?.to_str().ok())
+}
+
+/// Map a 404 to `PixivError::NotFound` and other failure statuses to a status error.
+fn check_download_status(res: reqwest::Response) -> Result<reqwest::Response, PixivError> {
+    if res.status() == StatusCode::NOT_FOUND {
+        return Err(PixivError::NotFound {
+            body: String::new(),
+            context: Some(ResponseContext::of(&res)),
+        });
+    }
+    Ok(res.error_for_status()?)
+}
+
+#[cfg(feature = "fs")]
+async fn write_ugoira_sidecar(path: &Path, metadata: &UgoiraMetadata) -> Result<(), PixivError> {
+    let json = serde_json::to_vec_pretty(metadata).map_err(|error| PixivError::Serde {
+        error,
+        body: String::new(),
+        context: None,
+    })?;
+    tokio::fs::write(path, json).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderMap, HeaderValue};
+
+    #[test]
+    fn probe_from_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_LENGTH, HeaderValue::from_static("12345"));
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("image/png"));
+        headers.insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+        let probe = DownloadProbe::from_headers(&headers);
+        assert_eq!(probe.content_length, Some(12345));
+        assert_eq!(probe.content_type.as_deref(), Some("image/png"));
+        assert!(probe.accept_ranges);
+    }
+
+    #[cfg(feature = "fs")]
+    #[tokio::test]
+    async fn ugoira_sidecar_round_trip() {
+        let metadata: UgoiraMetadata = serde_json::from_str(
+            r#"{"zip_urls": {"medium": "https://example.com/1.zip"}, "frames": [{"file": "000000.jpg", "delay": 60}]}"#,
+        )
+        .unwrap();
+        let path =
+            std::env::temp_dir().join(format!("pixiv3-rs-test-{}_ugoira.json", std::process::id()));
+        write_ugoira_sidecar(&path, &metadata).await.unwrap();
+        let written: UgoiraMetadata =
+            serde_json::from_slice(&tokio::fs::read(&path).await.unwrap()).unwrap();
+        tokio::fs::remove_file(&path).await.unwrap();
+        assert_eq!(written.frames[0].file, "000000.jpg");
+        assert_eq!(written.frames[0].delay, 60);
+    }
+
+    #[test]
+    fn probe_without_range_support() {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT_RANGES, HeaderValue::from_static("none"));
+        let probe = DownloadProbe::from_headers(&headers);
+        assert_eq!(probe.content_length, None);
+        assert!(!probe.accept_ranges);
+    }
+
+    /// Serve one raw HTTP response on a local port; returns the URL of `path` and the request.
+    async fn serve_once(
+        path: &str,
+        response: &'static [u8],
+    ) -> (String, tokio::task::JoinHandle<String>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}{path}", listener.local_addr().unwrap());
+        let request = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let n = stream.read(&mut request).await.unwrap();
+            let _ = stream.write_all(response).await;
+            String::from_utf8_lossy(&request[..n]).to_lowercase()
+        });
+        (url, request)
+    }
+
+    #[tokio::test]
+    async fn download_to_writer_and_memory() {
+        const OK: &[u8] = b"HTTP/1.1 200 OK\r\ncontent-length: 5\r\nconnection: close\r\n\r\nhello";
+        let api = AppPixivAPI::new_no_auth();
+        let (url, request) = serve_once("/img/4_p0.png", OK).await;
+        let mut buffer = Vec::new();
+        let written = api
+            .download_to_writer(&url, Some("https://app-api.pixiv.net/"), &mut buffer)
+            .await
+            .unwrap();
+        assert!(
+            request
+                .await
+                .unwrap()
+                .contains("referer: https://app-api.pixiv.net/")
+        );
+        assert_eq!((written, buffer.as_slice()), (5, b"hello".as_slice()));
+
+        let (url, _) = serve_once("/img/4_p0.png", OK).await;
+        assert_eq!(api.download_bytes(&url, None).await.unwrap(), "hello");
+
+        let (url, _) = serve_once(
+            "/img/4_p0.png",
+            b"HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\nconnection: close\r\n\r\n5\r\nhello\r\n0\r\n\r\n",
+        )
+        .await;
+        api.set_max_response_size(Some(4));
+        assert!(matches!(
+            api.download_bytes(&url, None).await,
+            Err(PixivError::ResponseTooLarge { limit: 4 })
+        ));
+    }
+
+    #[cfg(feature = "stream")]
+    #[tokio::test]
+    async fn download_stream_yields_body() {
+        let (url, _) = serve_once(
+            "/img/5_p0.png",
+            b"HTTP/1.1 200 OK\r\ncontent-length: 5\r\nconnection: close\r\n\r\nhello",
+        )
+        .await;
+        let api = AppPixivAPI::new_no_auth();
+        let mut stream = std::pin::pin!(api.download_stream(&url, None));
+        let mut body = Vec::new();
+        while let Some(chunk) = crate::paging::next(stream.as_mut()).await {
+            body.extend_from_slice(&chunk.unwrap());
+        }
+        assert_eq!(body, b"hello");
+    }
+
+    #[cfg(feature = "fs")]
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("pixiv3-rs-test-{}-{name}", std::process::id()))
+    }
+
+    #[cfg(feature = "fs")]
+    #[tokio::test]
+    async fn download_file_reports_progress() {
+        let (url, _) = serve_once(
+            "/img/1_p0.png",
+            b"HTTP/1.1 200 OK\r\ncontent-length: 5\r\nconnection: close\r\n\r\nhello",
+        )
+        .await;
+        let dir = temp_dir("dl");
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        let api = AppPixivAPI::new_no_auth();
+        let mut last = None;
+        let mut progress = |p: DownloadProgress| last = Some(p);
+        let file = api
+            .download_file(&url, &dir, None, false, None, Some(&mut progress))
+            .await
+            .unwrap();
+        assert_eq!(file.path, dir.join("1_p0.png"));
+        assert_eq!(file.bytes_written, 5);
+        assert!(!file.skipped);
+        assert_eq!(
+            last,
+            Some(DownloadProgress {
+                downloaded: 5,
+                total: Some(5)
+            })
+        );
+        let again = api
+            .download_file(&url, &dir, None, false, None, None)
+            .await
+            .unwrap();
+        assert!(again.skipped);
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    #[cfg(feature = "fs")]
+    #[tokio::test]
+    async fn download_file_resumes_partial_file() {
+        let (url, request) = serve_once(
+            "/img/2_p0.png",
+            b"HTTP/1.1 206 Partial Content\r\ncontent-length: 2\r\ncontent-range: bytes 3-4/5\r\nconnection: close\r\n\r\nlo",
+        )
+        .await;
+        let dir = temp_dir("resume");
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        tokio::fs::write(dir.join("2_p0.png.part"), "hel")
+            .await
+            .unwrap();
+        tokio::fs::write(dir.join("2_p0.png.part.validator"), "\"v1\"")
+            .await
+            .unwrap();
+        let file = AppPixivAPI::new_no_auth()
+            .download_file(&url, &dir, None, false, None, None)
+            .await
+            .unwrap();
+        let request = request.await.unwrap();
+        assert!(request.contains("range: bytes=3-"), "{request}");
+        assert!(request.contains("if-range: \"v1\""), "{request}");
+        assert_eq!(file.bytes_written, 2);
+        assert_eq!(tokio::fs::read(&file.path).await.unwrap(), b"hello");
+        assert!(
+            !tokio::fs::try_exists(dir.join("2_p0.png.part"))
+                .await
+                .unwrap()
+        );
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    #[cfg(feature = "fs")]
+    #[tokio::test]
+    async fn download_file_checks_announced_size() {
+        let (url, _) = serve_once(
+            "/img/3_p0.png",
+            b"HTTP/1.1 206 Partial Content\r\ncontent-length: 1\r\ncontent-range: bytes 3-4/9\r\nconnection: close\r\n\r\nl",
+        )
+        .await;
+        let dir = temp_dir("short");
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        tokio::fs::write(dir.join("3_p0.png.part"), "hel")
+            .await
+            .unwrap();
+        tokio::fs::write(dir.join("3_p0.png.part.validator"), "\"v1\"")
+            .await
+            .unwrap();
+        let err = AppPixivAPI::new_no_auth()
+            .download_file(&url, &dir, None, false, None, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            PixivError::IncompleteDownload {
+                expected: 9,
+                received: 4
+            }
+        ));
+        assert!(err.is_retryable());
+        assert!(
+            tokio::fs::try_exists(dir.join("3_p0.png.part"))
+                .await
+                .unwrap()
+        );
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    /// Serve `responses` to consecutive requests on a local port; returns the URL of `path` and
+    /// the requests.
+    #[cfg(feature = "fs")]
+    async fn serve_each(
+        path: &str,
+        responses: &'static [&'static [u8]],
+    ) -> (String, tokio::task::JoinHandle<Vec<String>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}{path}", listener.local_addr().unwrap());
+        let requests = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for response in responses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = [0u8; 1024];
+                let n = stream.read(&mut request).await.unwrap();
+                let _ = stream.write_all(response).await;
+                requests.push(String::from_utf8_lossy(&request[..n]).to_lowercase());
+            }
+            requests
+        });
+        (url, requests)
+    }
+
+    #[cfg(feature = "fs")]
+    #[tokio::test]
+    async fn download_file_restarts_unverifiable_resumes() {
+        const FULL: &[u8] = b"HTTP/1.1 200 OK\r\ncontent-length: 5\r\netag: \"v2\"\r\nconnection: close\r\n\r\nhello";
+        let dir = temp_dir("restart");
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+
+        // Without a validator the leftover is not trusted.
+        tokio::fs::write(dir.join("6_p0.png.part"), "xxx")
+            .await
+            .unwrap();
+        let (url, requests) = serve_each("/img/6_p0.png", &[FULL]).await;
+        let api = AppPixivAPI::new_no_auth();
+        let file = api
+            .download_file(&url, &dir, None, true, None, None)
+            .await
+            .unwrap();
+        assert!(!requests.await.unwrap()[0].contains("range:"));
+        assert_eq!(tokio::fs::read(&file.path).await.unwrap(), b"hello");
+        assert!(
+            !tokio::fs::try_exists(dir.join("6_p0.png.part.validator"))
+                .await
+                .unwrap()
+        );
+
+        // A range other than the one asked for is not appended.
+        tokio::fs::write(dir.join("6_p0.png.part"), "xxx")
+            .await
+            .unwrap();
+        tokio::fs::write(dir.join("6_p0.png.part.validator"), "\"v1\"")
+            .await
+            .unwrap();
+        let (url, requests) = serve_each(
+            "/img/6_p0.png",
+            &[
+                b"HTTP/1.1 206 Partial Content\r\ncontent-length: 2\r\ncontent-range: bytes 0-1/5\r\nconnection: close\r\n\r\nhe",
+                FULL,
+            ],
+        )
+        .await;
+        let file = api
+            .download_file(&url, &dir, None, true, None, None)
+            .await
+            .unwrap();
+        let requests = requests.await.unwrap();
+        assert!(requests[0].contains("range: bytes=3-"));
+        assert!(!requests[1].contains("range:"));
+        assert_eq!(tokio::fs::read(&file.path).await.unwrap(), b"hello");
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    #[test]
+    fn referer_from_host() {
+        assert_eq!(
+            referer_for("https://i.pximg.net/img-original/img/1_p0.png"),
+            Some(APP_API_REFERER)
+        );
+        assert_eq!(
+            referer_for("https://www.pixiv.net/ajax/illust/1"),
+            Some(WEB_REFERER)
+        );
+        assert_eq!(referer_for("https://notpximg.net/1.png"), None);
+        assert_eq!(referer_for("not a url"), None);
+    }
+
+    #[test]
+    fn collision_policy_from_bool() {
+        assert_eq!(CollisionPolicy::from(true), CollisionPolicy::Overwrite);
+        assert_eq!(CollisionPolicy::from(false), CollisionPolicy::Skip);
+    }
+
+    #[cfg(feature = "fs")]
+    #[tokio::test]
+    async fn download_file_collision_policies() {
+        const OK: &[u8] = b"HTTP/1.1 200 OK\r\ncontent-length: 5\r\nconnection: close\r\n\r\nhello";
+        let dir = temp_dir("collision");
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        tokio::fs::write(dir.join("6_p0.png"), "hi").await.unwrap();
+        let api = AppPixivAPI::new_no_auth();
+
+        let (url, _) = serve_once("/img/6_p0.png", OK).await;
+        let renamed = api
+            .download_file(
+                &url,
+                &dir,
+                None,
+                CollisionPolicy::RenameWithSuffix,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(renamed.path, dir.join("6_p0 (1).png"));
+        assert_eq!(tokio::fs::read(&renamed.path).await.unwrap(), b"hello");
+
+        let (url, _) = serve_once("/img/6_p0.png", OK).await;
+        let err = api
+            .download_file(
+                &url,
+                &dir,
+                None,
+                CollisionPolicy::ErrorIfDifferentSize,
+                None,
+                None,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            PixivError::DownloadConflict {
+                local_size: 2,
+                remote_size: Some(5),
+                ..
+            }
+        ));
+        assert_eq!(tokio::fs::read(dir.join("6_p0.png")).await.unwrap(), b"hi");
+
+        let (url, _) = serve_once("/img/6_p0.png", OK).await;
+        let same = api
+            .download_file(
+                &url,
+                &dir,
+                Some("6_p0 (1).png"),
+                CollisionPolicy::ErrorIfDifferentSize,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(same.skipped);
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    #[cfg(all(feature = "stream", feature = "fs"))]
+    fn illust(id: u64, original: &str, visible: bool) -> IllustrationInfo {
+        serde_json::from_value(serde_json::json!({
+            "id": id, "title": "", "type": "illust", "caption": "", "restrict": 0,
+            "image_urls": {"square_medium": "", "medium": "", "large": ""},
+            "user": {"id": 1, "name": "", "account": "", "profile_image_urls": {"medium": ""}},
+            "tags": [], "tools": [], "create_date": "2024-01-01T12:00:00+09:00",
+            "page_count": 1, "width": 1, "height": 1, "sanity_level": 2, "x_restrict": 0,
+            "meta_single_page": {"original_image_url": original},
+            "is_bookmarked": false, "visible": visible, "is_muted": false
+        }))
+        .unwrap()
+    }
+
+    #[cfg(all(feature = "stream", feature = "fs"))]
+    #[tokio::test]
+    async fn download_all_reports_each_work() {
+        let (url, _) = serve_once(
+            "/img/7_p0.png",
+            b"HTTP/1.1 200 OK\r\ncontent-length: 5\r\nconnection: close\r\n\r\nhello",
+        )
+        .await;
+        let dir = temp_dir("all");
+        let source = async_stream::stream! {
+            yield Ok(illust(7, &url, true));
+            yield Ok(illust(8, "", false));
+            yield Err(PixivError::InvalidArgument { message: "source".to_string() });
+        };
+        let api = AppPixivAPI::new_no_auth();
+        let mut stream =
+            std::pin::pin!(api.download_all(source, &dir, ImageSize::Original, false, 1, false));
+        let mut saved = Vec::new();
+        let mut failed = Vec::new();
+        let mut source_error = false;
+        while let Some(item) = crate::paging::next(stream.as_mut()).await {
+            match item {
+                Ok(WorkDownload {
+                    illust,
+                    result: Ok(files),
+                }) => saved.push((illust.id, files.len())),
+                Ok(WorkDownload { illust, .. }) => failed.push(illust.id),
+                Err(_) => source_error = true,
+            }
+        }
+        assert_eq!(saved, [(7, 1)]);
+        assert_eq!(failed, [8]);
+        assert!(source_error);
+        assert_eq!(
+            tokio::fs::read(dir.join("7_p0.png")).await.unwrap(),
+            b"hello"
+        );
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+}
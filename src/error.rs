@@ -1,5 +1,9 @@
 //! Error type and shared types (port of pixivpy3.utils).
 
+use reqwest::StatusCode;
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+
 /// An error occurred in pixiv3-rs.
 #[derive(Debug, thiserror::Error)]
 #[non_exhaustive]
@@ -7,9 +11,36 @@ pub enum PixivError {
     /// I/O error.
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
-    /// Reqwest error.
+    /// Reqwest error not covered by [`Self::Timeout`], [`Self::Connect`] or [`Self::Tls`].
     #[error("reqwest error: {0}")]
-    Reqwest(#[from] reqwest::Error),
+    Reqwest(reqwest::Error),
+    /// The request or reading the response timed out.
+    #[error("request timed out: {0}")]
+    Timeout(#[source] reqwest::Error),
+    /// Could not connect to the server (DNS, refused or reset connection, proxy failure).
+    #[error("connection failed: {0}")]
+    Connect(#[source] reqwest::Error),
+    /// TLS handshake or certificate verification failed; retrying will not help.
+    #[error("TLS error: {0}")]
+    Tls(#[source] reqwest::Error),
+    /// An ugoira frame zip could not be read.
+    #[cfg(feature = "ugoira")]
+    #[error("zip error: {0}")]
+    Zip(#[from] zip::result::ZipError),
+    /// An ugoira frame could not be decoded, or the animation not encoded.
+    #[cfg(feature = "ugoira-encode")]
+    #[error("image error: {0}")]
+    Image(#[from] image::ImageError),
+    /// A middleware of the `reqwest_middleware` client failed the request.
+    #[cfg(feature = "middleware")]
+    #[error("middleware error: {0}")]
+    Middleware(Box<dyn std::error::Error + Send + Sync>),
+    /// Invalid argument or configuration value.
+    #[error("invalid argument: {message}")]
+    InvalidArgument {
+        /// What is wrong.
+        message: String,
+    },
     /// Token required without authentication method provided.
     #[error("token required without authentication method provided")]
     NoAuth,
@@ -21,11 +52,24 @@ pub enum PixivError {
         /// The message.
         message: String,
     },
+    /// The OAuth token endpoint rejected a refresh or code exchange, e.g. `invalid_grant` for a
+    /// revoked refresh token; log in again rather than retrying.
+    #[error("OAuth error {error}{}", .description.as_ref().map(|d| format!(": {d}")).unwrap_or_default())]
+    OAuth {
+        /// OAuth error code, e.g. `invalid_grant` or `invalid_client`.
+        error: String,
+        /// Human-readable description, if provided.
+        description: Option<String>,
+    },
     /// Response contains error.
-    #[error("response contains error: {body}")]
+    #[error("response contains error: {body}{}", in_context(.context))]
     ErrResponse {
+        /// The parsed error payload.
+        error: Box<PixivApiError>,
         /// The response body.
         body: String,
+        /// The response the error came from.
+        context: Option<Box<ResponseContext>>,
     },
     /// Unintelligible response.
     #[error("unintelligible response: {body}")]
@@ -34,28 +78,476 @@ pub enum PixivError {
         body: String,
     },
     /// Rate limited.
-    #[error("rate limited: {body}")]
+    #[error("rate limited: {body}{}", in_context(.context))]
     RateLimited {
         /// The response body.
         body: String,
+        /// How long the server asked to wait, from the `Retry-After` header.
+        retry_after: Option<std::time::Duration>,
+        /// The response the error came from.
+        context: Option<Box<ResponseContext>>,
+    },
+    /// The server answered with a non-success status and a body that is not an API error payload,
+    /// e.g. an HTML error page from a proxy or a 5xx.
+    #[error("unexpected HTTP status {status}: {body}{}", in_context(.context))]
+    UnexpectedStatus {
+        /// The HTTP status.
+        status: StatusCode,
+        /// The response body.
+        body: String,
+        /// The response the error came from.
+        context: Option<Box<ResponseContext>>,
     },
     /// Not found.
-    #[error("not found: {body}")]
+    #[error("not found: {body}{}", in_context(.context))]
     NotFound {
         /// The response body.
         body: String,
+        /// The response the error came from.
+        context: Option<Box<ResponseContext>>,
+    },
+    /// The client is shutting down and no longer accepts requests.
+    #[error("client is shutting down")]
+    ShuttingDown,
+    /// Circuit breaker is open after repeated failures; the request was not sent.
+    #[error("circuit open, retry in {retry_in:?}")]
+    CircuitOpen {
+        /// Time until the circuit lets a trial request through.
+        retry_in: std::time::Duration,
+    },
+    /// A paginated operation did not finish within its overall deadline.
+    #[error("deadline of {timeout:?} exceeded")]
+    DeadlineExceeded {
+        /// The configured deadline.
+        timeout: std::time::Duration,
+    },
+    /// Response body exceeds the configured size limit.
+    #[error("response body exceeds the limit of {limit} bytes")]
+    ResponseTooLarge {
+        /// The configured limit in bytes.
+        limit: usize,
+    },
+    /// A download ended before the size announced by the server was received. The partial file
+    /// is kept; downloading again resumes it only if the server sent an `ETag` or
+    /// `Last-Modified` validator and answers with a matching `Content-Range`, and restarts it
+    /// otherwise.
+    #[error("download incomplete: received {received} of {expected} bytes")]
+    IncompleteDownload {
+        /// Size announced by the server.
+        expected: u64,
+        /// Bytes on disk.
+        received: u64,
+    },
+    /// The target file of a download exists with a size different from the remote file.
+    #[error("{} already exists with {local_size} bytes, remote size is {}", .path.display(), .remote_size.map_or("unknown".to_string(), |s| s.to_string()))]
+    DownloadConflict {
+        /// The existing file.
+        path: std::path::PathBuf,
+        /// Size of the existing file.
+        local_size: u64,
+        /// Size announced by the server, if any.
+        remote_size: Option<u64>,
+    },
+    /// A generated endpoint method failed; wraps the underlying error with the endpoint name and
+    /// the request URL (credentials in the query redacted). Use [`PixivError::root`] to get at the
+    /// underlying error.
+    #[error("{endpoint} ({url}): {source}")]
+    InEndpoint {
+        /// Name of the endpoint method, e.g. `illust_detail`.
+        endpoint: &'static str,
+        /// The request URL, query included.
+        url: String,
+        /// The underlying error.
+        #[source]
+        source: Box<PixivError>,
     },
     /// Serde error.
-    #[error("serde error: {error}, body: {body}")]
+    #[error("serde error: {error}, body: {body}{}", in_context(.context))]
     Serde {
         /// The internal error.
         #[source]
         error: serde_json::Error,
         /// The response body.
         body: String,
+        /// The response the body came from, if any.
+        context: Option<Box<ResponseContext>>,
     },
 }
 
+impl From<reqwest::Error> for PixivError {
+    fn from(error: reqwest::Error) -> Self {
+        if is_tls_error(&error) {
+            Self::Tls(error)
+        } else if error.is_timeout() {
+            Self::Timeout(error)
+        } else if error.is_connect() {
+            Self::Connect(error)
+        } else {
+            Self::Reqwest(error)
+        }
+    }
+}
+
+#[cfg(feature = "middleware")]
+impl From<reqwest_middleware::Error> for PixivError {
+    fn from(error: reqwest_middleware::Error) -> Self {
+        match error {
+            reqwest_middleware::Error::Reqwest(error) => error.into(),
+            reqwest_middleware::Error::Middleware(error) => Self::Middleware(error.into()),
+        }
+    }
+}
+
+/// Whether a `rustls` error is somewhere in the source chain of `error`.
+fn is_tls_error(error: &reqwest::Error) -> bool {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(error);
+    while let Some(e) = source {
+        if e.is::<rustls::Error>() {
+            return true;
+        }
+        // `io::Error::source` skips the wrapped error itself, so step into it explicitly.
+        source = match e
+            .downcast_ref::<std::io::Error>()
+            .and_then(|io| io.get_ref())
+        {
+            Some(inner) => Some(inner),
+            None => e.source(),
+        };
+    }
+    false
+}
+
+/// Default for [`crate::AppPixivAPI::set_error_body_limit`]: response bodies kept in errors are cut to this many bytes.
+///
+/// [`crate::AppPixivAPI::set_error_body_limit`] 的默认值：错误中保存的响应体截断为该字节数。
+pub const DEFAULT_ERROR_BODY_LIMIT: usize = 4096;
+
+/// Cut `body` to at most `limit` bytes on a char boundary.
+pub(crate) fn truncate(body: &str, limit: usize) -> &str {
+    if body.len() <= limit {
+        return body;
+    }
+    let mut end = limit;
+    while !body.is_char_boundary(end) {
+        end -= 1;
+    }
+    &body[..end]
+}
+
+/// Placeholder for redacted values.
+pub(crate) const REDACTED: &str = "<redacted>";
+
+/// Param/form keys whose values are credentials.
+pub(crate) const SENSITIVE_KEYS: &[&str] = &[
+    "access_token",
+    "refresh_token",
+    "client_secret",
+    "code",
+    "code_verifier",
+    "password",
+];
+
+/// `url` with the values of [`SENSITIVE_KEYS`] in its query replaced by [`REDACTED`].
+fn redact_url(url: &str) -> String {
+    let Ok(mut parsed) = reqwest::Url::parse(url) else {
+        return url.to_string();
+    };
+    if !parsed
+        .query_pairs()
+        .any(|(key, _)| SENSITIVE_KEYS.contains(&key.as_ref()))
+    {
+        return url.to_string();
+    }
+    let pairs: Vec<(String, String)> = parsed
+        .query_pairs()
+        .map(|(key, value)| {
+            let value = if SENSITIVE_KEYS.contains(&key.as_ref()) {
+                REDACTED.into()
+            } else {
+                value.into_owned()
+            };
+            (key.into_owned(), value)
+        })
+        .collect();
+    parsed.query_pairs_mut().clear().extend_pairs(pairs);
+    parsed.into()
+}
+
+/// Response headers worth keeping for diagnostics; others (cookies in particular) are dropped.
+const CONTEXT_HEADERS: &[&str] = &["content-type", "date", "server", "cf-ray", "x-request-id"];
+
+/// Where an error response came from: HTTP status, final URL and diagnostic headers.
+///
+/// 错误响应的来源：HTTP 状态码、最终 URL 及用于诊断的响应头。
+#[derive(Debug, Clone)]
+pub struct ResponseContext {
+    /// HTTP status code.
+    pub status: StatusCode,
+    /// Final URL of the request, after redirects.
+    pub url: String,
+    /// Selected response headers (content type, date, server and request ids).
+    pub headers: HeaderMap,
+    /// Where the full response body was saved (feature `error-capture`).
+    pub body_path: Option<std::path::PathBuf>,
+}
+
+impl ResponseContext {
+    /// Capture the context of `response`.
+    pub(crate) fn of(response: &reqwest::Response) -> Box<Self> {
+        let headers = CONTEXT_HEADERS
+            .iter()
+            .filter_map(|&name| {
+                let value = response.headers().get(name)?;
+                Some((
+                    reqwest::header::HeaderName::from_static(name),
+                    value.clone(),
+                ))
+            })
+            .collect();
+        Box::new(Self {
+            status: response.status(),
+            url: response.url().to_string(),
+            headers,
+            body_path: None,
+        })
+    }
+}
+
+impl std::fmt::Display for ResponseContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "HTTP {} from {}", self.status, self.url)?;
+        for (name, value) in &self.headers {
+            write!(f, ", {name}: {}", value.to_str().unwrap_or("<binary>"))?;
+        }
+        if let Some(path) = &self.body_path {
+            write!(f, ", body saved to {}", path.display())?;
+        }
+        Ok(())
+    }
+}
+
+fn in_context(context: &Option<Box<ResponseContext>>) -> String {
+    context
+        .as_ref()
+        .map(|context| format!(" ({context})"))
+        .unwrap_or_default()
+}
+
+/// Error payload of a failed API call: `{"error": {"user_message", "message", "reason", "user_message_details"}}`.
+///
+/// API 调用失败时返回的错误内容。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PixivApiError {
+    /// Message meant for end users, often localized.
+    #[serde(default)]
+    pub user_message: Option<String>,
+    /// Technical message.
+    #[serde(default)]
+    pub message: Option<String>,
+    /// Short machine-readable reason.
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// Extra details for `user_message`, usually an empty object.
+    #[serde(default)]
+    pub user_message_details: serde_json::Value,
+}
+
+impl PixivApiError {
+    /// Parse the `error` member of a response body; `None` if the body has none.
+    ///
+    /// A bare string `error` is kept as `message`.
+    ///
+    /// 解析响应体中的 `error` 字段；没有该字段时返回 `None`。若 `error` 为字符串则作为 `message` 保存。
+    pub fn from_body(body: &str) -> Option<Self> {
+        let mut parsed: serde_json::Value = serde_json::from_str(body).ok()?;
+        match parsed.get_mut("error")?.take() {
+            serde_json::Value::String(message) => Some(Self {
+                message: Some(message),
+                ..Self::default()
+            }),
+            error => Some(serde_json::from_value(error).unwrap_or_default()),
+        }
+    }
+
+    /// The most descriptive non-empty message: `user_message`, then `message`, then `reason`.
+    ///
+    /// 最具描述性的非空消息：依次为 `user_message`、`message`、`reason`。
+    pub fn description(&self) -> Option<&str> {
+        [&self.user_message, &self.message, &self.reason]
+            .into_iter()
+            .filter_map(|s| s.as_deref())
+            .find(|s| !s.is_empty())
+    }
+}
+
+impl PixivError {
+    /// The underlying error, without [`PixivError::InEndpoint`] wrappers.
+    ///
+    /// 去掉 [`PixivError::InEndpoint`] 包装后的底层错误。
+    pub fn root(&self) -> &PixivError {
+        match self {
+            Self::InEndpoint { source, .. } => source.root(),
+            _ => self,
+        }
+    }
+
+    /// Like [`Self::root`], by value.
+    ///
+    /// 同 [`Self::root`]，但获取所有权。
+    pub fn into_root(self) -> PixivError {
+        match self {
+            Self::InEndpoint { source, .. } => source.into_root(),
+            _ => self,
+        }
+    }
+
+    /// Parse an error body of the OAuth token endpoint: either the standard
+    /// `{"error", "error_description"}` or Pixiv's `{"has_error", "errors": {"system": {"message"}}}`.
+    pub(crate) fn from_oauth_body(body: &str) -> Option<Self> {
+        let parsed: serde_json::Value = serde_json::from_str(body).ok()?;
+        let system_message = parsed
+            .pointer("/errors/system/message")
+            .and_then(|m| m.as_str());
+        let error = parsed
+            .get("error")
+            .and_then(|e| e.as_str())
+            .or(system_message)?;
+        let description = parsed
+            .get("error_description")
+            .and_then(|d| d.as_str())
+            .or(system_message)
+            .filter(|d| *d != error);
+        Some(Self::OAuth {
+            error: error.to_string(),
+            description: description.map(str::to_string),
+        })
+    }
+
+    /// Cut the response body kept in the error to `limit` bytes, noting the original length.
+    pub(crate) fn truncate_body(&mut self, limit: usize) {
+        if let Some((body, _)) = self.body_and_context_mut() {
+            if body.len() > limit {
+                let total = body.len();
+                let kept = truncate(body, limit).len();
+                body.truncate(kept);
+                body.push_str(&format!("... [truncated, {total} bytes in total]"));
+            }
+        }
+    }
+
+    /// The response body and context held by errors caused by an HTTP response.
+    pub(crate) fn body_and_context_mut(
+        &mut self,
+    ) -> Option<(&mut String, Option<&mut ResponseContext>)> {
+        match self {
+            Self::ErrResponse { body, context, .. }
+            | Self::RateLimited { body, context, .. }
+            | Self::UnexpectedStatus { body, context, .. }
+            | Self::NotFound { body, context }
+            | Self::Serde { body, context, .. } => Some((body, context.as_deref_mut())),
+            _ => None,
+        }
+    }
+
+    /// Wrap in [`PixivError::InEndpoint`], preferring the URL the request actually went to over `url`.
+    pub(crate) fn in_endpoint(self, endpoint: &'static str, url: &str) -> Self {
+        if matches!(self, Self::InEndpoint { .. }) {
+            return self;
+        }
+        let effective = match &self {
+            Self::Reqwest(e) | Self::Timeout(e) | Self::Connect(e) | Self::Tls(e) => {
+                e.url().map(|u| u.as_str())
+            }
+            _ => self.context().map(|c| c.url.as_str()),
+        };
+        Self::InEndpoint {
+            endpoint,
+            url: redact_url(effective.unwrap_or(url)),
+            source: Box::new(self),
+        }
+    }
+
+    /// The response the error came from, for errors caused by an HTTP response.
+    ///
+    /// 由 HTTP 响应引起的错误所对应的响应信息。
+    pub fn context(&self) -> Option<&ResponseContext> {
+        match self.root() {
+            Self::ErrResponse { context, .. }
+            | Self::RateLimited { context, .. }
+            | Self::UnexpectedStatus { context, .. }
+            | Self::NotFound { context, .. }
+            | Self::Serde { context, .. } => context.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// How long to wait before trying again, if known: the `Retry-After` of a rate limit, or the
+    /// remaining cooldown of an open circuit.
+    ///
+    /// 重试前应等待的时间（如已知）：限流响应的 `Retry-After`，或熔断器剩余的冷却时间。
+    pub fn retry_after(&self) -> Option<std::time::Duration> {
+        match self.root() {
+            Self::RateLimited { retry_after, .. } => *retry_after,
+            Self::CircuitOpen { retry_in } => Some(*retry_in),
+            _ => None,
+        }
+    }
+
+    /// Whether the error is likely temporary, so retrying the same request may succeed: network
+    /// failures, rate limits and server errors (5xx).
+    ///
+    /// 错误是否可能是暂时的、重试同一请求可能成功：网络故障、限流与服务端错误（5xx）。
+    pub fn is_retryable(&self) -> bool {
+        match self.root() {
+            Self::Timeout(_) | Self::Connect(_) | Self::IncompleteDownload { .. } => true,
+            Self::Reqwest(e) => {
+                e.is_request() || e.is_body() || e.status().is_some_and(|s| s.is_server_error())
+            }
+            _ => {
+                self.is_rate_limited()
+                    || self
+                        .context()
+                        .is_some_and(|context| context.status.is_server_error())
+            }
+        }
+    }
+
+    /// Whether the request was rejected for rate limiting, either with HTTP 429 or with Pixiv's
+    /// `Rate Limit` error payload.
+    ///
+    /// 请求是否因限流被拒绝（HTTP 429 或 Pixiv 的 `Rate Limit` 错误内容）。
+    pub fn is_rate_limited(&self) -> bool {
+        match self.root() {
+            Self::RateLimited { .. } => true,
+            Self::ErrResponse { error, .. } => error
+                .message
+                .as_deref()
+                .is_some_and(|m| m.eq_ignore_ascii_case("rate limit")),
+            _ => false,
+        }
+    }
+
+    /// Whether authentication is missing or was rejected, so retrying without re-authenticating
+    /// is pointless.
+    ///
+    /// 是否缺少认证或认证被拒绝；不重新认证的情况下重试没有意义。
+    pub fn is_auth_error(&self) -> bool {
+        match self.root() {
+            Self::NoAuth | Self::BadAccessToken { .. } | Self::OAuth { .. } => true,
+            Self::ErrResponse { error, context, .. } => {
+                context
+                    .as_ref()
+                    .is_some_and(|c| c.status == StatusCode::UNAUTHORIZED)
+                    || error
+                        .message
+                        .as_deref()
+                        .is_some_and(|m| m.contains("OAuth"))
+            }
+            _ => false,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -77,17 +569,233 @@ mod tests {
     #[test]
     fn display_err_response() {
         let err = PixivError::ErrResponse {
+            error: Box::default(),
             body: "invalid request".to_string(),
+            context: None,
         };
         assert!(err.to_string().contains("invalid request"));
         assert!(err.to_string().contains("error"));
     }
 
+    #[test]
+    fn parse_api_error_payload() {
+        let body = r#"{"error": {"user_message": "", "message": "Rate Limit", "reason": "", "user_message_details": {}}}"#;
+        let error = PixivApiError::from_body(body).unwrap();
+        assert_eq!(error.message.as_deref(), Some("Rate Limit"));
+        assert_eq!(error.description(), Some("Rate Limit"));
+
+        let error = PixivApiError::from_body(r#"{"error": "invalid_grant"}"#).unwrap();
+        assert_eq!(error.description(), Some("invalid_grant"));
+        assert!(PixivApiError::from_body(r#"{"illust": {}}"#).is_none());
+    }
+
+    #[test]
+    fn display_includes_context() {
+        let mut headers = HeaderMap::new();
+        headers.insert("cf-ray", "abc-NRT".parse().unwrap());
+        let err = PixivError::NotFound {
+            body: String::new(),
+            context: Some(Box::new(ResponseContext {
+                status: StatusCode::NOT_FOUND,
+                url: "https://app-api.pixiv.net/v1/illust/detail?illust_id=1".to_string(),
+                headers,
+                body_path: None,
+            })),
+        };
+        assert_eq!(err.context().unwrap().status, StatusCode::NOT_FOUND);
+        let message = err.to_string();
+        assert!(message.contains(
+            "HTTP 404 Not Found from https://app-api.pixiv.net/v1/illust/detail?illust_id=1"
+        ));
+        assert!(message.contains("cf-ray: abc-NRT"));
+    }
+
+    #[test]
+    fn classification() {
+        assert!(PixivError::NoAuth.is_auth_error());
+        assert!(!PixivError::NoAuth.is_retryable());
+
+        let oauth = PixivError::ErrResponse {
+            error: Box::new(PixivApiError {
+                message: Some("Error occurred at the OAuth process. Please check your Access Token to fix this. Error Message: invalid_grant".into()),
+                ..PixivApiError::default()
+            }),
+            body: String::new(),
+            context: None,
+        };
+        assert!(oauth.is_auth_error());
+        assert!(!oauth.is_retryable());
+
+        let rate_limit = PixivError::ErrResponse {
+            error: Box::new(PixivApiError {
+                message: Some("Rate Limit".into()),
+                ..PixivApiError::default()
+            }),
+            body: String::new(),
+            context: None,
+        };
+        assert!(rate_limit.is_rate_limited());
+        assert!(rate_limit.is_retryable());
+
+        let server_error = PixivError::UnintelligibleResponse {
+            body: String::new(),
+        };
+        assert!(!server_error.is_retryable());
+        let server_error = PixivError::NotFound {
+            body: String::new(),
+            context: Some(Box::new(ResponseContext {
+                status: StatusCode::BAD_GATEWAY,
+                url: String::new(),
+                headers: HeaderMap::new(),
+                body_path: None,
+            })),
+        };
+        assert!(server_error.is_retryable());
+    }
+
+    #[tokio::test]
+    async fn classify_network_errors() {
+        use tokio::io::AsyncWriteExt;
+        use tokio::net::TcpListener;
+
+        // Nothing listens on the port of a dropped listener.
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let err: PixivError = reqwest::get(format!("http://{addr}"))
+            .await
+            .unwrap_err()
+            .into();
+        assert!(matches!(err, PixivError::Connect(_)), "{err}");
+        assert!(err.is_retryable());
+
+        // Accepts connections but never answers.
+        let silent = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = silent.local_addr().unwrap();
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_millis(100))
+            .build()
+            .unwrap();
+        let err: PixivError = client
+            .get(format!("http://{addr}"))
+            .send()
+            .await
+            .unwrap_err()
+            .into();
+        assert!(matches!(err, PixivError::Timeout(_)), "{err}");
+
+        // Answers a TLS handshake with plain text.
+        let garbage = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = garbage.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = garbage.accept().await.unwrap();
+            let _ = stream.write_all(b"HTTP/1.1 400 Bad Request\r\n\r\n").await;
+        });
+        let err: PixivError = reqwest::get(format!("https://{addr}"))
+            .await
+            .unwrap_err()
+            .into();
+        assert!(matches!(err, PixivError::Tls(_)), "{err}");
+        assert!(!err.is_retryable());
+        drop(silent);
+    }
+
+    #[test]
+    fn in_endpoint_wraps_once_and_redacts() {
+        let err = PixivError::NoAuth
+            .in_endpoint(
+                "user_detail",
+                "https://app-api.pixiv.net/v1/user/detail?user_id=11&access_token=secret",
+            )
+            .in_endpoint("outer", "https://example.com");
+        let PixivError::InEndpoint { endpoint, url, .. } = &err else {
+            panic!("not wrapped: {err}");
+        };
+        assert_eq!(*endpoint, "user_detail");
+        assert!(url.contains("user_id=11"));
+        assert!(!url.contains("secret"));
+        assert!(matches!(err.root(), PixivError::NoAuth));
+        assert!(err.is_auth_error());
+        assert!(
+            err.to_string()
+                .starts_with("user_detail (https://app-api.pixiv.net/v1/user/detail?")
+        );
+    }
+
+    #[test]
+    fn parse_oauth_errors() {
+        let err = PixivError::from_oauth_body(
+            r#"{"has_error": true, "errors": {"system": {"message": "Invalid refresh token", "code": 1508}}, "error": "invalid_grant"}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            &err,
+            PixivError::OAuth { error, description: Some(d) } if error == "invalid_grant" && d == "Invalid refresh token"
+        ));
+        assert!(err.is_auth_error());
+        assert_eq!(
+            err.to_string(),
+            "OAuth error invalid_grant: Invalid refresh token"
+        );
+
+        let err = PixivError::from_oauth_body(r#"{"error": "invalid_client"}"#).unwrap();
+        assert_eq!(err.to_string(), "OAuth error invalid_client");
+        assert!(PixivError::from_oauth_body(r#"{"access_token": "a"}"#).is_none());
+    }
+
+    #[test]
+    fn truncate_respects_char_boundary() {
+        assert_eq!(truncate("事務局", 4), "事");
+        assert_eq!(truncate("abc", 10), "abc");
+    }
+
+    #[test]
+    fn truncate_body_notes_length() {
+        let mut err = PixivError::NotFound {
+            body: "事務局".repeat(10),
+            context: None,
+        };
+        err.truncate_body(4);
+        assert!(
+            matches!(&err, PixivError::NotFound { body, .. } if body == "事... [truncated, 90 bytes in total]")
+        );
+    }
+
+    #[test]
+    fn display_response_too_large() {
+        let err = PixivError::ResponseTooLarge { limit: 1024 };
+        assert!(err.to_string().contains("1024 bytes"));
+    }
+
+    #[test]
+    fn display_deadline_exceeded() {
+        let err = PixivError::DeadlineExceeded {
+            timeout: std::time::Duration::from_secs(3),
+        };
+        assert!(err.to_string().contains("3s"));
+    }
+
     #[test]
     fn display_rate_limited() {
         let err = PixivError::RateLimited {
             body: "too many requests".to_string(),
+            retry_after: Some(std::time::Duration::from_secs(5)),
+            context: None,
         };
         assert!(err.to_string().contains("too many requests"));
+        assert_eq!(err.retry_after(), Some(std::time::Duration::from_secs(5)));
+    }
+
+    #[cfg(feature = "middleware")]
+    #[test]
+    fn middleware_errors_keep_their_source() {
+        let error = PixivError::from(reqwest_middleware::Error::middleware(
+            std::io::Error::other("cache unavailable"),
+        ));
+        assert!(matches!(error, PixivError::Middleware(_)));
+        assert!(error.to_string().contains("cache unavailable"));
+        assert!(!error.is_retryable());
     }
 }
@@ -0,0 +1,273 @@
+//! Incremental JSON Lines export of models, with optional file rotation.
+//!
+//! 以 JSON Lines 格式增量导出模型数据，支持按大小或日期切分文件。
+
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+use chrono::{NaiveDate, Utc};
+use serde::Serialize;
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncWriteExt, BufWriter};
+
+use crate::PixivError;
+
+/// When a [`JsonlWriter`] starts a new file.
+///
+/// [`JsonlWriter`] 切换到新文件的时机。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Rotation {
+    /// Write everything to `{prefix}.jsonl`.
+    ///
+    /// 全部写入 `{prefix}.jsonl`。
+    Never,
+    /// Start `{prefix}-{n}.jsonl` with the next `n` once a file reaches this many bytes.
+    ///
+    /// 文件达到该字节数后切换到编号递增的 `{prefix}-{n}.jsonl`。
+    BySize(u64),
+    /// Write to `{prefix}-{YYYY-MM-DD}.jsonl` by the current UTC date.
+    ///
+    /// 按当前 UTC 日期写入 `{prefix}-{YYYY-MM-DD}.jsonl`。
+    Daily,
+}
+
+/// Appends serialized items as JSON Lines to files in a directory.
+///
+/// Existing files are appended to, so an interrupted export can be resumed with the same
+/// settings. Call [`JsonlWriter::finish`] (or at least [`JsonlWriter::flush`]) when done, as
+/// output is buffered.
+///
+/// 将序列化后的数据以 JSON Lines 格式追加写入目录中的文件；已存在的文件会被追加，便于中断后继续导出。
+/// 输出带缓冲，结束时请调用 [`JsonlWriter::finish`]（或至少 [`JsonlWriter::flush`]）。
+pub struct JsonlWriter<T> {
+    dir: PathBuf,
+    prefix: String,
+    rotation: Rotation,
+    file: Option<BufWriter<File>>,
+    path: Option<PathBuf>,
+    size: u64,
+    index: u32,
+    date: Option<NaiveDate>,
+    _item: PhantomData<fn(&T)>,
+}
+
+impl<T: Serialize> JsonlWriter<T> {
+    /// Create a writer for files named after `prefix` in `dir`, creating `dir` if needed.
+    ///
+    /// 创建写入 `dir` 下以 `prefix` 命名文件的写入器，必要时创建目录。
+    pub async fn create(
+        dir: impl AsRef<Path>,
+        prefix: impl Into<String>,
+        rotation: Rotation,
+    ) -> Result<Self, PixivError> {
+        let dir = dir.as_ref().to_path_buf();
+        tokio::fs::create_dir_all(&dir).await?;
+        Ok(Self {
+            dir,
+            prefix: prefix.into(),
+            rotation,
+            file: None,
+            path: None,
+            size: 0,
+            index: 0,
+            date: None,
+            _item: PhantomData,
+        })
+    }
+
+    /// The file currently written to, if any item has been written.
+    ///
+    /// 当前写入的文件路径（尚未写入任何数据时为 `None`）。
+    pub fn current_path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Serialize `item` and append it as one line.
+    ///
+    /// 序列化 `item` 并作为一行追加写入。
+    pub async fn write(&mut self, item: &T) -> Result<(), PixivError> {
+        self.write_on(item, Utc::now().date_naive()).await
+    }
+
+    async fn write_on(&mut self, item: &T, today: NaiveDate) -> Result<(), PixivError> {
+        let mut line = serde_json::to_vec(item).map_err(|error| PixivError::Serde {
+            error,
+            body: String::new(),
+            context: None,
+        })?;
+        line.push(b'\n');
+
+        let rotate = match self.rotation {
+            Rotation::Never => false,
+            Rotation::BySize(limit) => self.size > 0 && self.size + line.len() as u64 > limit,
+            Rotation::Daily => self.date != Some(today),
+        };
+        if rotate || self.file.is_none() {
+            if rotate && matches!(self.rotation, Rotation::BySize(_)) {
+                self.index += 1;
+            }
+            self.open(today, line.len() as u64).await?;
+        }
+
+        let file = self
+            .file
+            .as_mut()
+            .ok_or_else(|| PixivError::InvalidArgument {
+                message: "JSONL writer has no open file".to_string(),
+            })?;
+        file.write_all(&line).await?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// Open the file for `today`, skipping size-rotated files that cannot take `incoming` more bytes.
+    async fn open(&mut self, today: NaiveDate, incoming: u64) -> Result<(), PixivError> {
+        if let Some(mut file) = self.file.take() {
+            file.flush().await?;
+        }
+        loop {
+            let path = self.dir.join(self.file_name(today));
+            let size = match tokio::fs::metadata(&path).await {
+                Ok(meta) => meta.len(),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => 0,
+                Err(e) => return Err(e.into()),
+            };
+            if let Rotation::BySize(limit) = self.rotation {
+                if size > 0 && size + incoming > limit {
+                    self.index += 1;
+                    continue;
+                }
+            }
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .await?;
+            self.file = Some(BufWriter::new(file));
+            self.path = Some(path);
+            self.size = size;
+            self.date = Some(today);
+            return Ok(());
+        }
+    }
+
+    fn file_name(&self, today: NaiveDate) -> String {
+        match self.rotation {
+            Rotation::Never => format!("{}.jsonl", self.prefix),
+            Rotation::BySize(_) => format!("{}-{}.jsonl", self.prefix, self.index),
+            Rotation::Daily => format!("{}-{}.jsonl", self.prefix, today.format("%Y-%m-%d")),
+        }
+    }
+
+    /// Write every item of `stream`, stopping at the first error; returns the number of items written.
+    ///
+    /// 写入 `stream` 的全部元素，遇到错误即停止；返回写入的条数。
+    #[cfg(feature = "stream")]
+    pub async fn write_stream<S>(&mut self, stream: S) -> Result<usize, PixivError>
+    where
+        S: futures_core::stream::Stream<Item = Result<T, PixivError>>,
+    {
+        let mut stream = std::pin::pin!(stream);
+        let mut count = 0;
+        while let Some(item) = crate::paging::next(stream.as_mut()).await {
+            self.write(&item?).await?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Flush buffered output to the current file.
+    ///
+    /// 将缓冲的数据刷入当前文件。
+    pub async fn flush(&mut self) -> Result<(), PixivError> {
+        if let Some(file) = self.file.as_mut() {
+            file.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Flush and close the writer.
+    ///
+    /// 刷新并关闭写入器。
+    pub async fn finish(mut self) -> Result<(), PixivError> {
+        self.flush().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("pixiv3-rs-test-{}-{name}", std::process::id()))
+    }
+
+    async fn read_lines(path: &Path) -> Vec<String> {
+        let content = tokio::fs::read_to_string(path).await.unwrap();
+        content.lines().map(str::to_string).collect()
+    }
+
+    #[tokio::test]
+    async fn writes_lines_and_appends() {
+        let dir = temp_dir("jsonl-never");
+        let mut writer = JsonlWriter::create(&dir, "users", Rotation::Never)
+            .await
+            .unwrap();
+        writer.write(&1u64).await.unwrap();
+        writer.write(&2u64).await.unwrap();
+        writer.finish().await.unwrap();
+
+        let mut writer = JsonlWriter::create(&dir, "users", Rotation::Never)
+            .await
+            .unwrap();
+        writer.write(&3u64).await.unwrap();
+        let path = writer.current_path().unwrap().to_path_buf();
+        writer.finish().await.unwrap();
+
+        assert_eq!(read_lines(&path).await, vec!["1", "2", "3"]);
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn rotates_by_size() {
+        let dir = temp_dir("jsonl-size");
+        let mut writer = JsonlWriter::create(&dir, "ids", Rotation::BySize(8))
+            .await
+            .unwrap();
+        for id in [100u64, 200, 300] {
+            writer.write(&id).await.unwrap();
+        }
+        writer.finish().await.unwrap();
+
+        assert_eq!(
+            read_lines(&dir.join("ids-0.jsonl")).await,
+            vec!["100", "200"]
+        );
+        assert_eq!(read_lines(&dir.join("ids-1.jsonl")).await, vec!["300"]);
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn rotates_daily() {
+        let dir = temp_dir("jsonl-daily");
+        let day1 = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let day2 = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        let mut writer = JsonlWriter::create(&dir, "feed", Rotation::Daily)
+            .await
+            .unwrap();
+        writer.write_on(&"a", day1).await.unwrap();
+        writer.write_on(&"b", day2).await.unwrap();
+        writer.finish().await.unwrap();
+
+        assert_eq!(
+            read_lines(&dir.join("feed-2024-01-01.jsonl")).await,
+            vec!["\"a\""]
+        );
+        assert_eq!(
+            read_lines(&dir.join("feed-2024-01-02.jsonl")).await,
+            vec!["\"b\""]
+        );
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+}
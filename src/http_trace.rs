@@ -0,0 +1,131 @@
+//! Trace-level request/response logging with redaction of credentials (feature `http-trace`).
+
+use kv_pairs::KVPairs;
+use reqwest::header::{AUTHORIZATION, COOKIE, HeaderMap, SET_COOKIE};
+
+use crate::trace;
+
+/// Response bodies longer than this are truncated in trace logs.
+pub(crate) const TRACE_BODY_LIMIT: usize = 1024;
+
+use crate::error::{REDACTED, SENSITIVE_KEYS, truncate};
+
+fn format_headers(headers: &HeaderMap) -> String {
+    let entries: Vec<String> = headers
+        .iter()
+        .map(|(name, value)| {
+            if name == AUTHORIZATION || name == COOKIE || name == SET_COOKIE {
+                format!("{name}: {REDACTED}")
+            } else {
+                format!("{name}: {}", value.to_str().unwrap_or("<binary>"))
+            }
+        })
+        .collect();
+    format!("{{{}}}", entries.join(", "))
+}
+
+fn format_pairs(pairs: &KVPairs<'_>) -> String {
+    let entries: Vec<String> = pairs
+        .iter()
+        .map(|(key, value)| {
+            if SENSITIVE_KEYS.contains(key) {
+                format!("{key}={REDACTED}")
+            } else {
+                format!("{key}={value}")
+            }
+        })
+        .collect();
+    format!("[{}]", entries.join(", "))
+}
+
+/// Log an outgoing request with credentials redacted.
+pub(crate) fn trace_request(
+    method: &str,
+    url: &str,
+    headers: &HeaderMap,
+    params: Option<&KVPairs<'_>>,
+    data: Option<&KVPairs<'_>>,
+) {
+    trace!(
+        "request {method} {url} headers={} params={} data={}",
+        format_headers(headers),
+        params.map(format_pairs).unwrap_or_default(),
+        data.map(format_pairs).unwrap_or_default(),
+    );
+}
+
+/// Log a response body, truncated to [`TRACE_BODY_LIMIT`] bytes and with token fields redacted.
+pub(crate) fn trace_response(status: reqwest::StatusCode, url: &str, body: &str) {
+    let shown = truncate(&redact_body(body), TRACE_BODY_LIMIT).to_string();
+    trace!(
+        "response {status} {url} ({} bytes): {shown}{}",
+        body.len(),
+        if body.len() > TRACE_BODY_LIMIT {
+            "..."
+        } else {
+            ""
+        },
+    );
+}
+
+/// Redact the values of sensitive JSON string fields in `body` (e.g. `"access_token": "..."`).
+fn redact_body(body: &str) -> String {
+    let mut redacted = body.to_string();
+    for key in SENSITIVE_KEYS {
+        let pattern = format!("\"{key}\":");
+        let mut search_from = 0;
+        while let Some(pos) = redacted[search_from..].find(&pattern) {
+            let value_start = search_from + pos + pattern.len();
+            let rest = &redacted[value_start..];
+            let Some(open) = rest.find('"') else { break };
+            if !rest[..open].trim().is_empty() {
+                search_from = value_start;
+                continue;
+            }
+            let Some(close) = rest[open + 1..].find('"') else {
+                break;
+            };
+            let range = value_start + open + 1..value_start + open + 1 + close;
+            redacted.replace_range(range, REDACTED);
+            search_from = value_start + open + 1 + REDACTED.len() + 1;
+        }
+    }
+    redacted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kv_pairs::kv_pairs;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn headers_redact_authorization() {
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer secret"));
+        headers.insert("app-os", HeaderValue::from_static("ios"));
+        let formatted = format_headers(&headers);
+        assert!(!formatted.contains("secret"));
+        assert!(formatted.contains("app-os: ios"));
+    }
+
+    #[test]
+    fn pairs_redact_tokens() {
+        let pairs = kv_pairs![
+            "refresh_token" => "secret",
+            "grant_type" => "refresh_token",
+        ];
+        let formatted = format_pairs(&pairs);
+        assert!(!formatted.contains("secret"));
+        assert!(formatted.contains("grant_type=refresh_token"));
+    }
+
+    #[test]
+    fn body_redacts_token_fields() {
+        let body = r#"{"access_token": "aaa", "expires_in": 3600, "refresh_token":"bbb"}"#;
+        let redacted = redact_body(body);
+        assert!(!redacted.contains("aaa"));
+        assert!(!redacted.contains("bbb"));
+        assert!(redacted.contains("3600"));
+    }
+}
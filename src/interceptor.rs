@@ -0,0 +1,74 @@
+//! Hooks run around every HTTP request sent by `do_api_request`.
+//!
+//! `do_api_request` 发出的每个 HTTP 请求前后执行的钩子。
+
+use std::time::Duration;
+
+use kv_pairs::KVPairs;
+use reqwest::StatusCode;
+use reqwest::header::HeaderMap;
+
+use crate::PixivError;
+use crate::aapi::HttpMethod;
+
+/// A request about to be sent. Interceptors may change any part of it.
+///
+/// 即将发出的请求；拦截器可以修改其任意部分。
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct RequestParts<'a> {
+    /// HTTP method.
+    pub method: HttpMethod,
+    /// Full request URL, without the query parameters.
+    pub url: String,
+    /// Request headers, including the app and `Authorization` headers.
+    pub headers: HeaderMap,
+    /// Query parameters.
+    pub params: Option<KVPairs<'a>>,
+    /// Form body.
+    pub data: Option<KVPairs<'a>>,
+}
+
+/// The outcome of a request, as seen by [`Interceptor::after`].
+///
+/// 请求的结果，供 [`Interceptor::after`] 查看。
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct ResponseMeta<'a> {
+    /// HTTP method.
+    pub method: HttpMethod,
+    /// Request URL, after all interceptors ran.
+    pub url: &'a str,
+    /// Response status; `None` if no response was received.
+    pub status: Option<StatusCode>,
+    /// Response headers; `None` if no response was received.
+    pub headers: Option<&'a HeaderMap>,
+    /// The error, if the request failed before a response was received.
+    pub error: Option<&'a PixivError>,
+    /// Time from sending the request until the response headers arrived.
+    pub elapsed: Duration,
+}
+
+/// Request/response hook registered with [`AppPixivAPI::add_interceptor`](crate::AppPixivAPI::add_interceptor).
+///
+/// `before` hooks run in registration order, `after` hooks in reverse order. Both run once per
+/// HTTP request, so a request that fails over to another host or is retried passes through them
+/// again.
+///
+/// 通过 [`AppPixivAPI::add_interceptor`](crate::AppPixivAPI::add_interceptor) 注册的请求/响应钩子。
+/// `before` 按注册顺序执行，`after` 按相反顺序执行；每个 HTTP 请求各执行一次，故切换主机或重试时会再次执行。
+pub trait Interceptor: Send + Sync {
+    /// Called before the request is sent.
+    ///
+    /// 在请求发出前调用。
+    fn before(&self, request: &mut RequestParts<'_>) {
+        let _ = request;
+    }
+
+    /// Called after the response headers arrive, or the request fails.
+    ///
+    /// 在收到响应头或请求失败后调用。
+    fn after(&self, response: &ResponseMeta<'_>) {
+        let _ = response;
+    }
+}
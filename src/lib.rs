@@ -7,13 +7,51 @@
 #![deny(clippy::unwrap_used)]
 
 pub mod aapi;
+#[cfg(all(feature = "stream", feature = "fs"))]
+pub mod backup;
+pub mod batch;
+#[cfg(feature = "stream")]
+pub mod bookmark_sync;
+pub mod builder;
+#[cfg(feature = "cache")]
+pub mod cache;
+pub mod circuit_breaker;
+#[cfg(feature = "stream")]
+pub mod crawler;
+pub mod cursor;
+pub mod device;
+pub mod download;
 pub mod error;
+#[cfg(feature = "fs")]
+pub mod export;
+#[cfg(feature = "http-trace")]
+mod http_trace;
+pub mod interceptor;
 mod log;
+pub mod login;
+#[cfg(all(feature = "stream", feature = "fs"))]
+pub mod mirror;
 pub mod models;
+pub mod mute;
+pub mod next_url;
+pub mod novel;
+#[cfg(feature = "stream")]
+pub mod paging;
 pub mod params;
+#[cfg(feature = "stream")]
+pub mod ranking;
+pub mod request;
+pub mod retry;
+pub mod search;
+mod shutdown;
 pub mod token_manager;
+pub mod token_store;
+#[cfg(feature = "ugoira")]
+pub mod ugoira;
+pub mod webapi;
 
 pub use crate::aapi::AppPixivAPI;
+pub use crate::builder::AppPixivAPIBuilder;
 pub use crate::error::PixivError;
 pub(crate) use crate::log::*;
 pub use crate::token_manager::TokenManager;
@@ -0,0 +1,152 @@
+//! PKCE login flow for obtaining the initial refresh token.
+//!
+//! Open [`PkceLogin::login_url`] in a browser and log in; Pixiv then redirects to a `pixiv://`
+//! URL carrying a `code` query parameter (visible in the browser's developer tools), which
+//! [`PkceLogin::exchange`] trades for tokens within a few minutes.
+//!
+//! 用于获取初始 refresh token 的 PKCE 登录流程：在浏览器中打开 [`PkceLogin::login_url`] 并登录，
+//! Pixiv 会重定向到带 `code` 查询参数的 `pixiv://` URL（可在浏览器开发者工具中看到），
+//! 随后在几分钟内用 [`PkceLogin::exchange`] 换取 token。
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use kv_pairs::kv_pairs;
+use reqwest::Url;
+
+use crate::PixivError;
+use crate::token_manager::{OAuthCredentials, TokenManager, request_token};
+
+/// Pixiv web login page for the app client.
+///
+/// App 客户端使用的 Pixiv 网页登录页。
+pub const LOGIN_URL: &str = "https://app-api.pixiv.net/web/v1/login";
+/// Redirect URI registered for the app client.
+///
+/// App 客户端注册的重定向 URI。
+pub const REDIRECT_URI: &str = "https://app-api.pixiv.net/web/v1/users/auth/pixiv/callback";
+
+/// One PKCE login attempt: a random code verifier and its S256 code challenge.
+///
+/// 一次 PKCE 登录：随机生成的 code verifier 及其 S256 code challenge。
+#[derive(Debug, Clone)]
+pub struct PkceLogin {
+    code_verifier: String,
+    code_challenge: String,
+    credentials: OAuthCredentials,
+    client: reqwest::Client,
+}
+
+impl PkceLogin {
+    /// Start a login with a fresh code verifier, using the default OAuth client identity.
+    ///
+    /// 以新生成的 code verifier 开始登录，使用默认 OAuth 客户端身份。
+    pub fn new() -> Result<Self, PixivError> {
+        let mut bytes = [0u8; 32];
+        aws_lc_rs::rand::fill(&mut bytes).map_err(|_| PixivError::InvalidArgument {
+            message: "system random number generator failed".to_string(),
+        })?;
+        Ok(Self::from_verifier(URL_SAFE_NO_PAD.encode(bytes)))
+    }
+
+    fn from_verifier(code_verifier: String) -> Self {
+        let digest =
+            aws_lc_rs::digest::digest(&aws_lc_rs::digest::SHA256, code_verifier.as_bytes());
+        Self {
+            code_challenge: URL_SAFE_NO_PAD.encode(digest.as_ref()),
+            code_verifier,
+            credentials: OAuthCredentials::default(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Exchange the code with the given OAuth client identity instead of the default one.
+    ///
+    /// 使用指定的 OAuth 客户端身份（而非默认值）换取 token。
+    pub fn credentials(mut self, credentials: OAuthCredentials) -> Self {
+        self.credentials = credentials;
+        self
+    }
+
+    /// Send the exchange, and later refreshes of the returned manager, through `client`.
+    ///
+    /// 通过 `client` 发送换取请求以及所返回管理器之后的刷新请求。
+    pub fn http_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// The code verifier, to resume the login elsewhere.
+    ///
+    /// code verifier，可用于在别处继续登录。
+    pub fn code_verifier(&self) -> &str {
+        &self.code_verifier
+    }
+
+    /// The URL to open in a browser to log in.
+    ///
+    /// 需在浏览器中打开以登录的 URL。
+    pub fn login_url(&self) -> String {
+        Url::parse_with_params(
+            LOGIN_URL,
+            [
+                ("code_challenge", self.code_challenge.as_str()),
+                ("code_challenge_method", "S256"),
+                ("client", "pixiv-android"),
+            ],
+        )
+        .expect("LOGIN_URL is a valid URL")
+        .into()
+    }
+
+    /// Exchange the authorization `code` for tokens, returning a refresh-token manager with the
+    /// access token already cached.
+    ///
+    /// 用授权 `code` 换取 token，返回已缓存 access token 的 refresh token 模式管理器。
+    pub async fn exchange(&self, code: &str) -> Result<TokenManager, PixivError> {
+        let data = kv_pairs![
+            "client_id" => self.credentials.client_id.as_str(),
+            "client_secret" => self.credentials.client_secret.as_str(),
+            "code" => code,
+            "code_verifier" => self.code_verifier.as_str(),
+            "grant_type" => "authorization_code",
+            "include_policy" => "true",
+            "redirect_uri" => REDIRECT_URI,
+        ];
+        let (result, expires_at) = request_token(&self.client, data).await?;
+        let Some(refresh_token) = result.refresh_token.clone() else {
+            return Err(PixivError::UnintelligibleResponse {
+                body: "token response carries no refresh_token".to_string(),
+            });
+        };
+        let manager = TokenManager::new_from_refresh_token(refresh_token)
+            .with_credentials(self.credentials.clone())
+            .with_http_client(self.client.clone());
+        manager.apply_refresh(result, expires_at);
+        Ok(manager)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn s256_challenge() {
+        // Example from RFC 7636, appendix B.
+        let login = PkceLogin::from_verifier("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk".into());
+        assert_eq!(
+            login.code_challenge,
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[test]
+    fn login_url_carries_challenge() {
+        let login = PkceLogin::new().unwrap();
+        assert_eq!(login.code_verifier().len(), 43);
+        let url = login.login_url();
+        assert!(url.starts_with(LOGIN_URL));
+        assert!(url.contains(&format!("code_challenge={}", login.code_challenge)));
+        assert!(url.contains("code_challenge_method=S256"));
+    }
+}
@@ -0,0 +1,334 @@
+//! Resumable mirroring of a user's bookmarked illusts to a local directory.
+//!
+//! 可断点续传的用户收藏插画本地镜像。
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::AppPixivAPI;
+use crate::PixivError;
+use crate::backup::BackupFailure;
+use crate::batch::join_bounded;
+use crate::download::CollisionPolicy;
+use crate::models::ImageSize;
+use crate::next_url::PageToken;
+use crate::params::Restrict;
+use crate::{debug, warn};
+
+/// File name of the checkpoint kept in the mirror directory while a mirror job runs.
+pub const CHECKPOINT_FILE: &str = "mirror-checkpoint.json";
+
+/// Where an interrupted [`AppPixivAPI::mirror_bookmarks`] job continues.
+///
+/// 中断的 [`AppPixivAPI::mirror_bookmarks`] 任务的续传位置。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MirrorCheckpoint {
+    /// User whose bookmarks are mirrored.
+    pub user_id: u64,
+    /// Which of the bookmarks are mirrored, `"public"` or `"private"`.
+    pub restrict: String,
+    /// `max_bookmark_id` of the page being mirrored; `None` for the first page.
+    pub max_bookmark_id: Option<String>,
+    /// Illusts of that page that are already done.
+    pub completed: BTreeSet<u64>,
+}
+
+impl MirrorCheckpoint {
+    /// A checkpoint at the first page of the `restrict` bookmarks of `user_id`.
+    fn start(user_id: u64, restrict: Restrict) -> Self {
+        Self {
+            user_id,
+            restrict: <&str>::from(restrict).to_string(),
+            max_bookmark_id: None,
+            completed: BTreeSet::new(),
+        }
+    }
+
+    /// Whether this checkpoint belongs to a mirror of the `restrict` bookmarks of `user_id`.
+    fn is_for(&self, user_id: u64, restrict: Restrict) -> bool {
+        self.user_id == user_id && self.restrict == <&str>::from(restrict)
+    }
+
+    /// Load the checkpoint from `path`; `None` if there is none.
+    async fn load(path: &Path) -> Result<Option<Self>, PixivError> {
+        let data = match tokio::fs::read(path).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        serde_json::from_slice(&data).map_err(|error| PixivError::Serde {
+            error,
+            body: String::from_utf8_lossy(&data).into_owned(),
+            context: None,
+        })
+    }
+
+    /// Write the checkpoint to `path`, replacing it atomically.
+    async fn save(&self, path: &Path) -> Result<(), PixivError> {
+        let json = serde_json::to_vec(self).map_err(|error| PixivError::Serde {
+            error,
+            body: String::new(),
+            context: None,
+        })?;
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, json).await?;
+        tokio::fs::rename(&tmp, path).await?;
+        Ok(())
+    }
+}
+
+/// Settings of [`AppPixivAPI::mirror_bookmarks`].
+///
+/// [`AppPixivAPI::mirror_bookmarks`] 的配置。
+#[derive(Debug, Clone)]
+pub struct MirrorConfig {
+    /// Which bookmarks to mirror; private ones need the authenticated user's own id.
+    pub restrict: Restrict,
+    /// Image size downloaded for every page.
+    pub image_size: ImageSize,
+    /// What to do with media files that already exist.
+    pub collision: CollisionPolicy,
+    /// Maximum number of works downloaded at the same time.
+    pub concurrency: usize,
+}
+
+impl Default for MirrorConfig {
+    fn default() -> Self {
+        Self {
+            restrict: Restrict::Public,
+            image_size: ImageSize::Original,
+            collision: CollisionPolicy::Skip,
+            concurrency: 4,
+        }
+    }
+}
+
+impl MirrorConfig {
+    /// Create the default config: public bookmarks in original size, existing files kept, four
+    /// works at a time.
+    ///
+    /// 创建默认配置：公开收藏、原图，保留已存在的文件，同时下载四个作品。
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set which bookmarks to mirror.
+    pub fn restrict(mut self, restrict: Restrict) -> Self {
+        self.restrict = restrict;
+        self
+    }
+
+    /// Set the image size downloaded for every page.
+    pub fn image_size(mut self, size: ImageSize) -> Self {
+        self.image_size = size;
+        self
+    }
+
+    /// Set the policy for media files that already exist.
+    pub fn collision(mut self, collision: CollisionPolicy) -> Self {
+        self.collision = collision;
+        self
+    }
+
+    /// Set the maximum number of works downloaded at the same time.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+}
+
+/// Outcome of [`AppPixivAPI::mirror_bookmarks`].
+///
+/// [`AppPixivAPI::mirror_bookmarks`] 的结果。
+#[derive(Debug, Default)]
+pub struct MirrorReport {
+    /// Bookmarked works handled by this run, not counting those done before a resume.
+    pub works: usize,
+    /// Works skipped because they are deleted, private or otherwise not visible.
+    pub limited: usize,
+    /// Media files downloaded by this run.
+    pub downloaded: usize,
+    /// Media files that already existed and were kept.
+    pub skipped: usize,
+    /// Works whose media could not be saved; they count as done for the checkpoint.
+    pub failures: Vec<BackupFailure>,
+}
+
+impl AppPixivAPI {
+    /// Mirror the bookmarked illusts of user `user_id` into `dest`, page by page.
+    ///
+    /// Progress is kept in `dest/mirror-checkpoint.json` ([`CHECKPOINT_FILE`]): the
+    /// `max_bookmark_id` of the current page and the works of that page already done, saved after
+    /// every batch of `config.concurrency` works. An interrupted job called again with the same
+    /// `dest` continues from there instead of walking all pages again. The checkpoint is removed
+    /// once the last page is done, so the next run starts over from the newest bookmarks. A
+    /// checkpoint left by a mirror of another user or `restrict` fails with
+    /// `PixivError::InvalidArgument` rather than mixing the two.
+    ///
+    /// A work whose media cannot be saved is recorded in [`MirrorReport::failures`] and the
+    /// mirror goes on; failing to fetch a page stops it, keeping the checkpoint.
+    ///
+    /// 逐页将用户 `user_id` 收藏的插画镜像到 `dest`。进度保存在 `dest/mirror-checkpoint.json`
+    /// （[`CHECKPOINT_FILE`]）中：当前页的 `max_bookmark_id` 及该页已完成的作品，每完成
+    /// `config.concurrency` 个作品保存一次。中断后以相同 `dest` 再次调用即从该处继续，无需重新遍历所有分页；
+    /// 最后一页完成后删除检查点，下一次运行将从最新收藏重新开始。若检查点来自其他用户或其他 `restrict`
+    /// 的镜像任务，则返回 `PixivError::InvalidArgument` 而不混用。单个作品保存失败会记录在
+    /// [`MirrorReport::failures`] 中并继续；获取分页失败则中止并保留检查点。
+    pub async fn mirror_bookmarks(
+        &self,
+        user_id: u64,
+        dest: &Path,
+        config: &MirrorConfig,
+        with_auth: bool,
+    ) -> Result<MirrorReport, PixivError> {
+        tokio::fs::create_dir_all(dest).await?;
+        let checkpoint_path = dest.join(CHECKPOINT_FILE);
+        let mut checkpoint = match MirrorCheckpoint::load(&checkpoint_path).await? {
+            Some(saved) if !saved.is_for(user_id, config.restrict) => {
+                return Err(PixivError::InvalidArgument {
+                    message: format!(
+                        "{} belongs to a mirror of the {} bookmarks of user {}",
+                        checkpoint_path.display(),
+                        saved.restrict,
+                        saved.user_id
+                    ),
+                });
+            }
+            Some(saved) => {
+                debug!(
+                    "resuming bookmark mirror of user {user_id} at max_bookmark_id {:?}, {} done",
+                    saved.max_bookmark_id,
+                    saved.completed.len()
+                );
+                saved
+            }
+            None => MirrorCheckpoint::start(user_id, config.restrict),
+        };
+
+        let mut report = MirrorReport::default();
+        loop {
+            let page = self
+                .user_bookmarks_illust(
+                    user_id,
+                    Some(config.restrict),
+                    None,
+                    checkpoint.max_bookmark_id.as_deref(),
+                    None,
+                    with_auth,
+                )
+                .await?;
+            let pending: Vec<_> = page
+                .illusts
+                .iter()
+                .filter(|illust| !checkpoint.completed.contains(&illust.id))
+                .collect();
+            for batch in pending.chunks(config.concurrency.max(1)) {
+                let tasks = batch.iter().map(|illust| async move {
+                    if illust.is_limited() {
+                        return (illust.id, None);
+                    }
+                    let files = self
+                        .download_work_media(
+                            illust,
+                            dest,
+                            config.image_size,
+                            config.collision,
+                            with_auth,
+                        )
+                        .await;
+                    (illust.id, Some(files))
+                });
+                for (id, result) in join_bounded(tasks, config.concurrency).await {
+                    report.works += 1;
+                    match result {
+                        None => report.limited += 1,
+                        Some(Ok(files)) => {
+                            for file in files {
+                                if file.skipped {
+                                    report.skipped += 1;
+                                } else {
+                                    report.downloaded += 1;
+                                }
+                            }
+                        }
+                        Some(Err(error)) => {
+                            warn!("mirror: work {id} failed: {error}");
+                            report.failures.push(BackupFailure { id, error });
+                        }
+                    }
+                    checkpoint.completed.insert(id);
+                }
+                checkpoint.save(&checkpoint_path).await?;
+            }
+
+            match page
+                .next_url
+                .as_deref()
+                .and_then(PageToken::from_next_url)
+                .and_then(|token| token.max_bookmark_id)
+            {
+                Some(next) => {
+                    checkpoint.max_bookmark_id = Some(next.to_string());
+                    checkpoint.completed.clear();
+                    checkpoint.save(&checkpoint_path).await?;
+                }
+                None => break,
+            }
+        }
+        match tokio::fs::remove_file(&checkpoint_path).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn checkpoint_round_trip() {
+        let path = std::env::temp_dir().join(format!(
+            "pixiv3-rs-test-{}-{CHECKPOINT_FILE}",
+            std::process::id()
+        ));
+        assert_eq!(MirrorCheckpoint::load(&path).await.unwrap(), None);
+        let checkpoint = MirrorCheckpoint {
+            max_bookmark_id: Some("42".to_string()),
+            completed: [1, 2].into(),
+            ..MirrorCheckpoint::start(7, Restrict::Private)
+        };
+        checkpoint.save(&path).await.unwrap();
+        assert_eq!(
+            MirrorCheckpoint::load(&path).await.unwrap(),
+            Some(checkpoint)
+        );
+        tokio::fs::remove_file(&path).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejects_checkpoint_of_another_mirror() {
+        let dest =
+            std::env::temp_dir().join(format!("pixiv3-rs-test-mirror-{}", std::process::id()));
+        tokio::fs::create_dir_all(&dest).await.unwrap();
+        MirrorCheckpoint::start(1, Restrict::Public)
+            .save(&dest.join(CHECKPOINT_FILE))
+            .await
+            .unwrap();
+        assert!(MirrorCheckpoint::start(1, Restrict::Public).is_for(1, Restrict::Public));
+
+        let api = AppPixivAPI::new_no_auth();
+        let private = MirrorConfig::new().restrict(Restrict::Private);
+        for (user_id, config) in [(2, MirrorConfig::new()), (1, private)] {
+            let result = api.mirror_bookmarks(user_id, &dest, &config, false).await;
+            assert!(
+                matches!(result, Err(PixivError::InvalidArgument { .. })),
+                "{result:?}"
+            );
+        }
+        tokio::fs::remove_dir_all(&dest).await.unwrap();
+    }
+}
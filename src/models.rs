@@ -7,10 +7,12 @@
 #![allow(missing_docs)]
 
 use chrono::{DateTime, FixedOffset};
+use pixiv3_rs_proc::PagedResponse;
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 
 use crate::PixivError;
+use crate::error::{PixivApiError, ResponseContext};
 use crate::{error, warn};
 
 // ----------------------------------------------------------------------------
@@ -20,7 +22,7 @@ use crate::{error, warn};
 /// Profile image URL (medium size).
 ///
 /// 头像图片 URL（中等尺寸）。
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ProfileImageUrls {
     pub medium: String,
 }
@@ -28,7 +30,7 @@ pub struct ProfileImageUrls {
 /// Basic user info as returned in lists and details.
 ///
 /// 列表与详情中返回的基本用户信息。
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct UserInfo {
     pub id: u64,
     pub name: String,
@@ -134,14 +136,58 @@ pub struct UserInfoDetailed {
 // Illust / image
 // ----------------------------------------------------------------------------
 
-/// Image URLs for an illust (square, medium, large).
+/// Image URLs for an illust (square, medium, large; original for the pages of multi-page works).
 ///
-/// 插画图片 URL（方形、中等、大图）。
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// 插画图片 URL（方形、中等、大图；多页作品的各页还包含原图）。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ImageUrls {
     pub square_medium: String,
     pub medium: String,
+    #[serde(default)]
     pub large: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original: Option<String>,
+}
+
+/// Image size to pick from an illust's URLs.
+///
+/// 从插画 URL 中选取的图片尺寸。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ImageSize {
+    /// The uploaded file, unscaled.
+    #[default]
+    Original,
+    /// Scaled to at most 1200 pixels (`_master1200`).
+    Large,
+    /// Scaled to at most 540 pixels.
+    Medium,
+    /// 360×360 square crop.
+    SquareMedium,
+}
+
+/// Path of the placeholder images Pixiv serves in place of limited works.
+const LIMIT_IMAGE_MARKER: &str = "/common/images/limit_";
+
+impl ImageUrls {
+    /// Whether these are Pixiv's placeholder images for a deleted or restricted work.
+    ///
+    /// 是否为 Pixiv 对已删除或受限作品使用的占位图。
+    pub fn is_placeholder(&self) -> bool {
+        self.square_medium.contains(LIMIT_IMAGE_MARKER)
+    }
+
+    /// The URL of `size`, if present.
+    ///
+    /// 对应 `size` 的 URL（如有）。
+    pub fn get(&self, size: ImageSize) -> Option<&str> {
+        let url = match size {
+            ImageSize::Original => self.original.as_deref()?,
+            ImageSize::Large => &self.large,
+            ImageSize::Medium => &self.medium,
+            ImageSize::SquareMedium => &self.square_medium,
+        };
+        Some(url).filter(|url| !url.is_empty())
+    }
 }
 
 /// Tag on an illustration.
@@ -162,6 +208,53 @@ pub struct Series {
     pub title: String,
 }
 
+/// Cover image URL of an illust series.
+///
+/// 插画系列封面图片 URL。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SeriesCoverImageUrls {
+    pub medium: String,
+}
+
+/// Illust series summary as listed by `user_illust_series`.
+///
+/// `user_illust_series` 列出的插画系列概要。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IllustSeriesDetail {
+    pub id: u64,
+    pub title: String,
+    #[serde(default)]
+    pub caption: String,
+    #[serde(default)]
+    pub cover_image_urls: SeriesCoverImageUrls,
+    #[serde(default)]
+    pub series_work_count: u32,
+    #[serde(default)]
+    pub create_date: String,
+    pub user: UserInfo,
+}
+
+/// Manga series on the authenticated user's watchlist.
+///
+/// 当前用户追更列表中的漫画系列。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchlistSeries {
+    pub id: u64,
+    pub title: String,
+    /// Cover image URL.
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub mask_text: Option<String>,
+    #[serde(default)]
+    pub published_content_count: u32,
+    #[serde(default)]
+    pub last_published_content_datetime: String,
+    #[serde(default)]
+    pub latest_content_id: Option<u64>,
+    pub user: UserInfo,
+}
+
 /// Pixiv returns `{}` instead of `null` for empty objects.
 ///
 /// Pixiv 以 `{}` 表示空对象而非 `null`。
@@ -179,10 +272,16 @@ pub enum SeriesOrEmpty {
     Empty(EmptyObject),
 }
 
+impl Default for SeriesOrEmpty {
+    fn default() -> Self {
+        Self::Empty(EmptyObject {})
+    }
+}
+
 /// Single-page illust meta (original image URL).
 ///
 /// 单页插画 meta（原图 URL）。
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct MetaSinglePage {
     pub original_image_url: Option<String>,
 }
@@ -197,34 +296,52 @@ pub struct MetaPage {
 
 /// Illustration info (list or detail).
 ///
-/// 插画信息（列表或详情）。
+/// Deleted, private or otherwise invisible works come back stripped down; the fields Pixiv
+/// may omit then default to empty values. Check [`IllustrationInfo::is_limited`] first.
+///
+/// 插画信息（列表或详情）。已删除、非公开等不可见作品的数据会被精简，缺失字段取空值；请先检查
+/// [`IllustrationInfo::is_limited`]。
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IllustrationInfo {
     pub id: u64,
+    #[serde(default)]
     pub title: String,
     #[serde(rename = "type")]
     pub type_: String,
+    #[serde(default)]
     pub image_urls: ImageUrls,
+    #[serde(default)]
     pub caption: String,
     pub restrict: i32,
+    #[serde(default)]
     pub user: UserInfo,
+    #[serde(default)]
     pub tags: Vec<IllustrationTag>,
+    #[serde(default)]
     pub tools: Vec<String>,
     pub create_date: DateTime<FixedOffset>,
     pub page_count: i32,
+    #[serde(default)]
     pub width: i32,
+    #[serde(default)]
     pub height: i32,
     pub sanity_level: i32,
     pub x_restrict: i32,
     pub series: Option<Series>,
+    #[serde(default)]
     pub meta_single_page: MetaSinglePage,
+    #[serde(default)]
     pub meta_pages: Vec<MetaPage>,
+    #[serde(default)]
     pub total_view: i64,
+    #[serde(default)]
     pub total_bookmarks: i64,
     pub is_bookmarked: bool,
     pub visible: bool,
     pub is_muted: bool,
+    #[serde(default)]
     pub illust_ai_type: i32,
+    #[serde(default)]
     pub illust_book_style: i32,
     #[serde(default)]
     pub total_comments: Option<i32>,
@@ -232,6 +349,42 @@ pub struct IllustrationInfo {
     pub restriction_attributes: Vec<String>,
 }
 
+impl IllustrationInfo {
+    /// Whether this is the stripped-down payload of a deleted, private or otherwise invisible work.
+    ///
+    /// 是否为已删除、非公开等不可见作品的精简数据。
+    pub fn is_limited(&self) -> bool {
+        !self.visible || self.image_urls.is_placeholder()
+    }
+
+    /// Image URL of every page at `size`, resolving single-page works (`meta_single_page`) and
+    /// multi-page ones (`meta_pages`). Pages without a URL of that size are left out.
+    ///
+    /// 各页在 `size` 尺寸下的图片 URL，兼顾单页（`meta_single_page`）与多页（`meta_pages`）作品；
+    /// 缺少该尺寸 URL 的页会被略过。
+    pub fn urls_for(&self, size: ImageSize) -> Vec<&str> {
+        if !self.meta_pages.is_empty() {
+            return self
+                .meta_pages
+                .iter()
+                .filter_map(|page| page.image_urls.get(size))
+                .collect();
+        }
+        let url = match size {
+            ImageSize::Original => self.meta_single_page.original_image_url.as_deref(),
+            size => self.image_urls.get(size),
+        };
+        url.into_iter().collect()
+    }
+
+    /// Original-size URL of every page; shorthand for `urls_for(ImageSize::Original)`.
+    ///
+    /// 各页原图 URL，等同于 `urls_for(ImageSize::Original)`。
+    pub fn original_urls(&self) -> Vec<&str> {
+        self.urls_for(ImageSize::Original)
+    }
+}
+
 /// Illust detail response (wraps single illust).
 ///
 /// 插画详情响应（单条插画）。
@@ -240,6 +393,51 @@ pub struct IllustDetail {
     pub illust: IllustrationInfo,
 }
 
+/// Zip archive URLs of an ugoira.
+///
+/// ugoira 压缩包 URL。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UgoiraZipUrls {
+    pub medium: String,
+}
+
+impl UgoiraZipUrls {
+    /// URL of the zip with the original-size frames. The API only lists the 600×600 re-encoded
+    /// `medium` zip; the original one sits next to it with `1920x1080` in its name.
+    ///
+    /// 原尺寸帧压缩包的 URL。API 只提供 600×600 重新编码的 `medium` 压缩包，原尺寸压缩包位于同一位置，
+    /// 文件名中为 `1920x1080`。
+    pub fn original(&self) -> String {
+        self.medium.replace("600x600", "1920x1080")
+    }
+}
+
+/// One ugoira frame: file name inside the zip and display time in milliseconds.
+///
+/// ugoira 的一帧：压缩包内文件名及显示时长（毫秒）。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UgoiraFrame {
+    pub file: String,
+    pub delay: u32,
+}
+
+/// Ugoira metadata (zip URLs and frame delays).
+///
+/// ugoira 元信息（压缩包 URL 与帧延时）。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UgoiraMetadata {
+    pub zip_urls: UgoiraZipUrls,
+    pub frames: Vec<UgoiraFrame>,
+}
+
+/// Ugoira metadata response. Port of ugoira_metadata response.
+///
+/// ugoira 元信息响应。对应 ugoira_metadata 接口响应。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UgoiraMetadataResponse {
+    pub ugoira_metadata: UgoiraMetadata,
+}
+
 // ----------------------------------------------------------------------------
 // Novel
 // ----------------------------------------------------------------------------
@@ -256,35 +454,62 @@ pub struct NovelTag {
 
 /// Novel info (list or detail).
 ///
-/// 小说信息（列表或详情）。
+/// Like [`IllustrationInfo`], invisible novels come back stripped down; check
+/// [`NovelInfo::is_limited`] first.
+///
+/// 小说信息（列表或详情）。与 [`IllustrationInfo`] 相同，不可见小说的数据会被精简；请先检查
+/// [`NovelInfo::is_limited`]。
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NovelInfo {
     pub id: u64,
+    #[serde(default)]
     pub title: String,
+    #[serde(default)]
     pub caption: String,
     pub restrict: i32,
     pub x_restrict: i32,
+    #[serde(default)]
     pub is_original: bool,
+    #[serde(default)]
     pub image_urls: ImageUrls,
     pub create_date: String,
+    #[serde(default)]
     pub tags: Vec<NovelTag>,
     pub page_count: i32,
+    #[serde(default)]
     pub text_length: i64,
+    #[serde(default)]
     pub user: UserInfo,
+    #[serde(default)]
     pub series: SeriesOrEmpty,
     pub is_bookmarked: bool,
+    #[serde(default)]
     pub total_bookmarks: i64,
+    #[serde(default)]
     pub total_view: i64,
     pub visible: bool,
+    #[serde(default)]
     pub total_comments: i32,
     pub is_muted: bool,
+    #[serde(default)]
     pub is_mypixiv_only: bool,
+    #[serde(default)]
     pub is_x_restricted: bool,
+    #[serde(default)]
     pub novel_ai_type: i32,
     #[serde(default)]
     pub comment_access_control: Option<i32>,
 }
 
+impl NovelInfo {
+    /// Whether this is the stripped-down payload of a deleted, private or otherwise invisible novel.
+    ///
+    /// 是否为已删除、非公开等不可见小说的精简数据。
+    pub fn is_limited(&self) -> bool {
+        !self.visible || self.image_urls.is_placeholder()
+    }
+}
+
 /// Recursive: comment or empty object (Pixiv uses `{}` for no parent).
 ///
 /// 评论或空对象（Pixiv 用 `{}` 表示无父评论）。
@@ -307,10 +532,56 @@ pub struct Comment {
     pub parent_comment: CommentOrEmpty,
 }
 
+/// Response of `illust_comment_add` and `novel_comment_add` (wraps the created comment).
+///
+/// `illust_comment_add` 与 `novel_comment_add` 的响应（新建的评论）。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommentDetail {
+    pub comment: Comment,
+}
+
+/// A comment emoji, written as `(slug)` in comment text.
+///
+/// 评论表情，在评论文本中写作 `(slug)`。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmojiDefinition {
+    pub id: u64,
+    pub slug: String,
+    pub image_url_medium: String,
+}
+
+/// All comment emoji.
+///
+/// 全部评论表情。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmojiList {
+    pub emoji_definitions: Vec<EmojiDefinition>,
+}
+
+impl EmojiList {
+    /// The emoji written as `(slug)` in comments.
+    ///
+    /// 评论中写作 `(slug)` 的表情。
+    pub fn get(&self, slug: &str) -> Option<&EmojiDefinition> {
+        self.emoji_definitions
+            .iter()
+            .find(|emoji| emoji.slug == slug)
+    }
+}
+
+/// Novel body from the legacy `novel_text` endpoint.
+///
+/// 旧版 `novel_text` 接口返回的小说正文。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NovelText {
+    /// The text, including `[pixivimage:]` / `[newpage]` tags.
+    pub novel_text: String,
+}
+
 /// Novel comments list with pagination.
 ///
 /// 小说评论列表（分页）。
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PagedResponse)]
 pub struct NovelComments {
     pub total_comments: i32,
     pub comments: Vec<Comment>,
@@ -318,6 +589,15 @@ pub struct NovelComments {
     pub comment_access_control: i32,
 }
 
+/// Replies to a comment (paged).
+///
+/// 评论的回复列表（分页）。
+#[derive(Debug, Clone, Serialize, Deserialize, PagedResponse)]
+pub struct CommentReplies {
+    pub comments: Vec<Comment>,
+    pub next_url: Option<String>,
+}
+
 /// Novel stats (like, bookmark, view counts).
 ///
 /// 小说统计（点赞、收藏、浏览数）。
@@ -382,8 +662,10 @@ pub struct WebviewNovel {
     pub rating: NovelRating,
     pub text: String,
     pub marker: Option<String>,
-    pub illusts: Vec<String>,
-    pub images: Vec<String>,
+    /// Illusts embedded with `[pixivimage:]`; an object keyed by reference, or `[]` when empty.
+    pub illusts: ParsedJson,
+    /// Images embedded with `[uploadedimage:]`; an object keyed by image id, or `[]` when empty.
+    pub images: ParsedJson,
     pub series_navigation: Option<SeriesNavigationOrEmpty>,
     pub glossary_items: Vec<String>,
     pub replaceable_item_ids: Vec<String>,
@@ -395,19 +677,100 @@ pub struct WebviewNovel {
 // Response wrappers (illust/user/novel lists)
 // ----------------------------------------------------------------------------
 
+/// A page of a paged endpoint, linking to the following page with `next_url`.
+///
+/// Implemented for every paged response model; follow it with
+/// [`AppPixivAPI::next_page`](crate::AppPixivAPI::next_page).
+///
+/// 分页接口的一页，通过 `next_url` 指向下一页；所有分页响应模型均已实现，可用
+/// [`AppPixivAPI::next_page`](crate::AppPixivAPI::next_page) 翻页。
+pub trait PagedResponse {
+    /// URL of the following page; `None` on the last page.
+    ///
+    /// 下一页的 URL；最后一页为 `None`。
+    fn next_url(&self) -> Option<&str>;
+}
+
+/// An item of a paged list with a numeric id, used by
+/// [`paging::dedup_by_id`](crate::paging::dedup_by_id).
+///
+/// 分页列表中带数字 id 的元素，供 [`paging::dedup_by_id`](crate::paging::dedup_by_id) 使用。
+pub trait HasId {
+    /// Id of the illust, novel, user, comment, series, article or live.
+    ///
+    /// 插画、小说、用户、评论、系列、文章或直播的 id。
+    fn id(&self) -> u64;
+}
+
+macro_rules! impl_has_id {
+    ($($ty:ty),*) => {
+        $(impl HasId for $ty {
+            fn id(&self) -> u64 {
+                self.id
+            }
+        })*
+    };
+}
+
+impl_has_id!(
+    IllustrationInfo,
+    NovelInfo,
+    UserInfo,
+    Comment,
+    IllustSeriesDetail
+);
+
+impl HasId for UserPreview {
+    fn id(&self) -> u64 {
+        self.user.id
+    }
+}
+
+impl HasId for MarkedNovel {
+    fn id(&self) -> u64 {
+        self.novel.id
+    }
+}
+
 /// User bookmarked novels (paged).
 ///
 /// 用户收藏小说列表（分页）。
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PagedResponse)]
 pub struct UserBookmarksNovel {
     pub novels: Vec<NovelInfo>,
     pub next_url: Option<String>,
 }
 
+/// Reading position saved by a novel marker ("しおり").
+///
+/// 小说书签（“しおり”）保存的阅读位置。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NovelMarker {
+    pub page: u32,
+}
+
+/// A novel with the authenticated user's marker.
+///
+/// 带有当前用户书签的小说。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarkedNovel {
+    pub novel: NovelInfo,
+    pub novel_marker: NovelMarker,
+}
+
+/// Novels marked by the authenticated user (paged).
+///
+/// 当前用户加了书签的小说列表（分页）。
+#[derive(Debug, Clone, Serialize, Deserialize, PagedResponse)]
+pub struct NovelMarkers {
+    pub marked_novels: Vec<MarkedNovel>,
+    pub next_url: Option<String>,
+}
+
 /// User novels list (paged).
 ///
 /// 用户小说列表（分页）。
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PagedResponse)]
 pub struct UserNovels {
     pub user: UserInfo,
     pub novels: Vec<NovelInfo>,
@@ -417,7 +780,7 @@ pub struct UserNovels {
 /// Novel search result (paged).
 ///
 /// 小说搜索结果（分页）。
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PagedResponse)]
 pub struct SearchNovel {
     pub novels: Vec<NovelInfo>,
     pub next_url: Option<String>,
@@ -428,7 +791,7 @@ pub struct SearchNovel {
 /// Illust search result (paged).
 ///
 /// 插画搜索结果（分页）。
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PagedResponse)]
 pub struct SearchIllustrations {
     pub illusts: Vec<IllustrationInfo>,
     pub next_url: Option<String>,
@@ -439,7 +802,7 @@ pub struct SearchIllustrations {
 /// User bookmarked illusts (paged).
 ///
 /// 用户收藏插画列表（分页）。
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PagedResponse)]
 pub struct UserBookmarksIllustrations {
     pub illusts: Vec<IllustrationInfo>,
     pub next_url: Option<String>,
@@ -456,25 +819,268 @@ pub struct UserPreview {
     pub is_muted: bool,
 }
 
+/// State of the authenticated account.
+///
+/// 当前登录账号的状态。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserState {
+    #[serde(default)]
+    pub is_mail_authorized: bool,
+    #[serde(default)]
+    pub has_mail_address: bool,
+    #[serde(default)]
+    pub has_changed_pixiv_id: bool,
+    #[serde(default)]
+    pub can_change_pixiv_id: bool,
+    #[serde(default)]
+    pub has_password: bool,
+    #[serde(default)]
+    pub require_policy_agreement: bool,
+    #[serde(default)]
+    pub no_login_method: bool,
+    #[serde(default)]
+    pub is_user_restricted: bool,
+}
+
+/// Response of `user_me_state` (wraps the account state).
+///
+/// `user_me_state` 的响应（账号状态）。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserMeState {
+    pub user_state: UserState,
+}
+
+/// A muted tag.
+///
+/// 已屏蔽的标签。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MutedTag {
+    pub tag: IllustrationTag,
+    /// Whether the mute uses one of the slots only premium accounts have.
+    #[serde(default)]
+    pub is_premium_slot: bool,
+}
+
+/// A muted user.
+///
+/// 已屏蔽的用户。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MutedUser {
+    pub user: UserInfo,
+    /// Whether the mute uses one of the slots only premium accounts have.
+    #[serde(default)]
+    pub is_premium_slot: bool,
+}
+
+/// Muted tags and users of the authenticated account, as returned by `mute_list`; convert it
+/// into a [`mute::MuteList`](crate::mute::MuteList) to filter works with it.
+///
+/// `mute_list` 返回的当前账号屏蔽的标签与用户；可转换为 [`mute::MuteList`](crate::mute::MuteList) 用于过滤作品。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MuteListResponse {
+    #[serde(default)]
+    pub muted_tags: Vec<MutedTag>,
+    #[serde(default)]
+    pub muted_users: Vec<MutedUser>,
+    #[serde(default)]
+    pub muted_count: u32,
+}
+
+/// A pixivision (spotlight) article.
+///
+/// pixivision（特辑）文章。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpotlightArticle {
+    pub id: u64,
+    pub title: String,
+    #[serde(default)]
+    pub pure_title: String,
+    pub thumbnail: String,
+    pub article_url: String,
+    pub publish_date: String,
+    pub category: String,
+    #[serde(default)]
+    pub subcategory_label: String,
+}
+
+/// Pixivision (spotlight) articles (paged).
+///
+/// pixivision（特辑）文章列表（分页）。
+#[derive(Debug, Clone, Serialize, Deserialize, PagedResponse)]
+pub struct SpotlightArticles {
+    pub spotlight_articles: Vec<SpotlightArticle>,
+    pub next_url: Option<String>,
+}
+
+/// A bookmark-count bucket of a search, usable as `bookmark_num_min` / `bookmark_num_max` of
+/// [`SearchQuery`](crate::search::SearchQuery); `None` means unbounded.
+///
+/// 搜索结果的收藏数区间，可用作 [`SearchQuery`](crate::search::SearchQuery) 的 `bookmark_num_min` /
+/// `bookmark_num_max`；`None` 表示无界。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BookmarkRange {
+    #[serde(default, deserialize_with = "bound_from_str_or_int")]
+    pub bookmark_num_min: Option<u32>,
+    #[serde(default, deserialize_with = "bound_from_str_or_int")]
+    pub bookmark_num_max: Option<u32>,
+}
+
+/// Bookmark-count buckets of an illust search.
+///
+/// 插画搜索的收藏数区间。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchBookmarkRanges {
+    pub bookmark_ranges: Vec<BookmarkRange>,
+}
+
 /// User following list (paged).
 ///
 /// 用户关注列表（分页）。
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PagedResponse)]
 pub struct UserFollowing {
     pub user_previews: Vec<UserPreview>,
     pub next_url: Option<String>,
 }
 
+/// User follower list (paged).
+///
+/// 用户粉丝列表（分页）。
+#[derive(Debug, Clone, Serialize, Deserialize, PagedResponse)]
+pub struct UserFollower {
+    pub user_previews: Vec<UserPreview>,
+    pub next_url: Option<String>,
+}
+
+/// Manga series watchlist of the authenticated user (paged).
+///
+/// 当前用户的漫画追更列表（分页）。
+#[derive(Debug, Clone, Serialize, Deserialize, PagedResponse)]
+pub struct WatchlistManga {
+    pub series: Vec<WatchlistSeries>,
+    pub next_url: Option<String>,
+}
+
+/// Illust series published by a user (paged).
+///
+/// 用户发布的插画系列列表（分页）。
+#[derive(Debug, Clone, Serialize, Deserialize, PagedResponse)]
+pub struct UserIllustSeries {
+    pub illust_series_details: Vec<IllustSeriesDetail>,
+    pub next_url: Option<String>,
+}
+
 /// User illusts list (paged).
 ///
 /// 用户插画列表（分页）。
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PagedResponse)]
 pub struct UserIllustrations {
     pub user: UserInfo,
     pub illusts: Vec<IllustrationInfo>,
     pub next_url: Option<String>,
 }
 
+/// Owner of a sketch live.
+///
+/// 直播的主播。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiveOwner {
+    pub user: UserInfo,
+}
+
+/// A pixiv Sketch live stream.
+///
+/// pixiv Sketch 直播。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SketchLive {
+    #[serde(deserialize_with = "u64_from_str_or_int")]
+    pub id: u64,
+    pub name: String,
+    pub owner: LiveOwner,
+    #[serde(default)]
+    pub thumbnail_image_url: String,
+    /// Viewers watching right now.
+    #[serde(default)]
+    pub audience_count: u32,
+    /// Viewers since the live started.
+    #[serde(default)]
+    pub total_audience_count: u32,
+    /// Performers on stage, including the owner.
+    #[serde(default)]
+    pub member_count: u32,
+    #[serde(default)]
+    pub is_r18: bool,
+    #[serde(default)]
+    pub created_at: String,
+}
+
+/// Active sketch lives (paged).
+///
+/// 正在进行的 Sketch 直播列表（分页）。
+#[derive(Debug, Clone, Serialize, Deserialize, PagedResponse)]
+pub struct LiveList {
+    pub lives: Vec<SketchLive>,
+    pub next_url: Option<String>,
+}
+
+/// New works from followed users, as polled by the app's notifications.
+///
+/// 应用通知所轮询的关注用户新作品。
+#[derive(Debug, Clone, Serialize, Deserialize, PagedResponse)]
+pub struct NewFromFollowing {
+    #[serde(default)]
+    pub illusts: Vec<IllustrationInfo>,
+    #[serde(default)]
+    pub novels: Vec<NovelInfo>,
+    #[serde(default)]
+    pub next_url: Option<String>,
+}
+
+/// One kind of notification and whether it is delivered.
+///
+/// 一种通知及其是否开启。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationSetting {
+    pub id: u64,
+    pub name: String,
+    pub enabled: bool,
+}
+
+/// Notification settings of the authenticated account.
+///
+/// 当前账号的通知设置。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationSettings {
+    /// Whether push notifications show a preview of the content.
+    #[serde(default)]
+    pub is_preview_enabled: bool,
+    #[serde(default)]
+    pub types: Vec<NotificationSetting>,
+}
+
+/// Latest version of the official app on one platform.
+///
+/// 某一平台官方应用的最新版本信息。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApplicationInfo {
+    pub latest_version: String,
+    #[serde(default)]
+    pub update_required: bool,
+    #[serde(default)]
+    pub update_available: bool,
+    #[serde(default)]
+    pub update_message: String,
+    #[serde(default)]
+    pub store_url: String,
+}
+
+/// Response of `application_info_android` / `application_info_ios`.
+///
+/// `application_info_android` / `application_info_ios` 的响应。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApplicationInfoResponse {
+    pub application_info: ApplicationInfo,
+}
+
 /// OAuth token refresh response (access_token, expires_in, etc.).
 ///
 /// OAuth 刷新 token 的响应（access_token、expires_in 等）。
@@ -483,6 +1089,28 @@ pub struct TokenRefreshResult {
     pub access_token: String,
     pub refresh_token: Option<String>,
     pub expires_in: Option<i64>,
+    #[serde(default)]
+    pub user: Option<AuthUser>,
+}
+
+/// The authenticated account, as returned with an OAuth token.
+///
+/// 随 OAuth token 返回的当前登录账号。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthUser {
+    /// Sent as a string by the OAuth endpoint.
+    #[serde(deserialize_with = "u64_from_str_or_int")]
+    pub id: u64,
+    pub name: String,
+    pub account: String,
+    #[serde(default)]
+    pub mail_address: Option<String>,
+    #[serde(default)]
+    pub is_premium: bool,
+    #[serde(default)]
+    pub x_restrict: i32,
+    #[serde(default)]
+    pub is_mail_authorized: bool,
 }
 
 // ----------------------------------------------------------------------------
@@ -495,10 +1123,65 @@ pub struct TokenRefreshResult {
 /// 接口返回的 JSON 解析结果；可用索引方式访问字段。
 pub type ParsedJson = serde_json::Value;
 
+/// Typed response data together with HTTP metadata (status, headers, elapsed time).
+///
+/// 类型化的响应数据及其 HTTP 元信息（状态码、响应头、耗时）。
+#[derive(Debug, Clone)]
+pub struct ResponseEnvelope<T> {
+    pub data: T,
+    pub status: StatusCode,
+    pub headers: reqwest::header::HeaderMap,
+    /// Time from sending the request until the body was parsed.
+    pub elapsed: std::time::Duration,
+}
+
+impl<T> ResponseEnvelope<T> {
+    /// Returns a response header as a string, if present and valid UTF-8.
+    ///
+    /// 以字符串形式返回响应头（存在且为合法 UTF-8 时）。
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|v| v.to_str().ok())
+    }
+}
+
 // ----------------------------------------------------------------------------
 // Parsing
 // ----------------------------------------------------------------------------
 
+/// Accept an id sent either as a JSON number or as a numeric string.
+pub(crate) fn u64_from_str_or_int<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<u64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StrOrInt {
+        Int(u64),
+        Str(String),
+    }
+    match StrOrInt::deserialize(deserializer)? {
+        StrOrInt::Int(id) => Ok(id),
+        StrOrInt::Str(s) => s.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+/// Accept a bound sent as a JSON number or numeric string, with `"*"` (or `null`) for unbounded.
+fn bound_from_str_or_int<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<u32>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StrOrInt {
+        Int(u32),
+        Str(String),
+    }
+    match Option::<StrOrInt>::deserialize(deserializer)? {
+        Some(StrOrInt::Int(n)) => Ok(Some(n)),
+        Some(StrOrInt::Str(s)) if s == "*" || s.is_empty() => Ok(None),
+        Some(StrOrInt::Str(s)) => s.parse().map(Some).map_err(serde::de::Error::custom),
+        None => Ok(None),
+    }
+}
+
 /// Returns true if the response body is a JSON object with an `"error"` key (API error response).
 ///
 /// 若响应体为带 `"error"` 键的 JSON 对象（API 错误响应）则返回 true。
@@ -520,40 +1203,175 @@ pub fn parse_into<T: DeserializeOwned, S: AsRef<str> + Into<String>>(
         Err(error) => Err(PixivError::Serde {
             error,
             body: res_body.into(),
+            context: None,
         }),
     }
 }
 
+/// Read the whole response body as text, failing with `PixivError::ResponseTooLarge` as soon as more than `max_size` bytes arrive.
+///
+/// 读取完整响应体；若超过 `max_size` 字节则立即中止并返回 `PixivError::ResponseTooLarge`。
+pub async fn read_response_body(
+    response: reqwest::Response,
+    max_size: Option<usize>,
+) -> Result<String, PixivError> {
+    #[cfg(feature = "http-trace")]
+    let (status, url) = (response.status(), response.url().to_string());
+    let body = read_response_body_inner(response, max_size).await?;
+    #[cfg(feature = "http-trace")]
+    crate::http_trace::trace_response(status, &url, &body);
+    Ok(body)
+}
+
+async fn read_response_body_inner(
+    response: reqwest::Response,
+    max_size: Option<usize>,
+) -> Result<String, PixivError> {
+    if max_size.is_none() {
+        return Ok(response.text().await?);
+    }
+    let body = read_response_bytes(response, max_size).await?;
+    Ok(match String::from_utf8(body) {
+        Ok(text) => text,
+        Err(e) => String::from_utf8_lossy(e.as_bytes()).into_owned(),
+    })
+}
+
+/// Read the whole response body as bytes, failing with `PixivError::ResponseTooLarge` as soon as
+/// more than `max_size` bytes arrive.
+pub(crate) async fn read_response_bytes(
+    mut response: reqwest::Response,
+    max_size: Option<usize>,
+) -> Result<Vec<u8>, PixivError> {
+    let Some(limit) = max_size else {
+        return Ok(response.bytes().await?.to_vec());
+    };
+    if response
+        .content_length()
+        .is_some_and(|len| len > limit as u64)
+    {
+        error!(
+            "API response declares {:?} bytes, over the limit of {limit}",
+            response.content_length()
+        );
+        return Err(PixivError::ResponseTooLarge { limit });
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if body.len() + chunk.len() > limit {
+            error!("API response exceeds the limit of {limit} bytes, aborted");
+            return Err(PixivError::ResponseTooLarge { limit });
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+/// A response equivalent to one whose body has already been read into `body`.
+pub(crate) fn rebuild_response(
+    status: StatusCode,
+    url: reqwest::Url,
+    headers: reqwest::header::HeaderMap,
+    body: Vec<u8>,
+) -> Result<reqwest::Response, PixivError> {
+    use reqwest::ResponseBuilderExt;
+
+    let mut builder = http::Response::builder().status(status).url(url);
+    if let Some(builder_headers) = builder.headers_mut() {
+        builder_headers.extend(headers);
+    }
+    let response = builder
+        .body(body)
+        .map_err(|e| PixivError::InvalidArgument {
+            message: format!("cannot rebuild response: {e}"),
+        })?;
+    Ok(response.into())
+}
+
+/// Parse a `Retry-After` header, given either in seconds or as an HTTP date.
+fn parse_retry_after(headers: &reqwest::header::HeaderMap) -> Option<std::time::Duration> {
+    let value = headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(std::time::Duration::from_secs(secs));
+    }
+    let at = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (at.to_utc() - chrono::Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
 /// Read response body and deserialize into `T`. Handles rate limit (429), not found (404), and API error payloads.
 ///
 /// 读取响应体并反序列化为 `T`；会处理 429、404 及 API 错误体。
 pub async fn parse_response_into<T: DeserializeOwned>(
     response: reqwest::Response,
+) -> Result<T, PixivError> {
+    parse_response_into_limited(response, None).await
+}
+
+/// Same as [`parse_response_into`], but reads at most `max_size` bytes of body.
+///
+/// 同 [`parse_response_into`]，但最多读取 `max_size` 字节的响应体。
+pub async fn parse_response_into_limited<T: DeserializeOwned>(
+    response: reqwest::Response,
+    max_size: Option<usize>,
 ) -> Result<T, PixivError> {
     let status = response.status();
-    let body = response.text().await?;
+    let retry_after = parse_retry_after(response.headers());
+    let context = Some(ResponseContext::of(&response));
+    let body = read_response_body(response, max_size).await?;
 
     match status {
         StatusCode::TOO_MANY_REQUESTS => {
-            error!("API rate limited: {body}");
-            Err(PixivError::RateLimited { body })
+            error!("API rate limited (retry after {retry_after:?}): {body}");
+            Err(PixivError::RateLimited {
+                body,
+                retry_after,
+                context,
+            })
         }
         StatusCode::NOT_FOUND => {
             error!("API resource not found: {body}");
-            Err(PixivError::NotFound { body })
+            Err(PixivError::NotFound { body, context })
+        }
+        _ if !status.is_success() => {
+            warn!("API request returned non-success status: {status}");
+            Err(match PixivApiError::from_body(&body) {
+                Some(error) => PixivError::ErrResponse {
+                    error: Box::new(error),
+                    body,
+                    context,
+                },
+                None => PixivError::UnexpectedStatus {
+                    status,
+                    body,
+                    context,
+                },
+            })
         }
         _ => {
-            if !status.is_success() {
-                warn!("API request returned non-success status: {status}, parse body anyway");
-            }
-
             parse_into(body).map_err(|e| {
                 // If it failed to parse, check if it's an error response
-                if let PixivError::Serde { error, body } = e {
-                    if is_error_response(&body) {
-                        PixivError::ErrResponse { body }
+                if let PixivError::Serde { error, body, .. } = e {
+                    if let Some(error) = PixivApiError::from_body(&body) {
+                        PixivError::ErrResponse {
+                            error: Box::new(error),
+                            body,
+                            context,
+                        }
                     } else {
-                        PixivError::Serde { error, body }
+                        PixivError::Serde {
+                            error,
+                            body,
+                            context,
+                        }
                     }
                 } else {
                     e
@@ -630,6 +1448,149 @@ mod tests {
         assert_eq!(detail.illust.page_count, 1);
     }
 
+    #[test]
+    fn deserialize_limited_illust() {
+        let json = r#"{
+            "id": 12345,
+            "title": "",
+            "type": "illust",
+            "image_urls": {
+                "square_medium": "https://s.pximg.net/common/images/limit_unknown_360.png",
+                "medium": "https://s.pximg.net/common/images/limit_unknown_360.png"
+            },
+            "restrict": 2,
+            "create_date": "2024-01-01T12:00:00+09:00",
+            "page_count": 1,
+            "sanity_level": 2,
+            "x_restrict": 0,
+            "series": null,
+            "meta_single_page": {},
+            "is_bookmarked": false,
+            "visible": false,
+            "is_muted": false
+        }"#;
+        let illust: IllustrationInfo = serde_json::from_str(json).unwrap();
+        assert!(illust.is_limited());
+        assert_eq!(illust.user.id, 0);
+        assert!(illust.tags.is_empty());
+        assert!(illust.image_urls.large.is_empty());
+    }
+
+    #[test]
+    fn deserialize_user_illust_series() {
+        let json = r#"{
+            "illust_series_details": [{
+                "id": 123,
+                "title": "Series",
+                "caption": "",
+                "cover_image_urls": {"medium": "https://i.pximg.net/c/cover.jpg"},
+                "series_work_count": 4,
+                "create_date": "2024-01-01T00:00:00+09:00",
+                "width": 1000,
+                "height": 1414,
+                "user": {"id": 1, "name": "a", "account": "a", "profile_image_urls": {"medium": ""}}
+            }],
+            "next_url": null
+        }"#;
+        let series: UserIllustSeries = serde_json::from_str(json).unwrap();
+        assert_eq!(series.illust_series_details[0].id(), 123);
+        assert_eq!(series.illust_series_details[0].series_work_count, 4);
+        assert!(series.next_url().is_none());
+    }
+
+    #[test]
+    fn deserialize_user_me_state() {
+        let json = r#"{"user_state": {
+            "is_mail_authorized": true,
+            "has_mail_address": true,
+            "has_changed_pixiv_id": false,
+            "can_change_pixiv_id": true,
+            "has_password": true,
+            "require_policy_agreement": false,
+            "no_login_method": false,
+            "is_user_restricted": false,
+            "some_future_flag": 1
+        }}"#;
+        let state: UserMeState = serde_json::from_str(json).unwrap();
+        assert!(state.user_state.is_mail_authorized);
+        assert!(state.user_state.can_change_pixiv_id);
+        assert!(!state.user_state.is_user_restricted);
+    }
+
+    #[test]
+    fn deserialize_bookmark_ranges() {
+        let json = r#"{"bookmark_ranges": [
+            {"bookmark_num_min": "*", "bookmark_num_max": "*"},
+            {"bookmark_num_min": "100", "bookmark_num_max": 499}
+        ]}"#;
+        let ranges: SearchBookmarkRanges = serde_json::from_str(json).unwrap();
+        assert_eq!(ranges.bookmark_ranges[0].bookmark_num_min, None);
+        assert_eq!(ranges.bookmark_ranges[1].bookmark_num_min, Some(100));
+        assert_eq!(ranges.bookmark_ranges[1].bookmark_num_max, Some(499));
+    }
+
+    #[test]
+    fn deserialize_live_list() {
+        let json = r#"{"lives": [{
+            "id": "1234567",
+            "name": "drawing",
+            "owner": {"user": {"id": 1, "name": "a", "account": "a", "profile_image_urls": {"medium": ""}}},
+            "thumbnail_image_url": "https://img-sketch.pixiv.net/t.jpg",
+            "audience_count": 12,
+            "total_audience_count": 340,
+            "member_count": 1,
+            "is_r18": false
+        }], "next_url": null}"#;
+        let lives: LiveList = serde_json::from_str(json).unwrap();
+        assert_eq!(lives.lives[0].id, 1234567);
+        assert_eq!(lives.lives[0].audience_count, 12);
+    }
+
+    #[test]
+    fn deserialize_notification_settings() {
+        let json = r#"{
+            "is_preview_enabled": true,
+            "types": [{"id": 1, "name": "New works from followed users", "enabled": true}]
+        }"#;
+        let settings: NotificationSettings = serde_json::from_str(json).unwrap();
+        assert!(settings.is_preview_enabled);
+        assert_eq!(settings.types[0].id, 1);
+        let works: NewFromFollowing = serde_json::from_str("{}").unwrap();
+        assert!(works.illusts.is_empty() && works.next_url().is_none());
+    }
+
+    #[test]
+    fn emoji_lookup_by_slug() {
+        let json = r#"{"emoji_definitions": [
+            {"id": 101, "slug": "normal", "image_url_medium": "https://s.pximg.net/common/images/emoji/101.png"}
+        ]}"#;
+        let emoji: EmojiList = serde_json::from_str(json).unwrap();
+        assert_eq!(emoji.get("normal").unwrap().id, 101);
+        assert!(emoji.get("surprise").is_none());
+    }
+
+    #[test]
+    fn deserialize_ugoira_metadata() {
+        let json = r#"{
+            "ugoira_metadata": {
+                "zip_urls": { "medium": "https://i.pximg.net/img-zip-ugoira/img/1_ugoira600x600.zip" },
+                "frames": [
+                    { "file": "000000.jpg", "delay": 100 },
+                    { "file": "000001.jpg", "delay": 80 }
+                ]
+            }
+        }"#;
+        let result: UgoiraMetadataResponse = serde_json::from_str(json).unwrap();
+        let metadata = result.ugoira_metadata;
+        assert!(metadata.zip_urls.medium.ends_with(".zip"));
+        assert_eq!(
+            metadata.zip_urls.original(),
+            "https://i.pximg.net/img-zip-ugoira/img/1_ugoira1920x1080.zip"
+        );
+        assert_eq!(metadata.frames.len(), 2);
+        assert_eq!(metadata.frames[1].delay, 80);
+    }
+
     #[test]
     fn deserialize_empty_series_as_empty_object() {
         let json = r#"{}"#;
@@ -677,6 +1638,64 @@ mod tests {
         assert!(matches!(err, PixivError::Serde { .. }));
     }
 
+    #[test]
+    fn retry_after_seconds_or_date() {
+        use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+        let mut headers = HeaderMap::new();
+        assert_eq!(parse_retry_after(&headers), None);
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+        assert_eq!(
+            parse_retry_after(&headers),
+            Some(std::time::Duration::from_secs(120))
+        );
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(parse_retry_after(&headers), Some(std::time::Duration::ZERO));
+    }
+
+    /// Serve one raw HTTP response on a local port and return its URL.
+    async fn serve_once(response: &'static str) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request).await;
+            let _ = stream.write_all(response.as_bytes()).await;
+        });
+        format!("http://{addr}/")
+    }
+
+    #[tokio::test]
+    async fn non_success_status_errors() {
+        let url = serve_once(
+            "HTTP/1.1 502 Bad Gateway\r\ncontent-length: 13\r\nconnection: close\r\n\r\n<html></html>",
+        )
+        .await;
+        let response = reqwest::get(&url).await.unwrap();
+        let err = parse_response_into::<ParsedJson>(response)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, PixivError::UnexpectedStatus { status, body, .. } if *status == StatusCode::BAD_GATEWAY && body == "<html></html>"),
+            "{err}"
+        );
+        assert!(err.is_retryable());
+
+        let url = serve_once(
+            "HTTP/1.1 400 Bad Request\r\ncontent-length: 30\r\nconnection: close\r\n\r\n{\"error\": {\"message\": \"Oops\"}}",
+        )
+        .await;
+        let response = reqwest::get(&url).await.unwrap();
+        let err = parse_response_into::<ParsedJson>(response)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, PixivError::ErrResponse { .. }), "{err}");
+    }
+
     #[test]
     fn deserialize_token_refresh_result() {
         let json = r#"{
@@ -688,5 +1707,74 @@ mod tests {
         assert_eq!(result.access_token, "abc123");
         assert_eq!(result.refresh_token.as_deref(), Some("xyz789"));
         assert_eq!(result.expires_in, Some(3600));
+        assert!(result.user.is_none());
+    }
+
+    #[test]
+    fn paged_responses_expose_next_url() {
+        let page: UserFollowing = serde_json::from_str(
+            r#"{"user_previews": [], "next_url": "https://app-api.pixiv.net/v1/user/following?offset=30"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            page.next_url(),
+            Some("https://app-api.pixiv.net/v1/user/following?offset=30")
+        );
+        let last: NovelComments = serde_json::from_str(
+            r#"{"total_comments": 0, "comments": [], "next_url": null, "comment_access_control": 0}"#,
+        )
+        .unwrap();
+        assert_eq!(last.next_url(), None);
+    }
+
+    #[test]
+    fn urls_for_by_size() {
+        let mut illust: IllustrationInfo = serde_json::from_str(
+            r#"{
+            "id": 1, "title": "", "type": "illust", "caption": "", "restrict": 0,
+            "image_urls": {"square_medium": "sq", "medium": "m", "large": "l"},
+            "user": {"id": 1, "name": "", "account": "", "profile_image_urls": {"medium": ""}},
+            "tags": [], "tools": [], "create_date": "2024-01-01T12:00:00+09:00",
+            "page_count": 1, "width": 1, "height": 1, "sanity_level": 2, "x_restrict": 0,
+            "meta_single_page": {"original_image_url": "o"},
+            "is_bookmarked": false, "visible": true, "is_muted": false
+        }"#,
+        )
+        .unwrap();
+        assert_eq!(illust.urls_for(ImageSize::Original), ["o"]);
+        assert_eq!(illust.urls_for(ImageSize::Large), ["l"]);
+
+        illust.meta_pages = serde_json::from_str(
+            r#"[
+            {"image_urls": {"square_medium": "sq0", "medium": "m0", "large": "l0", "original": "o0"}},
+            {"image_urls": {"square_medium": "sq1", "medium": "m1", "large": "l1", "original": "o1"}}
+        ]"#,
+        )
+        .unwrap();
+        assert_eq!(illust.original_urls(), ["o0", "o1"]);
+        assert_eq!(illust.urls_for(ImageSize::SquareMedium), ["sq0", "sq1"]);
+    }
+
+    #[test]
+    fn deserialize_token_refresh_user() {
+        let json = r#"{
+            "access_token": "abc123",
+            "expires_in": 3600,
+            "user": {
+                "profile_image_urls": { "px_16x16": "", "px_50x50": "", "px_170x170": "" },
+                "id": "11",
+                "name": "pixiv事務局",
+                "account": "pixiv",
+                "mail_address": "",
+                "is_premium": false,
+                "x_restrict": 2,
+                "is_mail_authorized": true
+            }
+        }"#;
+        let result: TokenRefreshResult = serde_json::from_str(json).unwrap();
+        let user = result.user.unwrap();
+        assert_eq!(user.id, 11);
+        assert_eq!(user.account, "pixiv");
+        assert_eq!(user.x_restrict, 2);
     }
 }
@@ -0,0 +1,244 @@
+//! Client-side filtering of muted tags and users, like the official app does.
+//!
+//! 客户端屏蔽：按屏蔽的标签与用户过滤作品，与官方 App 行为一致。
+
+use std::collections::HashSet;
+
+use crate::models::{IllustrationInfo, MuteListResponse, NovelInfo};
+
+/// Works that can be matched against a [`MuteList`].
+///
+/// 可以用 [`MuteList`] 匹配的作品。
+pub trait Mutable {
+    /// Id of the author.
+    fn author_id(&self) -> u64;
+    /// Names of the tags on the work.
+    fn tag_names(&self) -> Vec<&str>;
+    /// Whether Pixiv itself flagged the work as muted (the `is_muted` field).
+    fn is_muted_by_server(&self) -> bool;
+}
+
+impl Mutable for IllustrationInfo {
+    fn author_id(&self) -> u64 {
+        self.user.id
+    }
+
+    fn tag_names(&self) -> Vec<&str> {
+        self.tags.iter().map(|t| t.name.as_str()).collect()
+    }
+
+    fn is_muted_by_server(&self) -> bool {
+        self.is_muted
+    }
+}
+
+impl Mutable for NovelInfo {
+    fn author_id(&self) -> u64 {
+        self.user.id
+    }
+
+    fn tag_names(&self) -> Vec<&str> {
+        self.tags.iter().map(|t| t.name.as_str()).collect()
+    }
+
+    fn is_muted_by_server(&self) -> bool {
+        self.is_muted
+    }
+}
+
+/// A work together with whether it matched the mute list.
+///
+/// 作品及其是否命中屏蔽列表。
+#[derive(Debug, Clone)]
+pub struct Muted<T> {
+    /// The work.
+    pub item: T,
+    /// Whether the work is muted.
+    pub muted: bool,
+}
+
+/// Muted tags and users.
+///
+/// 屏蔽的标签与用户。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MuteList {
+    /// Muted tag names, matched exactly.
+    pub tags: HashSet<String>,
+    /// Muted user ids.
+    pub user_ids: HashSet<u64>,
+}
+
+impl MuteList {
+    /// Create an empty mute list. Works flagged `is_muted` by Pixiv are still treated as muted.
+    ///
+    /// 创建空的屏蔽列表；Pixiv 标记为 `is_muted` 的作品仍视为已屏蔽。
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mute a tag.
+    pub fn mute_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.insert(tag.into());
+        self
+    }
+
+    /// Mute a user.
+    pub fn mute_user(mut self, user_id: u64) -> Self {
+        self.user_ids.insert(user_id);
+        self
+    }
+
+    /// Whether `work` is by a muted user, carries a muted tag, or is flagged muted by Pixiv.
+    ///
+    /// 作品是否来自被屏蔽用户、带有被屏蔽标签或被 Pixiv 标记为已屏蔽。
+    pub fn is_muted(&self, work: &impl Mutable) -> bool {
+        work.is_muted_by_server()
+            || self.user_ids.contains(&work.author_id())
+            || work.tag_names().iter().any(|t| self.tags.contains(*t))
+    }
+
+    /// Drop muted works from `stream`.
+    ///
+    /// 从 `stream` 中去除被屏蔽的作品。
+    #[cfg(feature = "stream")]
+    pub fn filter<'a, S, T>(
+        &'a self,
+        stream: S,
+    ) -> impl futures_core::stream::Stream<Item = Result<T, crate::PixivError>> + use<'a, S, T>
+    where
+        S: futures_core::stream::Stream<Item = Result<T, crate::PixivError>> + 'a,
+        T: Mutable + 'a,
+    {
+        async_stream::try_stream! {
+            let mut stream = std::pin::pin!(stream);
+            while let Some(item) = crate::paging::next(stream.as_mut()).await {
+                let item = item?;
+                if !self.is_muted(&item) {
+                    yield item;
+                }
+            }
+        }
+    }
+
+    /// Keep every work of `stream`, annotated with whether it is muted.
+    ///
+    /// 保留 `stream` 中的全部作品，并标注其是否被屏蔽。
+    #[cfg(feature = "stream")]
+    pub fn annotate<'a, S, T>(
+        &'a self,
+        stream: S,
+    ) -> impl futures_core::stream::Stream<Item = Result<Muted<T>, crate::PixivError>> + use<'a, S, T>
+    where
+        S: futures_core::stream::Stream<Item = Result<T, crate::PixivError>> + 'a,
+        T: Mutable + 'a,
+    {
+        async_stream::try_stream! {
+            let mut stream = std::pin::pin!(stream);
+            while let Some(item) = crate::paging::next(stream.as_mut()).await {
+                let item = item?;
+                let muted = self.is_muted(&item);
+                yield Muted { item, muted };
+            }
+        }
+    }
+}
+
+impl From<&MuteListResponse> for MuteList {
+    /// The account's mutes from `mute_list`, so works are filtered as in the official app.
+    fn from(response: &MuteListResponse) -> Self {
+        Self {
+            tags: response
+                .muted_tags
+                .iter()
+                .map(|muted| muted.tag.name.clone())
+                .collect(),
+            user_ids: response
+                .muted_users
+                .iter()
+                .map(|muted| muted.user.id)
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Work {
+        user: u64,
+        tags: Vec<&'static str>,
+        is_muted: bool,
+    }
+
+    impl Mutable for Work {
+        fn author_id(&self) -> u64 {
+            self.user
+        }
+
+        fn tag_names(&self) -> Vec<&str> {
+            self.tags.clone()
+        }
+
+        fn is_muted_by_server(&self) -> bool {
+            self.is_muted
+        }
+    }
+
+    fn work(user: u64, tags: &[&'static str]) -> Work {
+        Work {
+            user,
+            tags: tags.to_vec(),
+            is_muted: false,
+        }
+    }
+
+    #[test]
+    fn matches_users_tags_and_server_flag() {
+        let mutes = MuteList::new().mute_tag("R-18G").mute_user(42);
+        assert!(mutes.is_muted(&work(42, &[])));
+        assert!(mutes.is_muted(&work(1, &["風景", "R-18G"])));
+        assert!(!mutes.is_muted(&work(1, &["風景"])));
+        let flagged = Work {
+            is_muted: true,
+            ..work(1, &[])
+        };
+        assert!(mutes.is_muted(&flagged));
+    }
+
+    #[test]
+    fn from_mute_list_response() {
+        let response: MuteListResponse = serde_json::from_value(serde_json::json!({
+            "muted_tags": [{"tag": {"name": "R-18G", "translated_name": null}, "is_premium_slot": false}],
+            "muted_users": [{"user": {"id": 42, "name": "", "account": "", "profile_image_urls": {"medium": ""}}, "is_premium_slot": false}],
+            "muted_count": 2
+        }))
+        .unwrap();
+        let mutes = MuteList::from(&response);
+        assert_eq!(mutes, MuteList::new().mute_tag("R-18G").mute_user(42));
+    }
+
+    #[cfg(feature = "stream")]
+    #[tokio::test]
+    async fn filter_and_annotate_streams() {
+        use crate::paging::collect_all;
+
+        let mutes = MuteList::new().mute_user(2);
+        let source = || {
+            async_stream::try_stream! {
+                for user in [1, 2, 3] {
+                    yield work(user, &[]);
+                }
+            }
+        };
+
+        let kept = collect_all(mutes.filter(source()), None).await.unwrap();
+        assert_eq!(kept.iter().map(|w| w.user).collect::<Vec<_>>(), vec![1, 3]);
+
+        let annotated = collect_all(mutes.annotate(source()), None).await.unwrap();
+        assert_eq!(
+            annotated.iter().map(|m| m.muted).collect::<Vec<_>>(),
+            vec![false, true, false]
+        );
+    }
+}
@@ -0,0 +1,94 @@
+//! Parsing of the `next_url` of paged responses (port of `parse_qs`).
+//!
+//! 解析分页响应中的 `next_url`（对应 `parse_qs`）。
+
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+/// Decoded query parameters of `next_url`, in order and with repeated keys (such as `tags[]`)
+/// kept. Port of `parse_qs`; empty if the URL cannot be parsed.
+///
+/// `next_url` 中解码后的查询参数，保持顺序并保留重复的键（如 `tags[]`）。对应 `parse_qs`；URL 无法解析时为空。
+pub fn parse_qs(next_url: &str) -> Vec<(String, String)> {
+    Url::parse(next_url)
+        .map(|url| url.query_pairs().into_owned().collect())
+        .unwrap_or_default()
+}
+
+/// The pagination position carried by a `next_url`, small enough to persist instead of the whole
+/// URL. Pass its fields back as the `offset` / `max_bookmark_id` / `last_order` parameters of the
+/// same endpoint to continue from that page.
+///
+/// `next_url` 中的分页位置，可代替整个 URL 持久化保存；将其字段作为同一接口的 `offset` / `max_bookmark_id` /
+/// `last_order` 参数传回即可从该页继续。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageToken {
+    /// `offset`, used by most lists and searches.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+    /// `max_bookmark_id`, used by bookmark lists.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_bookmark_id: Option<u64>,
+    /// `last_order`, used by novel series.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_order: Option<u64>,
+}
+
+impl PageToken {
+    /// Extract the pagination position from `next_url`; `None` if it carries none.
+    ///
+    /// 从 `next_url` 中提取分页位置；不含分页参数时返回 `None`。
+    pub fn from_next_url(next_url: &str) -> Option<Self> {
+        let mut token = Self::default();
+        for (key, value) in parse_qs(next_url) {
+            let slot = match key.as_str() {
+                "offset" => &mut token.offset,
+                "max_bookmark_id" => &mut token.max_bookmark_id,
+                "last_order" => &mut token.last_order,
+                _ => continue,
+            };
+            *slot = value.parse().ok();
+        }
+        (token != Self::default()).then_some(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_query_pairs() {
+        let pairs = parse_qs(
+            "https://app-api.pixiv.net/v1/search/illust?word=%E3%81%82&tags%5B%5D=a&tags%5B%5D=b&offset=30",
+        );
+        assert_eq!(
+            pairs,
+            [
+                ("word".to_string(), "あ".to_string()),
+                ("tags[]".to_string(), "a".to_string()),
+                ("tags[]".to_string(), "b".to_string()),
+                ("offset".to_string(), "30".to_string()),
+            ]
+        );
+        assert!(parse_qs("not a url").is_empty());
+    }
+
+    #[test]
+    fn extracts_page_token() {
+        let token = PageToken::from_next_url(
+            "https://app-api.pixiv.net/v1/user/bookmarks/illust?user_id=1&restrict=public&max_bookmark_id=123456",
+        )
+        .unwrap();
+        assert_eq!(token.max_bookmark_id, Some(123456));
+        assert_eq!(token.offset, None);
+        assert_eq!(
+            serde_json::to_string(&token).unwrap(),
+            r#"{"max_bookmark_id":123456}"#
+        );
+        assert_eq!(
+            PageToken::from_next_url("https://app-api.pixiv.net/v1/user/illusts?user_id=1"),
+            None
+        );
+    }
+}
@@ -0,0 +1,333 @@
+//! Images embedded in novel text (`[pixivimage:]` / `[uploadedimage:]`) and their download.
+//!
+//! 小说正文中嵌入的图片（`[pixivimage:]` / `[uploadedimage:]`）及其下载。
+
+#[cfg(feature = "fs")]
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use crate::AppPixivAPI;
+use crate::PixivError;
+#[cfg(feature = "fs")]
+use crate::download::CollisionPolicy;
+use crate::models::{ImageSize, WebviewNovel};
+#[cfg(feature = "fs")]
+use crate::{debug, warn};
+
+/// An image reference found in novel text.
+///
+/// 小说正文中的图片引用。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NovelImageRef {
+    /// `[pixivimage:ID]` or `[pixivimage:ID-PAGE]`: a page (1-based) of a pixiv illust.
+    ///
+    /// 引用 pixiv 插画的某一页（从 1 开始）。
+    Illust {
+        /// The illust id.
+        illust_id: u64,
+        /// The page, starting at 1.
+        page: u32,
+    },
+    /// `[uploadedimage:ID]`: an image uploaded with the novel.
+    ///
+    /// 随小说上传的图片。
+    Uploaded {
+        /// The novel image id.
+        image_id: u64,
+    },
+}
+
+/// Find all image references in novel text, in order of appearance and without duplicates.
+///
+/// 按出现顺序找出小说正文中所有（去重后的）图片引用。
+pub fn novel_image_refs(text: &str) -> Vec<NovelImageRef> {
+    static IMAGE_TAG_REGEX: LazyLock<regex::Regex> = LazyLock::new(|| {
+        regex::Regex::new(r"\[(pixivimage|uploadedimage):(\d+)(?:-(\d+))?\]").expect("valid regex")
+    });
+
+    let mut refs = Vec::new();
+    for caps in IMAGE_TAG_REGEX.captures_iter(text) {
+        let Ok(id) = caps[2].parse::<u64>() else {
+            continue;
+        };
+        let image_ref = if &caps[1] == "pixivimage" {
+            let page = caps
+                .get(3)
+                .and_then(|p| p.as_str().parse().ok())
+                .unwrap_or(1);
+            NovelImageRef::Illust {
+                illust_id: id,
+                page,
+            }
+        } else {
+            NovelImageRef::Uploaded { image_id: id }
+        };
+        if !refs.contains(&image_ref) {
+            refs.push(image_ref);
+        }
+    }
+    refs
+}
+
+impl WebviewNovel {
+    /// Image references embedded in [`Self::text`].
+    ///
+    /// [`Self::text`] 中嵌入的图片引用。
+    pub fn image_refs(&self) -> Vec<NovelImageRef> {
+        novel_image_refs(&self.text)
+    }
+
+    /// Original URL of an uploaded image, as listed in [`Self::images`].
+    ///
+    /// [`Self::images`] 中上传图片的原图 URL。
+    pub fn uploaded_image_url(&self, image_id: u64) -> Option<&str> {
+        let urls = &self.images.get(image_id.to_string())?["urls"];
+        urls.get("original")
+            .or_else(|| urls.get("1200x1200"))
+            .and_then(|u| u.as_str())
+    }
+}
+
+/// A downloaded novel image.
+///
+/// 已下载的小说图片。
+#[cfg(feature = "fs")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NovelImageFile {
+    /// The reference in the novel text.
+    pub image_ref: NovelImageRef,
+    /// The resolved image URL.
+    pub url: String,
+    /// Where the image was saved.
+    pub path: PathBuf,
+    /// Whether the image already existed and was not downloaded again.
+    pub skipped: bool,
+}
+
+/// Files written by [`AppPixivAPI::download_novel`].
+///
+/// [`AppPixivAPI::download_novel`] 写入的文件。
+#[cfg(feature = "fs")]
+#[derive(Debug, Clone)]
+pub struct SavedNovel {
+    /// The novel as returned by `webview_novel`.
+    pub novel: WebviewNovel,
+    /// The novel text, `{novel_id}.txt`, unchanged including its `[pixivimage:]` tags.
+    pub text_path: PathBuf,
+    /// JSON sidecar holding the whole [`WebviewNovel`] (title, caption, tags, ...).
+    pub metadata_path: PathBuf,
+    /// The embedded images, named as by [`AppPixivAPI::download_novel_images`].
+    pub images: Vec<NovelImageFile>,
+}
+
+#[cfg(feature = "fs")]
+fn extension_of(url: &str) -> &str {
+    url.rsplit('/')
+        .next()
+        .and_then(|name| name.rsplit_once('.'))
+        .map(|(_, ext)| ext)
+        .unwrap_or("jpg")
+}
+
+impl AppPixivAPI {
+    /// Resolve an image reference to an original-size URL. Illust references are looked up with
+    /// `illust_detail`; uploaded images come from the novel's `images` table. Returns `None` if the
+    /// image is unavailable: a missing page, or an illust that was deleted or is not visible.
+    ///
+    /// 将图片引用解析为原图 URL：插画引用通过 `illust_detail` 查询，上传图片取自小说的 `images`；页不存在、
+    /// 插画已删除或不可见等无法获取的情况返回 `None`。
+    pub async fn resolve_novel_image(
+        &self,
+        novel: &WebviewNovel,
+        image_ref: NovelImageRef,
+        with_auth: bool,
+    ) -> Result<Option<String>, PixivError> {
+        match image_ref {
+            NovelImageRef::Uploaded { image_id } => {
+                Ok(novel.uploaded_image_url(image_id).map(str::to_string))
+            }
+            NovelImageRef::Illust { illust_id, page } => {
+                let illust = match self.illust_detail(illust_id, with_auth).await {
+                    Ok(detail) => detail.illust,
+                    Err(e) if matches!(e.root(), PixivError::NotFound { .. }) => return Ok(None),
+                    Err(e) => return Err(e),
+                };
+                if illust.is_limited() {
+                    return Ok(None);
+                }
+                let index = page.saturating_sub(1) as usize;
+                Ok(illust
+                    .urls_for(ImageSize::Original)
+                    .get(index)
+                    .map(|url| url.to_string()))
+            }
+        }
+    }
+
+    /// Download every image embedded in `novel` into `dir`, named `{novel_id}_p{illust_id}-{page}.ext`
+    /// or `{novel_id}_u{image_id}.ext`. Images that cannot be resolved or downloaded are skipped
+    /// with a warning.
+    ///
+    /// 将小说中嵌入的所有图片下载到 `dir`；无法解析或下载失败的图片会被跳过并记录警告。
+    #[cfg(feature = "fs")]
+    pub async fn download_novel_images(
+        &self,
+        novel: &WebviewNovel,
+        dir: &Path,
+        collision: impl Into<CollisionPolicy>,
+        with_auth: bool,
+    ) -> Result<Vec<NovelImageFile>, PixivError> {
+        let collision = collision.into();
+        let mut files = Vec::new();
+        for image_ref in novel.image_refs() {
+            let Some(url) = self
+                .resolve_novel_image(novel, image_ref, with_auth)
+                .await?
+            else {
+                warn!(
+                    "novel {}: cannot resolve {:?}, skipped",
+                    novel.id, image_ref
+                );
+                continue;
+            };
+            let stem = match image_ref {
+                NovelImageRef::Illust { illust_id, page } => {
+                    format!("{}_p{illust_id}-{page}", novel.id)
+                }
+                NovelImageRef::Uploaded { image_id } => format!("{}_u{image_id}", novel.id),
+            };
+            let name = format!("{stem}.{}", extension_of(&url));
+            debug!("novel {}: downloading {url} as {name}", novel.id);
+            let file = match self
+                .download_file(&url, dir, Some(&name), collision, None, None)
+                .await
+            {
+                Ok(file) => file,
+                Err(e) => {
+                    warn!("novel {}: cannot download {url}: {e}, skipped", novel.id);
+                    continue;
+                }
+            };
+            files.push(NovelImageFile {
+                image_ref,
+                url,
+                path: file.path,
+                skipped: file.skipped,
+            });
+        }
+        Ok(files)
+    }
+
+    /// Save an offline copy of novel `novel_id` into `dir` (created if missing): the text as
+    /// `{novel_id}.txt`, the full `webview_novel` data as `{novel_id}.json`, and every embedded
+    /// image as by [`Self::download_novel_images`]. The text and JSON files are always rewritten;
+    /// existing images are handled per `collision`.
+    ///
+    /// 将小说 `novel_id` 离线保存到 `dir`（不存在则创建）：正文存为 `{novel_id}.txt`，完整的
+    /// `webview_novel` 数据存为 `{novel_id}.json`，嵌入图片按 [`Self::download_novel_images`] 下载。
+    /// 正文与 JSON 文件总会重写，已存在的图片按 `collision` 处理。
+    #[cfg(feature = "fs")]
+    pub async fn download_novel(
+        &self,
+        novel_id: u64,
+        dir: &Path,
+        collision: impl Into<CollisionPolicy>,
+        with_auth: bool,
+    ) -> Result<SavedNovel, PixivError> {
+        let novel = self.webview_novel(novel_id, with_auth).await?;
+        tokio::fs::create_dir_all(dir).await?;
+        let (text_path, metadata_path) = write_novel_files(dir, &novel).await?;
+        let images = self
+            .download_novel_images(&novel, dir, collision, with_auth)
+            .await?;
+        Ok(SavedNovel {
+            novel,
+            text_path,
+            metadata_path,
+            images,
+        })
+    }
+}
+
+/// Write `{id}.txt` and `{id}.json` for `novel` into `dir`.
+#[cfg(feature = "fs")]
+async fn write_novel_files(
+    dir: &Path,
+    novel: &WebviewNovel,
+) -> Result<(PathBuf, PathBuf), PixivError> {
+    let text_path = dir.join(format!("{}.txt", novel.id));
+    tokio::fs::write(&text_path, &novel.text).await?;
+    let json = serde_json::to_vec_pretty(novel).map_err(|error| PixivError::Serde {
+        error,
+        body: String::new(),
+        context: None,
+    })?;
+    let metadata_path = dir.join(format!("{}.json", novel.id));
+    tokio::fs::write(&metadata_path, json).await?;
+    Ok((text_path, metadata_path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_image_tags() {
+        let text = "a[pixivimage:100]b[pixivimage:200-3]c[uploadedimage:42][pixivimage:100]";
+        assert_eq!(
+            novel_image_refs(text),
+            vec![
+                NovelImageRef::Illust {
+                    illust_id: 100,
+                    page: 1
+                },
+                NovelImageRef::Illust {
+                    illust_id: 200,
+                    page: 3
+                },
+                NovelImageRef::Uploaded { image_id: 42 },
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_other_tags() {
+        assert!(novel_image_refs("[newpage][chapter:1][jump:2]").is_empty());
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn extension_from_url() {
+        assert_eq!(
+            extension_of("https://i.pximg.net/img-original/img/1_p0.png"),
+            "png"
+        );
+        assert_eq!(extension_of("https://example.com/noext"), "jpg");
+    }
+
+    #[cfg(feature = "fs")]
+    #[tokio::test]
+    async fn writes_text_and_metadata() {
+        let novel: WebviewNovel = serde_json::from_value(serde_json::json!({
+            "id": "7", "title": "t", "seriesId": null, "seriesTitle": null,
+            "seriesIsWatched": null, "userId": "1", "coverUrl": "", "tags": ["a"],
+            "caption": "", "cdate": "2024-01-01", "rating": {"like": 0, "bookmark": 0, "view": 0},
+            "text": "first[uploadedimage:42]", "marker": null, "illusts": [], "images": [],
+            "seriesNavigation": null, "glossaryItems": [], "replaceableItemIds": [],
+            "aiType": 0, "isOriginal": true
+        }))
+        .unwrap();
+        let dir = std::env::temp_dir().join(format!("pixiv3-rs-test-{}-novel", std::process::id()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        let (text_path, metadata_path) = write_novel_files(&dir, &novel).await.unwrap();
+        assert_eq!(text_path, dir.join("7.txt"));
+        assert_eq!(
+            tokio::fs::read_to_string(&text_path).await.unwrap(),
+            "first[uploadedimage:42]"
+        );
+        let written: WebviewNovel =
+            serde_json::from_slice(&tokio::fs::read(&metadata_path).await.unwrap()).unwrap();
+        assert_eq!(written.title, "t");
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+}
@@ -0,0 +1,214 @@
+//! Helpers for consuming the paged streams, with an optional overall deadline.
+//!
+//! 消费分页流的辅助函数，支持整体截止时间。
+
+use std::collections::HashSet;
+use std::future::Future;
+use std::pin::{Pin, pin};
+use std::time::Duration;
+
+use futures_core::stream::Stream;
+
+use crate::PixivError;
+use crate::models::HasId;
+
+/// Poll the next item of a pinned stream.
+pub(crate) fn next<S: Stream + ?Sized>(
+    mut stream: Pin<&mut S>,
+) -> impl Future<Output = Option<S::Item>> {
+    std::future::poll_fn(move |cx| stream.as_mut().poll_next(cx))
+}
+
+/// Wrap `stream` so that it fails with [`PixivError::DeadlineExceeded`] and ends once `timeout`
+/// has passed since it was first polled.
+///
+/// A `timeout` too large to represent as an instant (e.g. `Duration::MAX`) never elapses.
+///
+/// 包装 `stream`：自首次轮询起超过 `timeout` 后产出 [`PixivError::DeadlineExceeded`] 并结束。
+pub fn with_deadline<S, T>(
+    stream: S,
+    timeout: Duration,
+) -> impl Stream<Item = Result<T, PixivError>>
+where
+    S: Stream<Item = Result<T, PixivError>>,
+{
+    async_stream::try_stream! {
+        // A deadline too far away to represent is treated as no deadline at all.
+        let deadline = tokio::time::Instant::now().checked_add(timeout);
+        let mut stream = pin!(stream);
+        loop {
+            let item = match deadline {
+                Some(deadline) => tokio::time::timeout_at(deadline, next(stream.as_mut()))
+                    .await
+                    .map_err(|_| PixivError::DeadlineExceeded { timeout })?,
+                None => next(stream.as_mut()).await,
+            };
+            let Some(item) = item else { break };
+            yield item?;
+        }
+    }
+}
+
+/// End `stream` after its first `max` items, e.g. the first 300 results of `search_illust_iter`.
+///
+/// The inner stream is not polled again once `max` items were yielded, so no request for the
+/// following page is sent. Apply it to [`PageCursor::into_stream`](crate::cursor::PageCursor::into_stream)
+/// to limit the number of pages instead.
+///
+/// 在产出前 `max` 个元素后结束 `stream`（如 `search_illust_iter` 的前 300 个结果）。达到 `max` 后不再轮询内部流，
+/// 因此不会请求下一页。应用于 [`PageCursor::into_stream`](crate::cursor::PageCursor::into_stream) 可限制页数。
+pub fn take<S: Stream>(stream: S, max: usize) -> impl Stream<Item = S::Item> {
+    async_stream::stream! {
+        let mut stream = pin!(stream);
+        for _ in 0..max {
+            let Some(item) = next(stream.as_mut()).await else { break };
+            yield item;
+        }
+    }
+}
+
+/// Drop items of `stream` whose id was already yielded, e.g. illusts that a recommended feed
+/// repeats on a later page. Errors are passed through unchanged.
+///
+/// The ids seen so far are kept for the life of the stream.
+///
+/// 丢弃 `stream` 中 id 已出现过的元素（如推荐流在后续分页中重复的插画），错误原样传递。已出现的 id
+/// 在流的整个生命周期内保留。
+pub fn dedup_by_id<S, T>(stream: S) -> impl Stream<Item = Result<T, PixivError>>
+where
+    S: Stream<Item = Result<T, PixivError>>,
+    T: HasId,
+{
+    async_stream::stream! {
+        let mut stream = pin!(stream);
+        let mut seen = HashSet::new();
+        while let Some(item) = next(stream.as_mut()).await {
+            match item {
+                Ok(item) if !seen.insert(item.id()) => {}
+                item => yield item,
+            }
+        }
+    }
+}
+
+/// Collect every item of `stream`, stopping at the first error.
+///
+/// With `deadline` set, the whole collection fails with [`PixivError::DeadlineExceeded`] if it
+/// takes longer than that.
+///
+/// 收集 `stream` 的全部元素，遇到错误即停止；设置 `deadline` 后整体超时将返回 [`PixivError::DeadlineExceeded`]。
+pub async fn collect_all<S, T>(stream: S, deadline: Option<Duration>) -> Result<Vec<T>, PixivError>
+where
+    S: Stream<Item = Result<T, PixivError>>,
+{
+    let collect = async {
+        let mut stream = pin!(stream);
+        let mut items = Vec::new();
+        while let Some(item) = next(stream.as_mut()).await {
+            items.push(item?);
+        }
+        Ok(items)
+    };
+    match deadline {
+        Some(timeout) => tokio::time::timeout(timeout, collect)
+            .await
+            .map_err(|_| PixivError::DeadlineExceeded { timeout })?,
+        None => collect.await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slow_stream(count: u64, interval: Duration) -> impl Stream<Item = Result<u64, PixivError>> {
+        async_stream::try_stream! {
+            for i in 0..count {
+                tokio::time::sleep(interval).await;
+                yield i;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn collect_all_within_deadline() {
+        let stream = slow_stream(3, Duration::from_millis(20));
+        let items = collect_all(stream, Some(Duration::from_secs(5)))
+            .await
+            .unwrap();
+        assert_eq!(items, vec![0, 1, 2]);
+    }
+
+    #[tokio::test]
+    async fn collect_all_deadline_exceeded() {
+        let stream = slow_stream(3, Duration::from_millis(20));
+        let result = collect_all(stream, Some(Duration::from_millis(50))).await;
+        assert!(matches!(result, Err(PixivError::DeadlineExceeded { .. })));
+    }
+
+    #[tokio::test]
+    async fn with_deadline_yields_error_then_ends() {
+        let stream = with_deadline(
+            slow_stream(3, Duration::from_millis(20)),
+            Duration::from_millis(30),
+        );
+        let mut stream = pin!(stream);
+        assert_eq!(next(stream.as_mut()).await.unwrap().unwrap(), 0);
+        assert!(matches!(
+            next(stream.as_mut()).await,
+            Some(Err(PixivError::DeadlineExceeded { .. }))
+        ));
+        assert!(next(stream.as_mut()).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn take_stops_polling_at_limit() {
+        use std::cell::Cell;
+
+        let counter = Cell::new(0);
+        let produced = &counter;
+        let source = async_stream::stream! {
+            loop {
+                produced.set(produced.get() + 1);
+                yield produced.get();
+            }
+        };
+        let mut limited = pin!(take(source, 3));
+        let mut items = Vec::new();
+        while let Some(item) = next(limited.as_mut()).await {
+            items.push(item);
+        }
+        assert_eq!(items, [1, 2, 3]);
+        assert_eq!(produced.get(), 3);
+    }
+
+    #[tokio::test]
+    async fn dedup_drops_repeated_ids() {
+        struct Item(u64);
+        impl HasId for Item {
+            fn id(&self) -> u64 {
+                self.0
+            }
+        }
+
+        let source = async_stream::stream! {
+            for id in [1, 2, 1, 3, 2] {
+                yield Ok(Item(id));
+            }
+            yield Err(PixivError::NoAuth);
+        };
+        let mut deduped = pin!(dedup_by_id(source));
+        let mut ids = Vec::new();
+        while let Some(Ok(item)) = next(deduped.as_mut()).await {
+            ids.push(item.id());
+        }
+        assert_eq!(ids, [1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn with_deadline_max_never_elapses() {
+        let stream = with_deadline(slow_stream(2, Duration::from_millis(1)), Duration::MAX);
+        let items = collect_all(stream, None).await.unwrap();
+        assert_eq!(items, vec![0, 1]);
+    }
+}
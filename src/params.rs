@@ -104,6 +104,20 @@ pub enum Duration {
     LastMonth,
 }
 
+/// Category of pixivision (spotlight) articles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoStaticStr)]
+#[non_exhaustive]
+pub enum SpotlightCategory {
+    #[strum(serialize = "all")]
+    All,
+    #[strum(serialize = "illust")]
+    Illust,
+    #[strum(serialize = "manga")]
+    Manga,
+    #[strum(serialize = "cosplay")]
+    Cosplay,
+}
+
 impl_into_value_by_into_str_ref! {
     Filter,
     IllustType,
@@ -112,6 +126,7 @@ impl_into_value_by_into_str_ref! {
     SearchTarget,
     Sort,
     Duration,
+    SpotlightCategory,
 }
 
 #[cfg(test)]
@@ -171,4 +186,10 @@ mod tests {
         assert_eq!(<&'static str>::from(Duration::LastWeek), "last_week");
         assert_eq!(<&'static str>::from(Duration::LastMonth), "last_month");
     }
+
+    #[test]
+    fn spotlight_category_to_str() {
+        assert_eq!(<&'static str>::from(SpotlightCategory::All), "all");
+        assert_eq!(<&'static str>::from(SpotlightCategory::Cosplay), "cosplay");
+    }
 }
@@ -0,0 +1,120 @@
+//! Ranking history over a range of dates, for building local ranking archives.
+//!
+//! 按日期范围获取排行榜历史，用于建立本地排行榜存档。
+
+use std::time::Duration;
+
+use chrono::NaiveDate;
+use futures_core::stream::Stream;
+use serde::Deserialize;
+
+use crate::AppPixivAPI;
+use crate::PixivError;
+use crate::debug;
+use crate::models::IllustrationInfo;
+use crate::params::RankingMode;
+
+/// One page of `illust_ranking`.
+#[derive(Debug, Deserialize)]
+struct RankingPage {
+    illusts: Vec<IllustrationInfo>,
+    next_url: Option<String>,
+}
+
+impl RankingPage {
+    fn from_json(json: serde_json::Value) -> Result<Self, PixivError> {
+        serde_json::from_value(json).map_err(|error| PixivError::Serde {
+            error,
+            body: String::new(),
+            context: None,
+        })
+    }
+}
+
+impl AppPixivAPI {
+    /// Walk the `mode` ranking day by day from `from` to `to` (both inclusive), yielding each
+    /// date with its complete ranking (all pages, in rank order).
+    ///
+    /// Waits `interval` before every request after the first, to keep long archive runs polite.
+    /// Fails with `PixivError::InvalidArgument` if `from` is after `to`.
+    ///
+    /// 从 `from` 到 `to`（均含）逐日获取 `mode` 排行榜，产出每个日期及其完整排行（全部分页，按名次排序）。
+    /// 除第一个请求外，每个请求前等待 `interval`，以免长时间存档给服务端造成压力。`from` 晚于 `to` 时返回
+    /// `PixivError::InvalidArgument`。
+    pub fn illust_ranking_range<'a>(
+        &'a self,
+        mode: RankingMode,
+        from: NaiveDate,
+        to: NaiveDate,
+        interval: Duration,
+        with_auth: bool,
+    ) -> impl Stream<Item = Result<(NaiveDate, Vec<IllustrationInfo>), PixivError>> + 'a {
+        async_stream::try_stream! {
+            if from > to {
+                Err(PixivError::InvalidArgument {
+                    message: format!("ranking range starts after it ends ({from} > {to})"),
+                })?;
+            }
+            let mut first_request = true;
+            for date in from.iter_days().take_while(|date| *date <= to) {
+                let date_str = date.format("%Y-%m-%d").to_string();
+                debug!("illust_ranking_range: {date_str}");
+                let mut illusts = Vec::new();
+                let mut next_url: Option<String> = None;
+                loop {
+                    if !std::mem::take(&mut first_request) {
+                        tokio::time::sleep(interval).await;
+                    }
+                    let page = match &next_url {
+                        None => RankingPage::from_json(
+                            self.fetch_page(|| {
+                                self.illust_ranking(Some(mode), None, Some(&date_str), None, with_auth)
+                            })
+                            .await?,
+                        )?,
+                        Some(url) => {
+                            self.fetch_page(|| self.visit_next_url::<RankingPage>(url, with_auth))
+                                .await?
+                        }
+                    };
+                    illusts.extend(page.illusts);
+                    next_url = page.next_url;
+                    if next_url.is_none() {
+                        break;
+                    }
+                }
+                yield (date, illusts);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ranking_page() {
+        let page = RankingPage::from_json(serde_json::json!({
+            "illusts": [],
+            "next_url": "https://app-api.pixiv.net/v1/illust/ranking?mode=day&date=2024-01-01&offset=30"
+        }))
+        .unwrap();
+        assert!(page.illusts.is_empty());
+        assert!(page.next_url.is_some());
+        assert!(matches!(
+            RankingPage::from_json(serde_json::json!({"error": {}})),
+            Err(PixivError::Serde { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn rejects_inverted_range() {
+        let api = AppPixivAPI::new_no_auth();
+        let day = |d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap();
+        let stream =
+            api.illust_ranking_range(RankingMode::Day, day(2), day(1), Duration::ZERO, false);
+        let result = crate::paging::collect_all(stream, None).await;
+        assert!(matches!(result, Err(PixivError::InvalidArgument { .. })));
+    }
+}
@@ -0,0 +1,228 @@
+//! Builder for calling API endpoints that have no generated method, and per-request options.
+//!
+//! 用于调用尚无生成方法的 API 接口的请求构建器，以及单次请求选项。
+
+use std::time::Duration;
+
+use kv_pairs::{IntoValues, KVPairs};
+use reqwest::header::{ACCEPT_LANGUAGE, HeaderMap, HeaderName, HeaderValue};
+use serde::de::DeserializeOwned;
+
+use crate::AppPixivAPI;
+use crate::PixivError;
+use crate::aapi::HttpMethod;
+
+/// Options applied to the requests of an [`AppPixivAPI`] handle returned by
+/// [`AppPixivAPI::with_options`], overriding the client-wide settings.
+///
+/// ```no_run
+/// # async fn example(api: &pixiv3_rs::AppPixivAPI) -> Result<(), pixiv3_rs::PixivError> {
+/// use std::time::Duration;
+/// use pixiv3_rs::request::RequestOptions;
+///
+/// let slow = api.with_options(RequestOptions::new().timeout(Duration::from_secs(180)));
+/// let detail = slow.illust_detail(59580629, true).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// 通过 [`AppPixivAPI::with_options`] 返回的客户端句柄发出的请求所使用的选项，会覆盖客户端全局设置。
+#[derive(Debug, Clone, Default)]
+pub struct RequestOptions {
+    /// Total timeout of each request, instead of the client's.
+    pub timeout: Option<Duration>,
+    /// Headers added to each request, replacing default headers of the same name.
+    pub headers: HeaderMap,
+}
+
+impl RequestOptions {
+    /// Create empty options.
+    ///
+    /// 创建空的选项。
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Override the request timeout.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Add a header.
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Override the `Accept-Language` header (see [`AppPixivAPI::set_accept_language`]).
+    pub fn accept_language(self, language: HeaderValue) -> Self {
+        self.header(ACCEPT_LANGUAGE, language)
+    }
+}
+
+/// A request to an API endpoint, sent through [`AppPixivAPI::do_api_request`].
+///
+/// Paths starting with `/` are resolved against the client's API host; full URLs are used
+/// as is. Requests are authenticated unless [`ApiRequest::with_auth`] turns it off.
+///
+/// ```no_run
+/// # async fn example(api: &pixiv3_rs::AppPixivAPI) -> Result<(), pixiv3_rs::PixivError> {
+/// use pixiv3_rs::request::ApiRequest;
+///
+/// let result: serde_json::Value = ApiRequest::get("/v1/illust/detail")
+///     .param("illust_id", 59580629_u64)
+///     .send_as(api)
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// 发往 API 接口的请求，通过 [`AppPixivAPI::do_api_request`] 发送。以 `/` 开头的路径会拼接到客户端的 API
+/// 主机上，完整 URL 则原样使用；默认附带认证，可用 [`ApiRequest::with_auth`] 关闭。
+#[derive(Debug, Clone)]
+pub struct ApiRequest<'a> {
+    method: HttpMethod,
+    path: String,
+    params: KVPairs<'a>,
+    data: Option<KVPairs<'a>>,
+    headers: HeaderMap,
+    with_auth: bool,
+}
+
+impl<'a> ApiRequest<'a> {
+    /// Create a request with the given method and path or URL.
+    ///
+    /// 以指定方法与路径（或 URL）创建请求。
+    pub fn new(method: HttpMethod, path: impl Into<String>) -> Self {
+        Self {
+            method,
+            path: path.into(),
+            params: KVPairs::new(),
+            data: None,
+            headers: HeaderMap::new(),
+            with_auth: true,
+        }
+    }
+
+    /// Create a GET request.
+    pub fn get(path: impl Into<String>) -> Self {
+        Self::new(HttpMethod::GET, path)
+    }
+
+    /// Create a POST request.
+    pub fn post(path: impl Into<String>) -> Self {
+        Self::new(HttpMethod::POST, path)
+    }
+
+    /// Create a DELETE request.
+    pub fn delete(path: impl Into<String>) -> Self {
+        Self::new(HttpMethod::DELETE, path)
+    }
+
+    /// Add a query parameter; `None` adds nothing and slices add one pair per element.
+    ///
+    /// 添加查询参数；`None` 不添加，切片按元素逐个添加。
+    pub fn param<'b: 'a>(mut self, key: &'a str, value: impl IntoValues<'b>) -> Self {
+        self.params.push(key, value);
+        self
+    }
+
+    /// Add a form field, sending the request body as `application/x-www-form-urlencoded`.
+    ///
+    /// 添加表单字段，请求体将以 `application/x-www-form-urlencoded` 发送。
+    pub fn form<'b: 'a>(mut self, key: &'a str, value: impl IntoValues<'b>) -> Self {
+        self.data.get_or_insert_with(KVPairs::new).push(key, value);
+        self
+    }
+
+    /// Set a header. Setting `User-Agent` replaces all the default device headers.
+    ///
+    /// 设置请求头；设置 `User-Agent` 会替换全部默认设备请求头。
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Whether to send the `Authorization` header (default `true`).
+    ///
+    /// 是否发送 `Authorization` 请求头（默认 `true`）。
+    pub fn with_auth(mut self, with_auth: bool) -> Self {
+        self.with_auth = with_auth;
+        self
+    }
+
+    fn url(&self, api: &AppPixivAPI) -> String {
+        if self.path.starts_with('/') {
+            api.api_url(&self.path)
+        } else {
+            self.path.clone()
+        }
+    }
+
+    /// Send the request and return the raw response.
+    ///
+    /// 发送请求并返回原始响应。
+    pub async fn send(self, api: &AppPixivAPI) -> Result<reqwest::Response, PixivError> {
+        let url = self.url(api);
+        let params = (!self.params.is_empty()).then_some(self.params);
+        api.do_api_request(
+            self.method,
+            &url,
+            Some(self.headers),
+            params,
+            self.data,
+            self.with_auth,
+        )
+        .await
+    }
+
+    /// Send the request and parse the response into `T`, like the generated endpoint methods.
+    ///
+    /// 发送请求并将响应解析为 `T`，与生成的接口方法行为一致。
+    pub async fn send_as<T: DeserializeOwned>(self, api: &AppPixivAPI) -> Result<T, PixivError> {
+        let response = self.send(api).await?;
+        api.parse_response(response).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn options_collect_headers() {
+        let options = RequestOptions::new()
+            .timeout(Duration::from_secs(5))
+            .accept_language(HeaderValue::from_static("ja"));
+        assert_eq!(options.timeout, Some(Duration::from_secs(5)));
+        assert_eq!(options.headers[ACCEPT_LANGUAGE], "ja");
+    }
+
+    #[test]
+    fn builds_params_and_form() {
+        let tags = ["a".to_string(), "b".to_string()];
+        let request = ApiRequest::post("/v2/illust/bookmark/add")
+            .param("filter", None::<&str>)
+            .form("illust_id", 1_u64)
+            .form("tags[]", tags.as_slice());
+        assert!(request.params.is_empty());
+        let data = request.data.unwrap();
+        assert_eq!(data.content.len(), 3);
+        assert_eq!(data.content[0], ("illust_id", "1".into()));
+        assert!(request.with_auth);
+    }
+
+    #[test]
+    fn resolves_paths_against_hosts() {
+        let api = AppPixivAPI::new_no_auth();
+        assert_eq!(
+            ApiRequest::get("/v1/illust/detail").url(&api),
+            "https://app-api.pixiv.net/v1/illust/detail"
+        );
+        assert_eq!(
+            ApiRequest::get("https://example.com/x").url(&api),
+            "https://example.com/x"
+        );
+    }
+}
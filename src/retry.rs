@@ -0,0 +1,341 @@
+//! Retry policy with exponential backoff for transient failures.
+//!
+//! 针对暂时性失败的指数退避重试策略。
+
+use std::future::Future;
+use std::time::Duration;
+
+use crate::PixivError;
+use crate::aapi::HttpMethod;
+use crate::warn;
+
+/// How often and how patiently to retry a failed request.
+///
+/// 失败请求的重试次数与等待策略。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total attempts including the first one; `1` disables retrying.
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled for every further retry.
+    pub base_delay: Duration,
+    /// Upper bound of the delay between attempts. A server asking to wait longer with
+    /// `Retry-After` gets its error returned instead.
+    pub max_delay: Duration,
+    /// Randomize each delay between half and all of its value, so clients failing together do
+    /// not retry together.
+    pub jitter: bool,
+    /// Which errors are retried.
+    pub retry_on: RetryOn,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+            retry_on: RetryOn::default(),
+        }
+    }
+}
+
+/// Classes of errors a [`RetryPolicy`] retries. All are enabled by default, matching
+/// [`PixivError::is_retryable`].
+///
+/// [`RetryPolicy`] 重试的错误类别；默认全部启用，与 [`PixivError::is_retryable`] 一致。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryOn {
+    /// Timeouts, connect failures and interrupted transfers.
+    pub network: bool,
+    /// Rate limits (HTTP 429 or Pixiv's `Rate Limit` error).
+    pub rate_limited: bool,
+    /// Server errors (HTTP 5xx).
+    pub server_errors: bool,
+}
+
+impl Default for RetryOn {
+    fn default() -> Self {
+        Self {
+            network: true,
+            rate_limited: true,
+            server_errors: true,
+        }
+    }
+}
+
+impl RetryOn {
+    /// Whether `error` falls into one of the enabled classes.
+    ///
+    /// `error` 是否属于已启用的类别。
+    pub fn matches(&self, error: &PixivError) -> bool {
+        self.matches_request(HttpMethod::GET, error)
+    }
+
+    /// Whether `error` of a `method` request falls into one of the enabled classes. A request
+    /// other than GET (e.g. adding a comment) may have reached the server before a timeout or an
+    /// interrupted transfer, so of its network errors only connect failures match.
+    ///
+    /// `method` 请求的 `error` 是否属于已启用的类别。非 GET 请求（如发表评论）在超时或传输中断前可能已到达
+    /// 服务端，因此其网络错误中仅连接失败会被匹配。
+    pub fn matches_request(&self, method: HttpMethod, error: &PixivError) -> bool {
+        let root = error.root();
+        let network = match (method, root) {
+            (_, PixivError::Connect(_)) => true,
+            (HttpMethod::GET, root) => match root {
+                PixivError::Timeout(_) | PixivError::IncompleteDownload { .. } => true,
+                PixivError::Reqwest(e) => e.is_request() || e.is_body(),
+                _ => false,
+            },
+            _ => false,
+        };
+        let server_error = match root {
+            PixivError::Reqwest(e) => e.status().is_some_and(|s| s.is_server_error()),
+            _ => root.context().is_some_and(|c| c.status.is_server_error()),
+        };
+        (self.network && network)
+            || (self.rate_limited && root.is_rate_limited())
+            || (self.server_errors && server_error)
+    }
+}
+
+/// `delay` scaled by a random factor in `[0.5, 1.0]`.
+fn jittered(delay: Duration) -> Duration {
+    let mut bytes = [0u8; 4];
+    if aws_lc_rs::rand::fill(&mut bytes).is_err() {
+        return delay;
+    }
+    let factor = 0.5 + f64::from(u32::from_le_bytes(bytes)) / f64::from(u32::MAX) / 2.0;
+    delay.mul_f64(factor)
+}
+
+impl RetryPolicy {
+    /// A patient policy that only waits out rate limits: up to 8 attempts, sleeping for the
+    /// server's `Retry-After` of up to 5 minutes or backing off from 10 seconds to 5 minutes.
+    ///
+    /// Set it with [`AppPixivAPI::set_retry_policy`](crate::AppPixivAPI::set_retry_policy) so
+    /// every call, including the page fetches of `_iter` streams, survives temporary throttling.
+    ///
+    /// 仅等待限流的耐心策略：最多尝试 8 次，按服务端不超过 5 分钟的 `Retry-After` 或从 10 秒到 5 分钟指数退避等待。
+    /// 通过 [`AppPixivAPI::set_retry_policy`](crate::AppPixivAPI::set_retry_policy) 设置后，
+    /// 所有调用（包括 `_iter` 流的分页请求）都能挺过暂时的限流。
+    pub fn wait_on_rate_limit() -> Self {
+        Self {
+            max_attempts: 8,
+            base_delay: Duration::from_secs(10),
+            max_delay: Duration::from_secs(300),
+            jitter: true,
+            retry_on: RetryOn {
+                network: false,
+                rate_limited: true,
+                server_errors: false,
+            },
+        }
+    }
+
+    /// Delay before retry number `retry` (starting at 1).
+    ///
+    /// 第 `retry` 次重试（从 1 开始）前的等待时间。
+    pub fn delay_for(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+
+    /// Run `op` until it succeeds, fails with an error not selected by `retry_on`, or
+    /// `max_attempts` is reached. A `Retry-After` sent with a rate
+    /// limit takes precedence over the backoff delay; one longer than `max_delay` fails at once.
+    ///
+    /// 重复执行 `op`，直到成功、出现 `retry_on` 未选中的错误或达到 `max_attempts`。
+    /// 限流响应带有 `Retry-After` 时优先按其等待；若其长于 `max_delay` 则立即失败。
+    pub async fn run<T, F, Fut>(&self, op: F) -> Result<T, PixivError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, PixivError>>,
+    {
+        self.run_request(HttpMethod::GET, op).await
+    }
+
+    /// [`Self::run`] for a `method` request, retrying errors per [`RetryOn::matches_request`].
+    pub(crate) async fn run_request<T, F, Fut>(
+        &self,
+        method: HttpMethod,
+        mut op: F,
+    ) -> Result<T, PixivError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, PixivError>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match op().await {
+                Err(e)
+                    if attempt < self.max_attempts && self.retry_on.matches_request(method, &e) =>
+                {
+                    let delay = match e.retry_after() {
+                        // Retrying sooner would only be rejected again.
+                        Some(delay) if delay > self.max_delay => return Err(e),
+                        Some(delay) => delay,
+                        None if self.jitter => jittered(self.delay_for(attempt)),
+                        None => self.delay_for(attempt),
+                    };
+                    warn!(
+                        "request failed (attempt {attempt}/{}): {e}, retrying in {delay:?}",
+                        self.max_attempts
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay_doubles_up_to_max() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(300),
+            ..RetryPolicy::default()
+        };
+        assert_eq!(policy.delay_for(1), Duration::from_millis(100));
+        assert_eq!(policy.delay_for(2), Duration::from_millis(200));
+        assert_eq!(policy.delay_for(3), Duration::from_millis(300));
+        assert_eq!(policy.delay_for(30), Duration::from_millis(300));
+    }
+
+    #[tokio::test]
+    async fn retries_transient_errors_only() {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+            ..RetryPolicy::default()
+        };
+
+        let mut calls = 0;
+        let result: Result<(), _> = policy
+            .run(|| {
+                calls += 1;
+                async {
+                    Err(PixivError::RateLimited {
+                        body: String::new(),
+                        retry_after: None,
+                        context: None,
+                    })
+                }
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls, 3);
+
+        let mut calls = 0;
+        let result: Result<(), _> = policy
+            .run(|| {
+                calls += 1;
+                async { Err(PixivError::NoAuth) }
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn jitter_stays_within_bounds() {
+        let delay = Duration::from_secs(10);
+        for _ in 0..100 {
+            let d = jittered(delay);
+            assert!(d >= delay / 2 && d <= delay, "{d:?}");
+        }
+    }
+
+    #[test]
+    fn retry_on_selects_classes() {
+        let rate_limited = PixivError::RateLimited {
+            body: String::new(),
+            retry_after: None,
+            context: None,
+        };
+        assert!(RetryOn::default().matches(&rate_limited));
+        let no_rate_limits = RetryOn {
+            rate_limited: false,
+            ..RetryOn::default()
+        };
+        assert!(!no_rate_limits.matches(&rate_limited));
+        assert!(!RetryOn::default().matches(&PixivError::NoAuth));
+    }
+
+    #[test]
+    fn non_get_requests_retry_only_unsent_network_errors() {
+        let interrupted = PixivError::IncompleteDownload {
+            expected: 2,
+            received: 1,
+        };
+        let rate_limited = PixivError::RateLimited {
+            body: String::new(),
+            retry_after: None,
+            context: None,
+        };
+        let retry_on = RetryOn::default();
+        assert!(retry_on.matches_request(HttpMethod::GET, &interrupted));
+        assert!(!retry_on.matches_request(HttpMethod::POST, &interrupted));
+        assert!(!retry_on.matches_request(HttpMethod::DELETE, &interrupted));
+        assert!(retry_on.matches_request(HttpMethod::POST, &rate_limited));
+    }
+
+    #[tokio::test]
+    async fn wait_on_rate_limit_honors_retry_after() {
+        let policy = RetryPolicy {
+            base_delay: Duration::from_secs(3600),
+            ..RetryPolicy::wait_on_rate_limit()
+        };
+        let mut calls = 0;
+        let result = policy
+            .run(|| {
+                calls += 1;
+                let call = calls;
+                async move {
+                    match call {
+                        1 => Err(PixivError::RateLimited {
+                            body: String::new(),
+                            retry_after: Some(Duration::ZERO),
+                            context: None,
+                        }),
+                        2 => Err(PixivError::NoAuth),
+                        _ => Ok(()),
+                    }
+                }
+            })
+            .await;
+        assert!(matches!(result, Err(PixivError::NoAuth)));
+        assert_eq!(calls, 2);
+    }
+
+    #[tokio::test]
+    async fn retry_after_beyond_max_delay_fails() {
+        let policy = RetryPolicy {
+            max_delay: Duration::from_secs(1),
+            ..RetryPolicy::default()
+        };
+        let mut calls = 0;
+        let result: Result<(), _> = policy
+            .run(|| {
+                calls += 1;
+                async {
+                    Err(PixivError::RateLimited {
+                        body: String::new(),
+                        retry_after: Some(Duration::from_secs(3600)),
+                        context: None,
+                    })
+                }
+            })
+            .await;
+        assert!(matches!(result, Err(PixivError::RateLimited { .. })));
+        assert_eq!(calls, 1);
+    }
+}
@@ -0,0 +1,396 @@
+//! Reusable, validated search queries for `search_illust` / `search_novel`.
+//!
+//! 可复用、带校验的 `search_illust` / `search_novel` 搜索条件。
+
+#[cfg(feature = "stream")]
+use std::collections::HashSet;
+
+use chrono::NaiveDate;
+#[cfg(feature = "stream")]
+use futures_core::stream::Stream;
+use kv_pairs::{KVPairs, kv_pairs};
+
+use crate::AppPixivAPI;
+use crate::PixivError;
+#[cfg(feature = "stream")]
+use crate::models::IllustrationInfo;
+use crate::models::{SearchIllustrations, SearchNovel};
+#[cfg(feature = "stream")]
+use crate::next_url::PageToken;
+use crate::params::{Duration, SearchTarget, Sort};
+#[cfg(feature = "stream")]
+use crate::{debug, warn};
+
+/// Highest `offset` Pixiv serves for a search; later pages fail.
+#[cfg(feature = "stream")]
+const SEARCH_OFFSET_LIMIT: u64 = 5000;
+
+/// First day with works on Pixiv, used when a date-window search has no start date.
+#[cfg(feature = "stream")]
+const PIXIV_LAUNCH: NaiveDate = match NaiveDate::from_ymd_opt(2007, 9, 10) {
+    Some(date) => date,
+    None => panic!("valid date"),
+};
+
+/// Search conditions shared by [`AppPixivAPI::search_illust_query`] and [`AppPixivAPI::search_novel_query`].
+///
+/// [`AppPixivAPI::search_illust_query`] 与 [`AppPixivAPI::search_novel_query`] 共用的搜索条件。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchQuery {
+    /// Search keyword(s).
+    pub word: String,
+    /// What to match the keyword against.
+    pub search_target: SearchTarget,
+    /// Result order.
+    pub sort: Sort,
+    /// Relative time window; mutually exclusive with explicit dates.
+    pub duration: Option<Duration>,
+    /// First day (inclusive) of works to include.
+    pub start_date: Option<NaiveDate>,
+    /// Last day (inclusive) of works to include.
+    pub end_date: Option<NaiveDate>,
+    /// Whether AI-generated works are excluded (`search_ai_type`).
+    pub exclude_ai: Option<bool>,
+    /// Minimum bookmark count (premium only on Pixiv's side).
+    pub bookmark_num_min: Option<u32>,
+    /// Maximum bookmark count (premium only on Pixiv's side).
+    pub bookmark_num_max: Option<u32>,
+}
+
+impl SearchQuery {
+    /// Create a query for `word` with the API defaults: partial tag match, newest first.
+    ///
+    /// 以 API 默认值（标签部分一致、按最新排序）创建 `word` 的搜索条件。
+    pub fn new(word: impl Into<String>) -> Self {
+        Self {
+            word: word.into(),
+            search_target: SearchTarget::PartialMatchForTags,
+            sort: Sort::DateDesc,
+            duration: None,
+            start_date: None,
+            end_date: None,
+            exclude_ai: None,
+            bookmark_num_min: None,
+            bookmark_num_max: None,
+        }
+    }
+
+    /// Set what to match the keyword against.
+    pub fn target(mut self, search_target: SearchTarget) -> Self {
+        self.search_target = search_target;
+        self
+    }
+
+    /// Set the result order.
+    pub fn sort(mut self, sort: Sort) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// Restrict to a relative time window.
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// Restrict to works posted between `start` and `end` (both inclusive).
+    pub fn date_range(mut self, start: NaiveDate, end: NaiveDate) -> Self {
+        self.start_date = Some(start);
+        self.end_date = Some(end);
+        self
+    }
+
+    /// Restrict to works posted on or after `start`.
+    pub fn start_date(mut self, start: NaiveDate) -> Self {
+        self.start_date = Some(start);
+        self
+    }
+
+    /// Restrict to works posted on or before `end`.
+    pub fn end_date(mut self, end: NaiveDate) -> Self {
+        self.end_date = Some(end);
+        self
+    }
+
+    /// Exclude (or explicitly include) AI-generated works.
+    pub fn exclude_ai(mut self, exclude: bool) -> Self {
+        self.exclude_ai = Some(exclude);
+        self
+    }
+
+    /// Restrict the bookmark count; either bound may be open.
+    pub fn bookmark_range(mut self, min: Option<u32>, max: Option<u32>) -> Self {
+        self.bookmark_num_min = min;
+        self.bookmark_num_max = max;
+        self
+    }
+
+    /// Check the query for contradictions before sending it.
+    ///
+    /// 发送前检查搜索条件是否自相矛盾。
+    pub fn validate(&self) -> Result<(), PixivError> {
+        let invalid = |message: &str| {
+            Err(PixivError::InvalidArgument {
+                message: message.to_string(),
+            })
+        };
+        if self.word.trim().is_empty() {
+            return invalid("search word must not be empty");
+        }
+        if let (Some(start), Some(end)) = (self.start_date, self.end_date) {
+            if start > end {
+                return invalid("start_date is after end_date");
+            }
+        }
+        if self.duration.is_some() && (self.start_date.is_some() || self.end_date.is_some()) {
+            return invalid("duration cannot be combined with start_date/end_date");
+        }
+        if let (Some(min), Some(max)) = (self.bookmark_num_min, self.bookmark_num_max) {
+            if min > max {
+                return invalid("bookmark_num_min is greater than bookmark_num_max");
+            }
+        }
+        Ok(())
+    }
+
+    fn start_date_str(&self) -> Option<String> {
+        self.start_date.map(|d| d.format("%Y-%m-%d").to_string())
+    }
+
+    fn end_date_str(&self) -> Option<String> {
+        self.end_date.map(|d| d.format("%Y-%m-%d").to_string())
+    }
+
+    fn search_ai_type(&self) -> Option<u8> {
+        self.exclude_ai.map(|exclude| if exclude { 0 } else { 1 })
+    }
+
+    /// Parameters not covered by the generated method signatures.
+    fn bookmark_params(&self) -> KVPairs<'static> {
+        kv_pairs![
+            "bookmark_num_min" => self.bookmark_num_min,
+            "bookmark_num_max" => self.bookmark_num_max,
+        ]
+    }
+}
+
+impl AppPixivAPI {
+    /// Search illusts with a [`SearchQuery`]; the query is validated first.
+    ///
+    /// 使用 [`SearchQuery`] 搜索插画；请求前会先校验搜索条件。
+    pub async fn search_illust_query(
+        &self,
+        query: &SearchQuery,
+        offset: Option<&str>,
+        with_auth: bool,
+    ) -> Result<SearchIllustrations, PixivError> {
+        query.validate()?;
+        let start_date = query.start_date_str();
+        let end_date = query.end_date_str();
+        self.search_illust_with_extra(
+            &query.word,
+            Some(query.search_target),
+            Some(query.sort),
+            query.duration.map(<&'static str>::from),
+            start_date.as_deref(),
+            end_date.as_deref(),
+            None,
+            query.search_ai_type(),
+            offset,
+            query.bookmark_params(),
+            with_auth,
+        )
+        .await
+    }
+
+    /// Search novels with a [`SearchQuery`]; the query is validated first.
+    ///
+    /// 使用 [`SearchQuery`] 搜索小说；请求前会先校验搜索条件。
+    pub async fn search_novel_query(
+        &self,
+        query: &SearchQuery,
+        offset: Option<&str>,
+        with_auth: bool,
+    ) -> Result<SearchNovel, PixivError> {
+        query.validate()?;
+        let start_date = query.start_date_str();
+        let end_date = query.end_date_str();
+        let mut extra = query.bookmark_params();
+        extra.push("duration", query.duration);
+        self.search_novel_with_extra(
+            &query.word,
+            Some(query.search_target),
+            Some(query.sort),
+            None,
+            None,
+            start_date.as_deref(),
+            end_date.as_deref(),
+            None,
+            query.search_ai_type(),
+            offset,
+            extra,
+            with_auth,
+        )
+        .await
+    }
+}
+
+#[cfg(feature = "stream")]
+impl AppPixivAPI {
+    /// Search illusts with `query` beyond the offset limit of a single search (about 5000
+    /// results) by splitting its date range into windows of `window_days` days and searching
+    /// each in turn. Results are deduplicated by id.
+    ///
+    /// Without dates, the range runs from Pixiv's launch to today (JST). Windows are walked
+    /// newest first, or oldest first if `query.sort` is [`Sort::DateAsc`]. A window that still
+    /// reaches the offset limit is cut off with a warning; use smaller windows for busy tags.
+    /// Fails with `PixivError::InvalidArgument` if the query is invalid, uses `duration`, or
+    /// `window_days` is zero.
+    ///
+    /// 将 `query` 的日期范围按 `window_days` 天切分为多个窗口依次搜索，以突破单次搜索的 offset 上限（约 5000 条），
+    /// 结果按 id 去重。未指定日期时范围为 Pixiv 上线至今天（日本时间）。窗口按从新到旧遍历（`query.sort` 为
+    /// [`Sort::DateAsc`] 时从旧到新）；单个窗口仍达到上限时截断并记录警告，热门标签请使用更小的窗口。
+    /// 搜索条件无效、使用了 `duration` 或 `window_days` 为 0 时返回 `PixivError::InvalidArgument`。
+    pub fn search_illust_all<'a>(
+        &'a self,
+        query: &'a SearchQuery,
+        window_days: u32,
+        with_auth: bool,
+    ) -> impl Stream<Item = Result<IllustrationInfo, PixivError>> + 'a {
+        async_stream::try_stream! {
+            query.validate()?;
+            if query.duration.is_some() || window_days == 0 {
+                Err(PixivError::InvalidArgument {
+                    message: "date-window search needs window_days > 0 and no duration".to_string(),
+                })?;
+            }
+            // Pixiv dates are in JST (UTC+9).
+            let today = (chrono::Utc::now() + chrono::TimeDelta::hours(9)).date_naive();
+            let start = query.start_date.unwrap_or(PIXIV_LAUNCH);
+            let end = query.end_date.unwrap_or(today);
+            let ascending = query.sort == Sort::DateAsc;
+            let mut seen = HashSet::new();
+            for (window_start, window_end) in date_windows(start, end, window_days, ascending) {
+                debug!("search_illust_all: window {window_start} to {window_end}");
+                let window = query.clone().date_range(window_start, window_end);
+                let mut page = self
+                    .fetch_page(|| self.search_illust_query(&window, None, with_auth))
+                    .await?;
+                loop {
+                    for illust in page.illusts {
+                        if seen.insert(illust.id) {
+                            yield illust;
+                        }
+                    }
+                    let Some(next_url) = page.next_url else { break };
+                    let offset = PageToken::from_next_url(&next_url).and_then(|token| token.offset);
+                    if offset.is_some_and(|offset| offset >= SEARCH_OFFSET_LIMIT) {
+                        warn!(
+                            "search_illust_all: window {window_start} to {window_end} hit the offset limit, results cut off"
+                        );
+                        break;
+                    }
+                    self.pace_page().await;
+                    page = self
+                        .fetch_page(|| self.visit_next_url::<SearchIllustrations>(&next_url, with_auth))
+                        .await?;
+                }
+            }
+        }
+    }
+}
+
+/// Split `start..=end` into windows of `days` days, newest first unless `ascending`.
+#[cfg(feature = "stream")]
+fn date_windows(
+    start: NaiveDate,
+    end: NaiveDate,
+    days: u32,
+    ascending: bool,
+) -> Vec<(NaiveDate, NaiveDate)> {
+    let span = chrono::Days::new(u64::from(days.max(1)) - 1);
+    let mut windows = Vec::new();
+    let mut window_start = start;
+    while window_start <= end {
+        let window_end = window_start
+            .checked_add_days(span)
+            .map_or(end, |last| last.min(end));
+        windows.push((window_start, window_end));
+        match window_end.succ_opt() {
+            Some(next) => window_start = next,
+            None => break,
+        }
+    }
+    if !ascending {
+        windows.reverse();
+    }
+    windows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn valid_query() {
+        let query = SearchQuery::new("original")
+            .sort(Sort::PopularDesc)
+            .date_range(date(2024, 1, 1), date(2024, 1, 31))
+            .bookmark_range(Some(100), None)
+            .exclude_ai(true);
+        assert!(query.validate().is_ok());
+        assert_eq!(query.start_date_str().as_deref(), Some("2024-01-01"));
+        assert_eq!(query.search_ai_type(), Some(0));
+    }
+
+    #[test]
+    fn rejects_inverted_ranges() {
+        let query = SearchQuery::new("x").date_range(date(2024, 2, 1), date(2024, 1, 1));
+        assert!(query.validate().is_err());
+        let query = SearchQuery::new("x").bookmark_range(Some(10), Some(1));
+        assert!(query.validate().is_err());
+    }
+
+    #[test]
+    fn rejects_empty_word_and_duration_with_dates() {
+        assert!(SearchQuery::new("  ").validate().is_err());
+        let query = SearchQuery::new("x")
+            .duration(Duration::LastWeek)
+            .start_date(date(2024, 1, 1));
+        assert!(query.validate().is_err());
+    }
+
+    #[test]
+    fn bookmark_params_skip_open_bounds() {
+        let params = SearchQuery::new("x")
+            .bookmark_range(None, Some(500))
+            .bookmark_params();
+        assert_eq!(params.len(), 1);
+        assert_eq!(params[0].0, "bookmark_num_max");
+        assert_eq!(params[0].1, "500");
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn splits_date_windows() {
+        let windows = date_windows(date(2024, 1, 1), date(2024, 1, 10), 4, true);
+        assert_eq!(
+            windows,
+            [
+                (date(2024, 1, 1), date(2024, 1, 4)),
+                (date(2024, 1, 5), date(2024, 1, 8)),
+                (date(2024, 1, 9), date(2024, 1, 10)),
+            ]
+        );
+        let newest_first = date_windows(date(2024, 1, 1), date(2024, 1, 10), 4, false);
+        assert_eq!(newest_first[0], (date(2024, 1, 9), date(2024, 1, 10)));
+        assert_eq!(
+            date_windows(date(2024, 1, 1), date(2024, 1, 1), 1, false),
+            [(date(2024, 1, 1), date(2024, 1, 1))]
+        );
+    }
+}
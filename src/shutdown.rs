@@ -0,0 +1,93 @@
+//! Graceful shutdown: stop accepting new work and wait for in-flight operations.
+//!
+//! 优雅关闭：停止接受新任务并等待进行中的操作完成。
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use tokio::sync::Notify;
+
+use crate::PixivError;
+
+/// Tracks in-flight operations of a client and whether it is shutting down.
+#[derive(Debug, Default)]
+pub(crate) struct Shutdown {
+    closed: AtomicBool,
+    in_flight: AtomicUsize,
+    idle: Notify,
+}
+
+/// Marks one in-flight operation; dropping it completes the operation.
+#[derive(Debug)]
+pub(crate) struct OperationGuard<'a> {
+    shutdown: &'a Shutdown,
+}
+
+impl Drop for OperationGuard<'_> {
+    fn drop(&mut self) {
+        if self.shutdown.in_flight.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.shutdown.idle.notify_waiters();
+        }
+    }
+}
+
+impl Shutdown {
+    /// Register a new operation, or fail with `PixivError::ShuttingDown` after shutdown started.
+    pub(crate) fn begin(&self) -> Result<OperationGuard<'_>, PixivError> {
+        self.in_flight.fetch_add(1, Ordering::AcqRel);
+        let guard = OperationGuard { shutdown: self };
+        if self.closed.load(Ordering::Acquire) {
+            return Err(PixivError::ShuttingDown);
+        }
+        Ok(guard)
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    /// Stop accepting operations and wait up to `deadline` for in-flight ones.
+    /// Returns whether all of them finished in time.
+    pub(crate) async fn close(&self, deadline: Duration) -> bool {
+        self.closed.store(true, Ordering::Release);
+        let wait = async {
+            loop {
+                let idle = self.idle.notified();
+                tokio::pin!(idle);
+                idle.as_mut().enable();
+                if self.in_flight.load(Ordering::Acquire) == 0 {
+                    return;
+                }
+                idle.await;
+            }
+        };
+        tokio::time::timeout(deadline, wait).await.is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn rejects_work_after_close() {
+        let shutdown = Shutdown::default();
+        assert!(shutdown.close(Duration::from_millis(10)).await);
+        assert!(shutdown.is_closed());
+        assert!(matches!(shutdown.begin(), Err(PixivError::ShuttingDown)));
+        assert_eq!(shutdown.in_flight.load(Ordering::Acquire), 0);
+    }
+
+    #[tokio::test]
+    async fn waits_for_in_flight_operations() {
+        let shutdown = Shutdown::default();
+        let guard = shutdown.begin().unwrap();
+        assert!(!shutdown.close(Duration::from_millis(10)).await);
+
+        let (finished, ()) = tokio::join!(shutdown.close(Duration::from_secs(5)), async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            drop(guard);
+        });
+        assert!(finished);
+    }
+}
@@ -2,14 +2,16 @@
 
 use std::{sync::Arc, time::Duration};
 
-use arc_swap::ArcSwapOption;
+use arc_swap::{ArcSwap, ArcSwapOption};
 use chrono::{DateTime, Utc};
-use kv_pairs::kv_pairs;
+use kv_pairs::{KVPairs, kv_pairs};
+use serde::{Deserialize, Serialize};
 use tokio::sync::Mutex as AsyncMutex;
 
 use crate::PixivError;
-use crate::models::{TokenRefreshResult, parse_into};
-use crate::{debug, info};
+use crate::models::{AuthUser, TokenRefreshResult, parse_into, read_response_body};
+use crate::token_store::TokenStore;
+use crate::{debug, info, warn};
 
 /// Pixiv OAuth token endpoint.
 pub const AUTH_TOKEN_URL: &str = "https://oauth.secure.pixiv.net/auth/token";
@@ -39,6 +41,83 @@ pub const DEFAULT_EXPIRES_IN: u64 = 3600;
 /// 刷新 token 安全边距（秒）。
 pub const TOKEN_REFRESH_SAFE_MARGIN: u64 = 300;
 
+/// Environment variable read by [`TokenManager::from_env`] for the refresh token.
+///
+/// [`TokenManager::from_env`] 读取 refresh token 的环境变量。
+pub const ENV_REFRESH_TOKEN: &str = "PIXIV_REFRESH_TOKEN";
+/// Environment variable read by [`TokenManager::from_env`] for the access token.
+///
+/// [`TokenManager::from_env`] 读取 access token 的环境变量。
+pub const ENV_ACCESS_TOKEN: &str = "PIXIV_ACCESS_TOKEN";
+
+/// Read a non-empty environment variable.
+pub(crate) fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.trim().is_empty())
+}
+
+/// OAuth client identity used when refreshing tokens. Defaults to the Pixiv iOS app
+/// ([`DEFAULT_CLIENT_ID`], [`DEFAULT_CLIENT_SECRET`], [`HASH_SECRET`]).
+///
+/// 刷新 token 时使用的 OAuth 客户端身份，默认为 Pixiv iOS 应用（[`DEFAULT_CLIENT_ID`]、[`DEFAULT_CLIENT_SECRET`]、[`HASH_SECRET`]）。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OAuthCredentials {
+    /// OAuth client ID.
+    pub client_id: String,
+    /// OAuth client secret.
+    pub client_secret: String,
+    /// Secret for the `X-Client-Hash` header of the client identity. Not sent by this crate's refresh requests.
+    pub hash_secret: String,
+}
+
+impl Default for OAuthCredentials {
+    fn default() -> Self {
+        Self {
+            client_id: DEFAULT_CLIENT_ID.to_string(),
+            client_secret: DEFAULT_CLIENT_SECRET.to_string(),
+            hash_secret: HASH_SECRET.to_string(),
+        }
+    }
+}
+
+impl OAuthCredentials {
+    /// Create credentials from a client ID and secret, keeping the default [`HASH_SECRET`].
+    ///
+    /// 以客户端 ID 与密钥创建凭据，哈希密钥保持默认的 [`HASH_SECRET`]。
+    pub fn new(client_id: impl Into<String>, client_secret: impl Into<String>) -> Self {
+        Self {
+            client_id: client_id.into(),
+            client_secret: client_secret.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Replace the hash secret.
+    ///
+    /// 替换哈希密钥。
+    pub fn hash_secret(mut self, hash_secret: impl Into<String>) -> Self {
+        self.hash_secret = hash_secret.into();
+        self
+    }
+}
+
+/// Serializable snapshot of a refresh-token manager: the refresh token and the cached access token, if any.
+///
+/// refresh token 模式 token 管理器的可序列化快照：refresh token 及已缓存的 access token（如有）。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenState {
+    /// The refresh token.
+    pub refresh_token: String,
+    /// The cached access token.
+    pub access_token: Option<String>,
+    /// When the cached access token should be considered expired.
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Callback invoked with every successful OAuth refresh response; see [`TokenManager::on_token_refresh`].
+///
+/// 每次 OAuth 刷新成功时以响应调用的回调，参见 [`TokenManager::on_token_refresh`]。
+pub type TokenRefreshCallback = Arc<dyn Fn(&TokenRefreshResult) + Send + Sync>;
+
 /// Token manager: no auth, access token only, or refresh token with automatic refresh.
 ///
 /// Token 管理器：无认证、仅 access token、或带自动刷新的 refresh token。
@@ -58,12 +137,22 @@ pub enum TokenManager {
     ///
     /// 提供 refresh token，带自动刷新。
     RefreshToken {
-        /// The refresh token.
-        refresh_token: String,
+        /// The refresh token, replaced when Pixiv rotates it.
+        refresh_token: ArcSwap<String>,
         /// The current access token and its expiration time.
         access_token_and_expires_at: ArcSwapOption<(String, DateTime<Utc>)>,
         /// The lock for update.
         update_lock: AsyncMutex<()>,
+        /// Where refreshed state is persisted, if anywhere.
+        store: Option<Arc<dyn TokenStore>>,
+        /// Called with every successful refresh response.
+        on_refresh: Option<TokenRefreshCallback>,
+        /// The OAuth client identity used for refreshing.
+        credentials: OAuthCredentials,
+        /// The authenticated user from the latest refresh.
+        user: ArcSwapOption<AuthUser>,
+        /// HTTP client for refresh requests; a default client if `None`.
+        client: Option<reqwest::Client>,
     },
 }
 
@@ -87,57 +176,237 @@ impl TokenManager {
     /// 使用 refresh token 创建 token 管理器，access token 将在需要时获取或刷新。
     pub fn new_from_refresh_token(refresh_token: String) -> Self {
         Self::RefreshToken {
-            refresh_token,
+            refresh_token: ArcSwap::from_pointee(refresh_token),
             access_token_and_expires_at: ArcSwapOption::default(),
             update_lock: AsyncMutex::new(()),
+            store: None,
+            on_refresh: None,
+            credentials: OAuthCredentials::default(),
+            user: ArcSwapOption::default(),
+            client: None,
+        }
+    }
+
+    /// Create a refresh-token manager from the state saved in `store`, persisting later refreshes there too.
+    ///
+    /// Fails with `PixivError::InvalidArgument` if the store is empty; seed it with
+    /// [`TokenStore::save`] or use [`Self::with_token_store`] on a new manager instead.
+    ///
+    /// 从 `store` 中保存的状态创建 refresh token 模式的管理器，之后的刷新结果也会保存到其中。存储为空时返回
+    /// `PixivError::InvalidArgument`，此时可先用 [`TokenStore::save`] 写入，或对新管理器使用 [`Self::with_token_store`]。
+    pub fn from_token_store(store: Arc<dyn TokenStore>) -> Result<Self, PixivError> {
+        let state = store.load()?.ok_or_else(|| PixivError::InvalidArgument {
+            message: "token store holds no saved state".to_string(),
+        })?;
+        Ok(Self::load_state(state).with_token_store(store))
+    }
+
+    /// Persist the state to `store` after every successful refresh. No effect unless this is a refresh-token manager.
+    ///
+    /// 每次刷新成功后将状态保存到 `store`；仅对 refresh token 模式有效。
+    pub fn with_token_store(mut self, token_store: Arc<dyn TokenStore>) -> Self {
+        if let Self::RefreshToken { store, .. } = &mut self {
+            *store = Some(token_store);
+        }
+        self
+    }
+
+    /// Refresh with the given OAuth client identity instead of the default one. No effect unless this is a refresh-token manager.
+    ///
+    /// 使用指定的 OAuth 客户端身份（而非默认值）刷新 token；仅对 refresh token 模式有效。
+    pub fn with_credentials(mut self, oauth_credentials: OAuthCredentials) -> Self {
+        if let Self::RefreshToken { credentials, .. } = &mut self {
+            *credentials = oauth_credentials;
+        }
+        self
+    }
+
+    /// Send refresh requests through `http_client`, e.g. to share the proxy, TLS and timeout
+    /// settings of the API client. No effect unless this is a refresh-token manager.
+    ///
+    /// [`crate::AppPixivAPIBuilder::build`] does this with its own client unless a client is already set.
+    ///
+    /// 通过 `http_client` 发送刷新请求，例如与 API 客户端共用代理、TLS 与超时设置；仅对 refresh token 模式有效。
+    /// [`crate::AppPixivAPIBuilder::build`] 会在未设置时使用其自身的客户端。
+    pub fn with_http_client(mut self, http_client: reqwest::Client) -> Self {
+        if let Self::RefreshToken { client, .. } = &mut self {
+            *client = Some(http_client);
+        }
+        self
+    }
+
+    /// Like [`Self::with_http_client`], but keeps a client that is already set.
+    pub(crate) fn or_http_client(mut self, http_client: reqwest::Client) -> Self {
+        if let Self::RefreshToken { client, .. } = &mut self {
+            client.get_or_insert(http_client);
+        }
+        self
+    }
+
+    /// Call `callback` with the full response of every successful refresh, before it is used.
+    ///
+    /// Pixiv may rotate the refresh token in this response; the manager switches to the new one
+    /// itself, but applications persisting the token elsewhere should save it here before the old
+    /// one is invalidated. No effect unless this is a refresh-token manager.
+    ///
+    /// 每次刷新成功后以完整响应调用 `callback`。Pixiv 可能在响应中轮换 refresh token；管理器会自行改用新 token，
+    /// 但在别处保存 token 的应用应在此处及时保存新值，以免旧 token 失效。仅对 refresh token 模式有效。
+    pub fn on_token_refresh(
+        mut self,
+        callback: impl Fn(&TokenRefreshResult) + Send + Sync + 'static,
+    ) -> Self {
+        if let Self::RefreshToken { on_refresh, .. } = &mut self {
+            *on_refresh = Some(Arc::new(callback));
+        }
+        self
+    }
+
+    /// Create a token manager from [`ENV_REFRESH_TOKEN`], or else [`ENV_ACCESS_TOKEN`].
+    ///
+    /// Fails with `PixivError::InvalidArgument` if neither is set to a non-empty value.
+    ///
+    /// 依次从 [`ENV_REFRESH_TOKEN`]、[`ENV_ACCESS_TOKEN`] 读取 token 创建管理器；均未设置（或为空）时返回
+    /// `PixivError::InvalidArgument`。
+    pub fn from_env() -> Result<Self, PixivError> {
+        Self::from_vars(env_var)
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, PixivError> {
+        if let Some(refresh_token) = var(ENV_REFRESH_TOKEN) {
+            Ok(Self::new_from_refresh_token(refresh_token))
+        } else if let Some(access_token) = var(ENV_ACCESS_TOKEN) {
+            Ok(Self::new_from_access_token(access_token))
+        } else {
+            Err(PixivError::InvalidArgument {
+                message: format!("neither {ENV_REFRESH_TOKEN} nor {ENV_ACCESS_TOKEN} is set"),
+            })
+        }
+    }
+
+    /// Snapshot the state of a refresh-token manager. Returns `None` for the other variants, which hold nothing worth restoring.
+    ///
+    /// 获取 refresh token 模式的状态快照；其他模式返回 `None`。
+    pub fn save_state(&self) -> Option<TokenState> {
+        match self {
+            Self::RefreshToken {
+                refresh_token,
+                access_token_and_expires_at,
+                ..
+            } => {
+                let cached = access_token_and_expires_at.load_full();
+                Some(TokenState {
+                    refresh_token: refresh_token.load().to_string(),
+                    access_token: cached.as_ref().map(|c| c.0.clone()),
+                    expires_at: cached.as_ref().map(|c| c.1),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Restore a refresh-token manager from a snapshot. A cached access token is reused until it expires.
+    ///
+    /// 从快照恢复 refresh token 模式的 token 管理器；已缓存的 access token 在过期前会被继续使用。
+    pub fn load_state(state: TokenState) -> Self {
+        let cached = match (state.access_token, state.expires_at) {
+            (Some(access_token), Some(expires_at)) => Some(Arc::new((access_token, expires_at))),
+            _ => None,
+        };
+        Self::RefreshToken {
+            refresh_token: ArcSwap::from_pointee(state.refresh_token),
+            access_token_and_expires_at: ArcSwapOption::new(cached),
+            update_lock: AsyncMutex::new(()),
+            store: None,
+            on_refresh: None,
+            credentials: OAuthCredentials::default(),
+            user: ArcSwapOption::default(),
+            client: None,
         }
     }
 
     fn try_get_saved_token(
         access_token_and_expires_at: &ArcSwapOption<(String, DateTime<Utc>)>,
+        valid_until: DateTime<Utc>,
+        rejected: Option<&str>,
     ) -> Result<String, ()> {
         if let Some((access_token, expires_at)) = access_token_and_expires_at.load().as_deref() {
-            if *expires_at > Utc::now() {
+            if *expires_at > valid_until && rejected != Some(access_token.as_str()) {
                 return Ok(access_token.clone());
             }
         }
         Err(())
     }
 
-    async fn try_refresh_token(refresh_token: &str) -> Result<(String, DateTime<Utc>), PixivError> {
-        let client = reqwest::Client::new();
-        let request = client
-            .post(AUTH_TOKEN_URL)
-            .form(
-                &kv_pairs![
-                    "client_id" =>  DEFAULT_CLIENT_ID,
-                    "client_secret" => DEFAULT_CLIENT_SECRET,
-                    "grant_type" => "refresh_token",
-                    "include_policy" => "true",
-                    "refresh_token" => refresh_token,
-                ]
-                .content,
-            )
-            .header("User-Agent", AUTH_USER_AGENT);
-        let response = request.send().await?;
-        let parsed: TokenRefreshResult = parse_into(response.text().await?)?;
+    /// When the cached access token expires, for refresh-token managers holding one.
+    ///
+    /// 已缓存 access token 的过期时间（仅 refresh token 模式且已缓存时）。
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        match self {
+            Self::RefreshToken {
+                access_token_and_expires_at,
+                ..
+            } => access_token_and_expires_at.load().as_ref().map(|c| c.1),
+            _ => None,
+        }
+    }
 
-        let access_token = parsed.access_token;
-        let expires_at = Utc::now()
-            + Duration::from_secs(
-                match parsed.expires_in {
-                    Some(sec) if sec > 0 => sec as u64,
-                    _ => DEFAULT_EXPIRES_IN,
-                } - TOKEN_REFRESH_SAFE_MARGIN,
-            );
+    /// The authenticated user reported by the latest refresh, if any.
+    ///
+    /// 最近一次刷新返回的当前登录用户（如有）。
+    pub fn user(&self) -> Option<AuthUser> {
+        match self {
+            Self::RefreshToken { user, .. } => user.load().as_deref().cloned(),
+            _ => None,
+        }
+    }
 
-        Ok((access_token, expires_at))
+    async fn try_refresh_token(
+        client: &reqwest::Client,
+        refresh_token: &str,
+        credentials: &OAuthCredentials,
+    ) -> Result<(TokenRefreshResult, DateTime<Utc>), PixivError> {
+        let data = kv_pairs![
+            "client_id" => credentials.client_id.as_str(),
+            "client_secret" => credentials.client_secret.as_str(),
+            "grant_type" => "refresh_token",
+            "include_policy" => "true",
+            "refresh_token" => refresh_token,
+        ];
+        request_token(client, data).await
     }
 
     /// Returns the current access token, refreshing from refresh token if necessary.
     ///
     /// 返回当前 access token，若为 refresh token 模式则在需要时自动刷新。
     pub async fn get_access_token(&self) -> Result<String, PixivError> {
+        self.get_access_token_valid_until(Utc::now(), None).await
+    }
+
+    /// Refresh the access token now, ignoring any cached one, and return the new token.
+    ///
+    /// Other managers have nothing to refresh and behave like [`Self::get_access_token`].
+    ///
+    /// 忽略已缓存的 access token 立即刷新并返回新 token；非 refresh token 模式与 [`Self::get_access_token`] 相同。
+    pub async fn force_refresh(&self) -> Result<String, PixivError> {
+        self.get_access_token_valid_until(DateTime::<Utc>::MAX_UTC, None)
+            .await
+    }
+
+    /// Refresh the access token after the server rejected `rejected`, unless another task has
+    /// already replaced it; returns the token to retry with. Concurrent requests rejected with
+    /// the same token thus cause a single refresh.
+    pub(crate) async fn refresh_rejected(&self, rejected: &str) -> Result<String, PixivError> {
+        self.get_access_token_valid_until(Utc::now(), Some(rejected))
+            .await
+    }
+
+    /// Returns an access token that stays valid past `valid_until` and is not `rejected`,
+    /// refreshing if necessary.
+    async fn get_access_token_valid_until(
+        &self,
+        valid_until: DateTime<Utc>,
+        rejected: Option<&str>,
+    ) -> Result<String, PixivError> {
         match self {
             Self::NoAuth => Err(PixivError::NoAuth),
             Self::AccessToken { access_token } => Ok(access_token.clone()),
@@ -145,9 +414,14 @@ impl TokenManager {
                 access_token_and_expires_at,
                 update_lock,
                 refresh_token,
+                credentials,
+                client,
+                ..
             } => {
                 // Try to get saved token
-                if let Ok(access_token) = Self::try_get_saved_token(access_token_and_expires_at) {
+                if let Ok(access_token) =
+                    Self::try_get_saved_token(access_token_and_expires_at, valid_until, rejected)
+                {
                     return Ok(access_token);
                 }
 
@@ -157,20 +431,184 @@ impl TokenManager {
                 let mut _lock = update_lock.lock().await;
 
                 // Has any other thread already updated the token?
-                if let Ok(access_token) = Self::try_get_saved_token(access_token_and_expires_at) {
+                if let Ok(access_token) =
+                    Self::try_get_saved_token(access_token_and_expires_at, valid_until, rejected)
+                {
                     debug!("Token already updated by another thread");
                     return Ok(access_token);
                 }
 
                 // Refresh token
                 info!("Refreshing token");
-                let (access_token, expires_at) = Self::try_refresh_token(refresh_token).await?;
+                let (result, expires_at) = Self::try_refresh_token(
+                    &client.clone().unwrap_or_default(),
+                    &refresh_token.load(),
+                    credentials,
+                )
+                .await?;
                 info!("Token refreshed successfully, expires at {}", expires_at);
-                access_token_and_expires_at
-                    .store(Some(Arc::new((access_token.clone(), expires_at))));
-                Ok(access_token)
+                Ok(self.apply_refresh(result, expires_at))
+            }
+        }
+    }
+
+    /// Adopt a successful refresh response: notify the callback, switch to a rotated refresh token,
+    /// cache the access token and persist the new state. Returns the access token.
+    #[cfg_attr(not(feature = "log"), allow(unused_variables))]
+    pub(crate) fn apply_refresh(
+        &self,
+        result: TokenRefreshResult,
+        expires_at: DateTime<Utc>,
+    ) -> String {
+        let Self::RefreshToken {
+            refresh_token,
+            access_token_and_expires_at,
+            store,
+            on_refresh,
+            user,
+            ..
+        } = self
+        else {
+            return result.access_token;
+        };
+        if let Some(callback) = on_refresh {
+            callback(&result);
+        }
+        if let Some(rotated) = &result.refresh_token {
+            if **refresh_token.load() != *rotated {
+                info!("Refresh token rotated");
+                refresh_token.store(Arc::new(rotated.clone()));
+            }
+        }
+        if let Some(authenticated) = result.user {
+            user.store(Some(Arc::new(authenticated)));
+        }
+        let access_token = result.access_token;
+        access_token_and_expires_at.store(Some(Arc::new((access_token.clone(), expires_at))));
+        if let (Some(store), Some(state)) = (store, self.save_state()) {
+            if let Err(e) = store.save(&state) {
+                warn!("Failed to persist refreshed token: {e}");
             }
         }
+        access_token
+    }
+
+    /// Spawn a task refreshing the access token [`BACKGROUND_REFRESH_LEAD`] before it expires,
+    /// so requests never wait for a refresh. Returns `None` unless this is a refresh-token manager.
+    ///
+    /// At least [`BACKGROUND_REFRESH_RETRY`] passes between two refreshes, even for tokens
+    /// expiring sooner. The task stops when the returned handle is dropped or the manager is
+    /// dropped.
+    ///
+    /// 启动后台任务，在 access token 过期前 [`BACKGROUND_REFRESH_LEAD`] 主动刷新，避免请求等待刷新；
+    /// 仅 refresh token 模式返回 `Some`。两次刷新之间至少间隔 [`BACKGROUND_REFRESH_RETRY`]，即使 token
+    /// 更早过期。返回的句柄或 token 管理器被丢弃时任务停止。
+    #[cfg_attr(not(feature = "log"), allow(unused_variables))]
+    pub fn spawn_refresher(self: &Arc<Self>) -> Option<TokenRefresher> {
+        if !matches!(**self, Self::RefreshToken { .. }) {
+            return None;
+        }
+        let manager = Arc::downgrade(self);
+        let task = tokio::spawn(async move {
+            loop {
+                let Some(expires_at) = manager.upgrade().map(|m| m.expires_at()) else {
+                    return;
+                };
+                let wait = expires_at
+                    .map(|at| (at - Utc::now()).to_std().unwrap_or_default())
+                    .unwrap_or_default()
+                    .saturating_sub(BACKGROUND_REFRESH_LEAD);
+                tokio::time::sleep(wait).await;
+
+                let Some(manager) = manager.upgrade() else {
+                    return;
+                };
+                let valid_until = Utc::now() + BACKGROUND_REFRESH_LEAD;
+                let result = manager
+                    .get_access_token_valid_until(valid_until, None)
+                    .await;
+                drop(manager);
+                if let Err(e) = result {
+                    if e.is_auth_error() {
+                        warn!("Background token refresh rejected, stopping: {e}");
+                        return;
+                    }
+                    warn!("Background token refresh failed: {e}");
+                }
+                // Tokens living no longer than the safety margins are due again at once.
+                tokio::time::sleep(BACKGROUND_REFRESH_RETRY).await;
+            }
+        });
+        Some(TokenRefresher { task })
+    }
+}
+
+/// POST `data` to [`AUTH_TOKEN_URL`] and parse the token response, along with when the access
+/// token should be considered expired.
+pub(crate) async fn request_token(
+    client: &reqwest::Client,
+    data: KVPairs<'_>,
+) -> Result<(TokenRefreshResult, DateTime<Utc>), PixivError> {
+    #[cfg(feature = "http-trace")]
+    crate::http_trace::trace_request(
+        "POST",
+        AUTH_TOKEN_URL,
+        &reqwest::header::HeaderMap::new(),
+        None,
+        Some(&data),
+    );
+    let request = client
+        .post(AUTH_TOKEN_URL)
+        .form(&data.content)
+        .header("User-Agent", AUTH_USER_AGENT);
+    let response = request.send().await?;
+    let status = response.status();
+    let body = read_response_body(response, None).await?;
+    if !status.is_success() {
+        if let Some(error) = PixivError::from_oauth_body(&body) {
+            return Err(error);
+        }
+    }
+    let parsed: TokenRefreshResult = parse_into(body)?;
+
+    let expires_in = match parsed.expires_in {
+        Some(sec) if sec > 0 => sec as u64,
+        _ => DEFAULT_EXPIRES_IN,
+    };
+    let expires_at =
+        Utc::now() + Duration::from_secs(expires_in.saturating_sub(TOKEN_REFRESH_SAFE_MARGIN));
+
+    Ok((parsed, expires_at))
+}
+
+/// How long before expiry the background refresher renews the access token.
+///
+/// 后台刷新任务在 access token 过期前多久进行刷新。
+pub const BACKGROUND_REFRESH_LEAD: Duration = Duration::from_secs(60);
+/// Delay before the background refresher retries a failed refresh, and the least time between
+/// two of its refreshes.
+///
+/// 后台刷新失败后重试前的等待时间，也是两次后台刷新之间的最短间隔。
+pub const BACKGROUND_REFRESH_RETRY: Duration = Duration::from_secs(30);
+
+/// Handle of a background token refresh task; dropping it stops the task.
+///
+/// 后台 token 刷新任务的句柄，丢弃即停止任务。
+#[derive(Debug)]
+pub struct TokenRefresher {
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl TokenRefresher {
+    /// Stop the background task.
+    ///
+    /// 停止后台任务。
+    pub fn stop(self) {}
+}
+
+impl Drop for TokenRefresher {
+    fn drop(&mut self) {
+        self.task.abort();
     }
 }
 
@@ -186,6 +624,158 @@ mod tests {
         assert!(matches!(result, Err(PixivError::NoAuth)));
     }
 
+    #[test]
+    fn state_round_trip_reuses_cached_token() {
+        let state = TokenState {
+            refresh_token: "refresh".into(),
+            access_token: Some("cached".into()),
+            expires_at: Some(Utc::now() + Duration::from_secs(600)),
+        };
+        let json = serde_json::to_string(&state).unwrap();
+        let tm = TokenManager::load_state(serde_json::from_str(&json).unwrap());
+        assert_eq!(tm.save_state(), Some(state));
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        assert_eq!(rt.block_on(tm.get_access_token()).unwrap(), "cached");
+    }
+
+    #[test]
+    fn save_state_only_for_refresh_token() {
+        assert!(TokenManager::new_no_auth().save_state().is_none());
+        let tm = TokenManager::new_from_refresh_token("refresh".into());
+        let state = tm.save_state().unwrap();
+        assert_eq!(state.refresh_token, "refresh");
+        assert!(state.access_token.is_none());
+    }
+
+    #[test]
+    fn from_token_store_requires_saved_state() {
+        struct MemoryStore(std::sync::Mutex<Option<TokenState>>);
+        impl TokenStore for MemoryStore {
+            fn load(&self) -> Result<Option<TokenState>, PixivError> {
+                Ok(self.0.lock().unwrap().clone())
+            }
+            fn save(&self, state: &TokenState) -> Result<(), PixivError> {
+                *self.0.lock().unwrap() = Some(state.clone());
+                Ok(())
+            }
+        }
+
+        let store = Arc::new(MemoryStore(Default::default()));
+        assert!(matches!(
+            TokenManager::from_token_store(store.clone()),
+            Err(PixivError::InvalidArgument { .. })
+        ));
+
+        store
+            .save(&TokenState {
+                refresh_token: "refresh".into(),
+                access_token: None,
+                expires_at: None,
+            })
+            .unwrap();
+        let tm = TokenManager::from_token_store(store).unwrap();
+        assert_eq!(tm.save_state().unwrap().refresh_token, "refresh");
+    }
+
+    #[test]
+    fn refresh_adopts_rotated_token() {
+        let seen = Arc::new(std::sync::Mutex::new(None));
+        let tm = TokenManager::new_from_refresh_token("old".into()).on_token_refresh({
+            let seen = seen.clone();
+            move |result| *seen.lock().unwrap() = result.refresh_token.clone()
+        });
+        let expires_at = Utc::now() + Duration::from_secs(600);
+        let result = TokenRefreshResult {
+            access_token: "access".into(),
+            refresh_token: Some("new".into()),
+            expires_in: Some(3600),
+            user: None,
+        };
+        assert_eq!(tm.apply_refresh(result, expires_at), "access");
+        assert_eq!(seen.lock().unwrap().as_deref(), Some("new"));
+        let state = tm.save_state().unwrap();
+        assert_eq!(state.refresh_token, "new");
+        assert_eq!(state.access_token.as_deref(), Some("access"));
+    }
+
+    #[test]
+    fn refresh_rejected_reuses_replaced_token() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let tm = TokenManager::load_state(TokenState {
+            refresh_token: "refresh".into(),
+            access_token: Some("new".into()),
+            expires_at: Some(Utc::now() + Duration::from_secs(600)),
+        });
+        assert_eq!(rt.block_on(tm.refresh_rejected("old")).unwrap(), "new");
+    }
+
+    #[test]
+    fn force_refresh_without_refresh_token() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let tm = TokenManager::new_from_access_token("fixed".into());
+        assert_eq!(rt.block_on(tm.force_refresh()).unwrap(), "fixed");
+        assert!(tm.expires_at().is_none());
+    }
+
+    #[test]
+    fn credentials_only_for_refresh_token() {
+        let custom = OAuthCredentials::new("id", "secret");
+        assert_eq!(custom.hash_secret, HASH_SECRET);
+        let tm =
+            TokenManager::new_from_refresh_token("refresh".into()).with_credentials(custom.clone());
+        assert!(
+            matches!(tm, TokenManager::RefreshToken { credentials, .. } if credentials == custom)
+        );
+        assert!(matches!(
+            TokenManager::new_no_auth().with_credentials(custom),
+            TokenManager::NoAuth
+        ));
+    }
+
+    #[test]
+    fn or_http_client_keeps_explicit_client() {
+        let has_client = |tm: &TokenManager| {
+            matches!(
+                tm,
+                TokenManager::RefreshToken {
+                    client: Some(_),
+                    ..
+                }
+            )
+        };
+        let tm = TokenManager::new_from_refresh_token("refresh".into());
+        assert!(!has_client(&tm));
+        let tm = tm.or_http_client(reqwest::Client::new());
+        assert!(has_client(&tm));
+        assert!(matches!(
+            TokenManager::new_no_auth().with_http_client(reqwest::Client::new()),
+            TokenManager::NoAuth
+        ));
+    }
+
+    #[test]
+    fn from_vars_prefers_refresh_token() {
+        let vars = |name: &str| match name {
+            ENV_REFRESH_TOKEN => Some("refresh".to_string()),
+            ENV_ACCESS_TOKEN => Some("access".to_string()),
+            _ => None,
+        };
+        assert!(matches!(
+            TokenManager::from_vars(vars),
+            Ok(TokenManager::RefreshToken { .. })
+        ));
+
+        let vars = |name: &str| (name == ENV_ACCESS_TOKEN).then(|| "access".to_string());
+        assert!(matches!(
+            TokenManager::from_vars(vars),
+            Ok(TokenManager::AccessToken { .. })
+        ));
+
+        let err = TokenManager::from_vars(|_| None).err().unwrap();
+        assert!(err.to_string().contains(ENV_REFRESH_TOKEN));
+    }
+
     #[test]
     fn access_token_returns_token() {
         let tm = TokenManager::new_from_access_token("test_token".into());
@@ -193,4 +783,24 @@ mod tests {
         let result = rt.block_on(tm.get_access_token());
         assert_eq!(result.unwrap(), "test_token");
     }
+
+    #[tokio::test]
+    async fn refresher_only_for_refresh_token() {
+        assert!(
+            Arc::new(TokenManager::new_no_auth())
+                .spawn_refresher()
+                .is_none()
+        );
+
+        let state = TokenState {
+            refresh_token: "refresh".into(),
+            access_token: Some("cached".into()),
+            expires_at: Some(Utc::now() + Duration::from_secs(3600)),
+        };
+        let tm = Arc::new(TokenManager::load_state(state));
+        let refresher = tm.spawn_refresher().unwrap();
+        tokio::task::yield_now().await;
+        assert_eq!(tm.get_access_token().await.unwrap(), "cached");
+        refresher.stop();
+    }
 }
@@ -0,0 +1,102 @@
+//! Persistence of refresh-token state across process restarts.
+//!
+//! 跨进程重启持久化 refresh token 状态。
+
+#[cfg(feature = "fs")]
+use std::path::PathBuf;
+
+use crate::PixivError;
+use crate::token_manager::TokenState;
+
+/// Storage for the state of a refresh-token manager.
+///
+/// [`crate::TokenManager`] saves its state here after every successful refresh; failures to save
+/// are logged and do not fail the request.
+///
+/// refresh token 模式 token 管理器状态的存储。[`crate::TokenManager`] 每次刷新成功后都会保存状态；保存失败只记录日志，不影响请求。
+pub trait TokenStore: Send + Sync {
+    /// Load the saved state, or `None` if nothing has been saved yet.
+    ///
+    /// 读取已保存的状态；尚未保存时返回 `None`。
+    fn load(&self) -> Result<Option<TokenState>, PixivError>;
+
+    /// Save `state`, replacing any previous one.
+    ///
+    /// 保存 `state`，覆盖之前的状态。
+    fn save(&self, state: &TokenState) -> Result<(), PixivError>;
+}
+
+/// A [`TokenStore`] keeping the state as JSON in a file.
+///
+/// The file holds credentials; keep it out of shared or version-controlled directories.
+///
+/// 以 JSON 文件保存状态的 [`TokenStore`]。该文件包含凭据，请勿放在共享或受版本控制的目录中。
+#[cfg(feature = "fs")]
+#[derive(Debug, Clone)]
+pub struct FileTokenStore {
+    path: PathBuf,
+}
+
+#[cfg(feature = "fs")]
+impl FileTokenStore {
+    /// Create a store backed by the file at `path`; the file is created on the first save.
+    ///
+    /// 创建以 `path` 处文件为后端的存储；文件在首次保存时创建。
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[cfg(feature = "fs")]
+impl TokenStore for FileTokenStore {
+    fn load(&self) -> Result<Option<TokenState>, PixivError> {
+        let json = match std::fs::read_to_string(&self.path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|error| PixivError::Serde {
+                error,
+                body: String::new(),
+                context: None,
+            })
+    }
+
+    fn save(&self, state: &TokenState) -> Result<(), PixivError> {
+        let json = serde_json::to_vec_pretty(state).map_err(|error| PixivError::Serde {
+            error,
+            body: String::new(),
+            context: None,
+        })?;
+        // Write to a sibling file first so a crash never leaves a truncated state behind.
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_store_round_trip() {
+        let path =
+            std::env::temp_dir().join(format!("pixiv3-rs-test-{}-token.json", std::process::id()));
+        let store = FileTokenStore::new(&path);
+        assert!(store.load().unwrap().is_none());
+
+        let state = TokenState {
+            refresh_token: "refresh".into(),
+            access_token: Some("access".into()),
+            expires_at: None,
+        };
+        store.save(&state).unwrap();
+        assert_eq!(store.load().unwrap(), Some(state));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,343 @@
+//! Ugoira (animated illust) frames extracted from the frame zip, and, with the `ugoira-encode`
+//! feature, their encoding into animated images.
+//!
+//! 从帧压缩包中解出的 ugoira（动图）帧；启用 `ugoira-encode` 特性时还可将其编码为动画图片。
+
+use std::io::{Cursor, Read};
+#[cfg(feature = "fs")]
+use std::path::{Path, PathBuf};
+
+use bytes::Bytes;
+
+use crate::models::UgoiraMetadata;
+use crate::{AppPixivAPI, PixivError, debug};
+
+/// One frame of an ugoira.
+///
+/// ugoira 的一帧。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// File name inside the zip, e.g. `000000.jpg`.
+    pub file: String,
+    /// Display time in milliseconds.
+    pub delay: u32,
+    /// The encoded image (JPEG or PNG).
+    pub data: Bytes,
+}
+
+/// All frames of an ugoira, in display order.
+///
+/// ugoira 的全部帧（按显示顺序）。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ugoira {
+    /// The illust the frames belong to.
+    pub illust_id: u64,
+    /// Frames in display order.
+    pub frames: Vec<Frame>,
+}
+
+impl Ugoira {
+    /// Pair the frames listed in `metadata` with their images in `zip`.
+    ///
+    /// Fails with `PixivError::Zip` if the archive cannot be read or lacks a listed frame.
+    ///
+    /// 将 `metadata` 中列出的帧与 `zip` 中的图片对应起来；压缩包无法读取或缺少某帧时返回 `PixivError::Zip`。
+    pub fn from_zip(
+        illust_id: u64,
+        metadata: &UgoiraMetadata,
+        zip: &[u8],
+    ) -> Result<Self, PixivError> {
+        let mut archive = zip::ZipArchive::new(Cursor::new(zip))?;
+        let mut frames = Vec::with_capacity(metadata.frames.len());
+        for frame in &metadata.frames {
+            let mut entry = archive.by_name(&frame.file)?;
+            let mut data = Vec::with_capacity(entry.size() as usize);
+            entry.read_to_end(&mut data)?;
+            frames.push(Frame {
+                file: frame.file.clone(),
+                delay: frame.delay,
+                data: data.into(),
+            });
+        }
+        Ok(Self { illust_id, frames })
+    }
+
+    /// Total display time of one loop in milliseconds.
+    ///
+    /// 播放一轮的总时长（毫秒）。
+    pub fn duration_ms(&self) -> u64 {
+        self.frames.iter().map(|frame| u64::from(frame.delay)).sum()
+    }
+
+    /// Write every frame into `dir` (created if missing) under its file name; returns the paths
+    /// in display order.
+    ///
+    /// 将每一帧以其文件名写入 `dir`（不存在则创建），按显示顺序返回路径。
+    #[cfg(feature = "fs")]
+    pub async fn write_frames(&self, dir: &Path) -> Result<Vec<PathBuf>, PixivError> {
+        tokio::fs::create_dir_all(dir).await?;
+        let mut paths = Vec::with_capacity(self.frames.len());
+        for frame in &self.frames {
+            let path = dir.join(&frame.file);
+            tokio::fs::write(&path, &frame.data).await?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+}
+
+/// Animated image formats [`Ugoira::encode`] can produce.
+///
+/// Animated WebP is not supported: the `image` crate has no animated WebP encoder.
+///
+/// [`Ugoira::encode`] 可生成的动画图片格式。不支持动画 WebP：`image` crate 没有动画 WebP 编码器。
+#[cfg(feature = "ugoira-encode")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AnimationFormat {
+    /// Animated GIF. Colors are reduced to a 256-color palette per frame.
+    Gif,
+    /// Animated PNG, lossless.
+    Apng,
+}
+
+#[cfg(feature = "ugoira-encode")]
+impl AnimationFormat {
+    /// The usual file extension, without the dot.
+    ///
+    /// 常用的文件扩展名（不含点）。
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Gif => "gif",
+            Self::Apng => "png",
+        }
+    }
+}
+
+#[cfg(feature = "ugoira-encode")]
+impl Ugoira {
+    /// Encode the frames into an animation in `format` that loops forever, keeping each frame's
+    /// delay. Decoding and encoding are CPU-bound; run this on a blocking thread for large works.
+    ///
+    /// 将各帧编码为 `format` 格式、无限循环的动画，并保留每帧的延时。编解码为 CPU 密集型操作，大型作品请在
+    /// 阻塞线程中执行。
+    pub fn encode(&self, format: AnimationFormat) -> Result<Vec<u8>, PixivError> {
+        let frames = self
+            .frames
+            .iter()
+            .map(|frame| {
+                Ok((
+                    image::load_from_memory(&frame.data)?.to_rgba8(),
+                    frame.delay,
+                ))
+            })
+            .collect::<Result<Vec<_>, PixivError>>()?;
+        match format {
+            AnimationFormat::Gif => encode_gif(frames),
+            AnimationFormat::Apng => encode_apng(frames),
+        }
+    }
+
+    /// Encode the frames with [`Self::encode`] and write the animation to `path`.
+    ///
+    /// 用 [`Self::encode`] 编码各帧并将动画写入 `path`。
+    #[cfg(feature = "fs")]
+    pub async fn save_animation(
+        &self,
+        path: &Path,
+        format: AnimationFormat,
+    ) -> Result<(), PixivError> {
+        let data = self.encode(format)?;
+        tokio::fs::write(path, data).await?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "ugoira-encode")]
+fn encode_gif(frames: Vec<(image::RgbaImage, u32)>) -> Result<Vec<u8>, PixivError> {
+    use image::codecs::gif::{GifEncoder, Repeat};
+
+    let mut data = Vec::new();
+    {
+        let mut encoder = GifEncoder::new(&mut data);
+        encoder.set_repeat(Repeat::Infinite)?;
+        encoder.encode_frames(frames.into_iter().map(|(buffer, delay)| {
+            let delay = image::Delay::from_numer_denom_ms(delay, 1);
+            image::Frame::from_parts(buffer, 0, 0, delay)
+        }))?;
+    }
+    Ok(data)
+}
+
+#[cfg(feature = "ugoira-encode")]
+fn encode_apng(frames: Vec<(image::RgbaImage, u32)>) -> Result<Vec<u8>, PixivError> {
+    let png_error = |e: png::EncodingError| {
+        PixivError::Image(image::ImageError::Encoding(
+            image::error::EncodingError::new(image::ImageFormat::Png.into(), e),
+        ))
+    };
+    let Some((width, height)) = frames.first().map(|(buffer, _)| buffer.dimensions()) else {
+        return Err(PixivError::InvalidArgument {
+            message: "ugoira has no frames".to_string(),
+        });
+    };
+    let mut data = Vec::new();
+    let mut encoder = png::Encoder::new(&mut data, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .set_animated(frames.len() as u32, 0)
+        .map_err(png_error)?;
+    let mut writer = encoder.write_header().map_err(png_error)?;
+    for (buffer, delay) in &frames {
+        if buffer.dimensions() != (width, height) {
+            return Err(PixivError::InvalidArgument {
+                message: "ugoira frames differ in size".to_string(),
+            });
+        }
+        // Delays are in milliseconds; fall back to centiseconds if they do not fit in a `u16`.
+        let (numerator, denominator) = match u16::try_from(*delay) {
+            Ok(delay) => (delay, 1000),
+            Err(_) => (u16::try_from(delay / 10).unwrap_or(u16::MAX), 100),
+        };
+        writer
+            .set_frame_delay(numerator, denominator)
+            .map_err(png_error)?;
+        writer.write_image_data(buffer).map_err(png_error)?;
+    }
+    writer.finish().map_err(png_error)?;
+    Ok(data)
+}
+
+impl AppPixivAPI {
+    /// Fetch the metadata of ugoira `illust_id`, download its original-size frame zip (see
+    /// [`UgoiraZipUrls::original`](crate::models::UgoiraZipUrls::original)) into memory and
+    /// extract the frames with their delays.
+    ///
+    /// 获取 ugoira `illust_id` 的元信息，将原尺寸帧压缩包下载到内存并解出各帧及其延时。
+    pub async fn download_ugoira(
+        &self,
+        illust_id: u64,
+        with_auth: bool,
+    ) -> Result<Ugoira, PixivError> {
+        let metadata = self
+            .ugoira_metadata(illust_id, with_auth)
+            .await?
+            .ugoira_metadata;
+        debug!(
+            "ugoira {illust_id}: downloading {} frames",
+            metadata.frames.len()
+        );
+        let zip = self
+            .download_bytes(&metadata.zip_urls.original(), None)
+            .await?;
+        Ugoira::from_zip(illust_id, &metadata, &zip)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn zip_of(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        for (name, data) in files {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn metadata() -> UgoiraMetadata {
+        serde_json::from_str(
+            r#"{"zip_urls": {"medium": ""}, "frames": [{"file": "000001.jpg", "delay": 40}, {"file": "000000.jpg", "delay": 60}]}"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn frames_follow_metadata_order() {
+        let zip = zip_of(&[("000000.jpg", b"a"), ("000001.jpg", b"b")]);
+        let ugoira = Ugoira::from_zip(1, &metadata(), &zip).unwrap();
+        let files: Vec<_> = ugoira.frames.iter().map(|f| f.file.as_str()).collect();
+        assert_eq!(files, ["000001.jpg", "000000.jpg"]);
+        assert_eq!(ugoira.frames[0].data, "b");
+        assert_eq!(ugoira.duration_ms(), 100);
+    }
+
+    #[test]
+    fn missing_frame_is_an_error() {
+        let zip = zip_of(&[("000000.jpg", b"a")]);
+        assert!(matches!(
+            Ugoira::from_zip(1, &metadata(), &zip),
+            Err(PixivError::Zip(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn download_ugoira_fetches_original_zip() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let host = format!("http://{}", listener.local_addr().unwrap());
+        let body = format!(
+            r#"{{"ugoira_metadata": {{"zip_urls": {{"medium": "{host}/img-zip-ugoira/img/1_ugoira600x600.zip"}}, "frames": [{{"file": "000000.jpg", "delay": 60}}]}}}}"#
+        );
+        let zip = zip_of(&[("000000.jpg", b"a")]);
+        let server = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for body in [body.into_bytes(), zip] {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = [0u8; 1024];
+                let n = stream.read(&mut request).await.unwrap();
+                requests.push(String::from_utf8_lossy(&request[..n]).into_owned());
+                let head = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                    body.len()
+                );
+                stream.write_all(head.as_bytes()).await.unwrap();
+                stream.write_all(&body).await.unwrap();
+            }
+            requests
+        });
+
+        let api = AppPixivAPI::new_no_auth();
+        api.set_api_proxy(&host);
+        let ugoira = api.download_ugoira(1, false).await.unwrap();
+        assert_eq!(ugoira.frames[0].data, "a");
+        let requests = server.await.unwrap();
+        assert!(
+            requests[1].starts_with("GET /img-zip-ugoira/img/1_ugoira1920x1080.zip "),
+            "{}",
+            requests[1]
+        );
+    }
+
+    #[cfg(feature = "ugoira-encode")]
+    #[test]
+    fn encodes_gif_and_apng() {
+        let png = |color: [u8; 4]| {
+            let mut data = Vec::new();
+            image::RgbaImage::from_pixel(2, 2, image::Rgba(color))
+                .write_to(&mut Cursor::new(&mut data), image::ImageFormat::Png)
+                .unwrap();
+            data
+        };
+        let zip = zip_of(&[
+            ("000000.jpg", &png([255, 0, 0, 255])),
+            ("000001.jpg", &png([0, 0, 255, 255])),
+        ]);
+        let ugoira = Ugoira::from_zip(1, &metadata(), &zip).unwrap();
+
+        let gif = ugoira.encode(AnimationFormat::Gif).unwrap();
+        assert!(gif.starts_with(b"GIF89a"));
+        let apng = ugoira.encode(AnimationFormat::Apng).unwrap();
+        let decoder = png::Decoder::new(Cursor::new(apng.as_slice()));
+        let reader = decoder.read_info().unwrap();
+        let animation = reader.info().animation_control.unwrap();
+        assert_eq!(animation.num_frames, 2);
+        assert_eq!(animation.num_plays, 0);
+    }
+}
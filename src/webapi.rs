@@ -0,0 +1,464 @@
+//! Web AJAX API (`www.pixiv.net/ajax`), for data only the web site exposes.
+//!
+//! Web AJAX API（`www.pixiv.net/ajax`），用于仅网站提供的数据。
+
+use std::collections::BTreeSet;
+use std::sync::LazyLock;
+
+use kv_pairs::{KVPairs, kv_pairs};
+use reqwest::header::{COOKIE, HeaderMap, HeaderName, HeaderValue, REFERER, USER_AGENT};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tokio::sync::OnceCell;
+
+use crate::AppPixivAPI;
+use crate::PixivError;
+use crate::aapi::HttpMethod;
+use crate::debug;
+use crate::error::PixivApiError;
+use crate::models::{HasId, parse_into, read_response_body, u64_from_str_or_int};
+
+/// Base URL of the web AJAX API.
+pub const WEB_AJAX_BASE: &str = "https://www.pixiv.net/ajax";
+
+/// The web site, sent as Referer and fetched for the CSRF token.
+const WEB_ORIGIN: &str = "https://www.pixiv.net/";
+
+/// Browser User-Agent sent to the web site instead of the app's.
+const WEB_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 6.1; WOW64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/63.0.3239.132 Safari/537.36";
+
+/// Logged-in web session: the `PHPSESSID` cookie and the CSRF token of its pages.
+pub(crate) struct WebSession {
+    cookie: HeaderValue,
+    /// Fetched on the first POST request.
+    csrf_token: OnceCell<String>,
+}
+
+/// `{"error": bool, "message": string, "body": ...}` wrapper of every web API response.
+#[derive(Deserialize)]
+struct WebEnvelope {
+    error: bool,
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    body: serde_json::Value,
+}
+
+impl WebEnvelope {
+    /// The `body`, or the error the envelope reports.
+    fn into_body<T: DeserializeOwned>(self) -> Result<T, PixivError> {
+        if self.error {
+            return Err(PixivError::ErrResponse {
+                error: Box::new(PixivApiError {
+                    message: Some(self.message.clone()),
+                    ..PixivApiError::default()
+                }),
+                body: self.message,
+                context: None,
+            });
+        }
+        serde_json::from_value(self.body).map_err(|error| PixivError::Serde {
+            error,
+            body: String::new(),
+            context: None,
+        })
+    }
+}
+
+/// Image URLs of one page of an illust on the web site.
+///
+/// 网站上插画某一页的图片 URL。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebPageUrls {
+    /// 128×128 square thumbnail.
+    pub thumb_mini: String,
+    /// Thumbnail at most 540 px on its long side.
+    pub small: String,
+    /// Re-encoded JPEG at most 1200 px on its long side.
+    pub regular: String,
+    /// Original image file.
+    pub original: String,
+}
+
+/// One page of an illust, as returned by [`AppPixivAPI::web_illust_pages`].
+///
+/// [`AppPixivAPI::web_illust_pages`] 返回的插画单页。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebIllustPage {
+    /// Image URLs of the page.
+    pub urls: WebPageUrls,
+    /// Width of the original image, in pixels.
+    pub width: u32,
+    /// Height of the original image, in pixels.
+    pub height: u32,
+}
+
+/// Ids of every work of a user, newest first, as returned by
+/// [`AppPixivAPI::web_user_profile_all`]. Unlike `user_illusts`, this lists them all in one
+/// request.
+///
+/// [`AppPixivAPI::web_user_profile_all`] 返回的用户全部作品 id（由新到旧）；与 `user_illusts` 不同，一次请求即可列出全部。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WebUserProfileAll {
+    /// Illust and ugoira ids.
+    #[serde(default, deserialize_with = "ids_newest_first")]
+    pub illusts: Vec<u64>,
+    /// Manga ids.
+    #[serde(default, deserialize_with = "ids_newest_first")]
+    pub manga: Vec<u64>,
+    /// Novel ids.
+    #[serde(default, deserialize_with = "ids_newest_first")]
+    pub novels: Vec<u64>,
+}
+
+/// An illust as listed by the web site's feeds.
+///
+/// 网站信息流中列出的插画。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebIllustThumbnail {
+    /// Illust id.
+    #[serde(deserialize_with = "u64_from_str_or_int")]
+    pub id: u64,
+    /// Title.
+    pub title: String,
+    /// 0 for illusts, 1 for manga, 2 for ugoira.
+    pub illust_type: u8,
+    /// Square thumbnail URL.
+    pub url: String,
+    /// Tag names.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Id of the author.
+    #[serde(deserialize_with = "u64_from_str_or_int")]
+    pub user_id: u64,
+    /// Display name of the author.
+    pub user_name: String,
+    /// Width of the first page, in pixels.
+    pub width: u32,
+    /// Height of the first page, in pixels.
+    pub height: u32,
+    /// Number of pages.
+    pub page_count: u32,
+    /// Creation time, in RFC 3339 format.
+    pub create_date: String,
+    /// 0 for all ages, 1 for R-18, 2 for R-18G.
+    #[serde(default)]
+    pub x_restrict: u8,
+    /// 1 if not AI-generated, 2 if AI-generated, 0 if unknown.
+    #[serde(default)]
+    pub ai_type: u8,
+}
+
+impl HasId for WebIllustThumbnail {
+    fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+/// Paging state of a web feed page.
+///
+/// 网站信息流某一页的分页状态。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebFeedPage {
+    /// Ids of the works on this page, in feed order.
+    #[serde(default)]
+    pub ids: Vec<u64>,
+    /// Whether this is the last page of the feed.
+    #[serde(default)]
+    pub is_last_page: bool,
+}
+
+/// Works listed by a web feed page.
+///
+/// 网站信息流某一页列出的作品。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WebThumbnails {
+    /// Illusts, manga and ugoira on the page.
+    #[serde(default)]
+    pub illust: Vec<WebIllustThumbnail>,
+}
+
+/// One page of new illusts from followed users, as returned by
+/// [`AppPixivAPI::web_follow_latest_illust`].
+///
+/// [`AppPixivAPI::web_follow_latest_illust`] 返回的关注用户新作一页。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebFollowLatest {
+    /// Paging state.
+    pub page: WebFeedPage,
+    /// The works on this page.
+    #[serde(default)]
+    pub thumbnails: WebThumbnails,
+}
+
+/// Read the ids of an `{"<id>": null, ...}` object, which the web API sends as `[]` when empty.
+fn ids_newest_first<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<u64>, D::Error> {
+    let ids: BTreeSet<u64> = match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::Object(ids) => ids
+            .keys()
+            .map(|id| id.parse().map_err(serde::de::Error::custom))
+            .collect::<Result<_, _>>()?,
+        _ => BTreeSet::new(),
+    };
+    Ok(ids.into_iter().rev().collect())
+}
+
+/// Find the CSRF token embedded in a page of the web site.
+fn extract_csrf_token(html: &str) -> Option<&str> {
+    static CSRF_TOKEN_REGEX: LazyLock<regex::Regex> = LazyLock::new(|| {
+        regex::Regex::new(r#"\\?"token\\?"\s*:\s*\\?"([0-9a-f]{16,})"#).expect("valid regex")
+    });
+    Some(CSRF_TOKEN_REGEX.captures(html)?.get(1)?.as_str())
+}
+
+impl AppPixivAPI {
+    /// Log web API requests in as the browser session with cookie `PHPSESSID=phpsessid`, or
+    /// make them anonymously with `None` (the default). Some web endpoints, and every POST,
+    /// need a session. Fails with `PixivError::InvalidArgument` if `phpsessid` is not a valid
+    /// cookie value.
+    ///
+    /// 以 Cookie `PHPSESSID=phpsessid` 对应的浏览器会话发送 Web API 请求；`None`（默认）表示匿名请求。部分 Web
+    /// 接口及所有 POST 请求需要会话。`phpsessid` 不是合法的 Cookie 值时返回 `PixivError::InvalidArgument`。
+    pub fn set_web_session(&self, phpsessid: Option<&str>) -> Result<(), PixivError> {
+        let session = phpsessid
+            .map(|phpsessid| {
+                HeaderValue::from_str(&format!("PHPSESSID={phpsessid}"))
+                    .map(|cookie| WebSession {
+                        cookie,
+                        csrf_token: OnceCell::new(),
+                    })
+                    .map_err(|e| PixivError::InvalidArgument {
+                        message: format!("invalid PHPSESSID: {e}"),
+                    })
+            })
+            .transpose()?;
+        self.store_web_session(session);
+        Ok(())
+    }
+
+    /// Headers of a web request: browser User-Agent, Referer, and the session cookie if any.
+    pub(crate) fn web_headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(USER_AGENT, HeaderValue::from_static(WEB_USER_AGENT));
+        headers.insert(REFERER, HeaderValue::from_static(WEB_ORIGIN));
+        if let Some(session) = self.web_session() {
+            headers.insert(COOKIE, session.cookie.clone());
+        }
+        headers
+    }
+
+    /// The CSRF token of the web session, fetched from the web site once per session.
+    async fn web_csrf_token(&self, session: &WebSession) -> Result<HeaderValue, PixivError> {
+        let token = session
+            .csrf_token
+            .get_or_try_init(|| async {
+                debug!("fetching the web CSRF token");
+                let response = self
+                    .do_api_request(
+                        HttpMethod::GET,
+                        WEB_ORIGIN,
+                        Some(self.web_headers()),
+                        None,
+                        None,
+                        false,
+                    )
+                    .await?;
+                let html = read_response_body(response, self.max_response_size()).await?;
+                extract_csrf_token(&html).map(str::to_string).ok_or(
+                    PixivError::UnintelligibleResponse {
+                        body: "no CSRF token in the web page; is the session logged in?"
+                            .to_string(),
+                    },
+                )
+            })
+            .await?;
+        HeaderValue::from_str(token).map_err(|e| PixivError::InvalidArgument {
+            message: format!("invalid CSRF token: {e}"),
+        })
+    }
+
+    /// Call the web API endpoint `path` (relative to [`WEB_AJAX_BASE`], e.g. `/illust/1/pages`)
+    /// and parse the `body` of its response into `T`. POST requests carry the session's CSRF
+    /// token and fail with `PixivError::NoAuth` without a [web session](Self::set_web_session).
+    ///
+    /// 调用 Web API 接口 `path`（相对于 [`WEB_AJAX_BASE`]，如 `/illust/1/pages`），并将响应的 `body` 解析为 `T`。
+    /// POST 请求会附带会话的 CSRF token，未设置 [Web 会话](Self::set_web_session) 时返回 `PixivError::NoAuth`。
+    pub async fn web_call<T: DeserializeOwned>(
+        &self,
+        method: HttpMethod,
+        path: &str,
+        params: Option<KVPairs<'_>>,
+        data: Option<KVPairs<'_>>,
+    ) -> Result<T, PixivError> {
+        let mut headers = self.web_headers();
+        if let HttpMethod::POST = method {
+            let session = self.web_session().ok_or(PixivError::NoAuth)?;
+            headers.insert(
+                HeaderName::from_static("x-csrf-token"),
+                self.web_csrf_token(&session).await?,
+            );
+        }
+        let url = format!("{WEB_AJAX_BASE}{path}");
+        let response = self
+            .do_api_request(method, &url, Some(headers), params, data, false)
+            .await?;
+        let body = read_response_body(response, self.max_response_size()).await?;
+        parse_into::<WebEnvelope, _>(body)?.into_body()
+    }
+
+    /// Image URLs and sizes of every page of an illust, from the web site.
+    ///
+    /// 从网站获取插画每一页的图片 URL 与尺寸。
+    pub async fn web_illust_pages(&self, illust_id: u64) -> Result<Vec<WebIllustPage>, PixivError> {
+        self.web_call(
+            HttpMethod::GET,
+            &format!("/illust/{illust_id}/pages"),
+            None,
+            None,
+        )
+        .await
+    }
+
+    /// Ids of all illusts, manga and novels of a user, from the web site.
+    ///
+    /// 从网站获取用户全部插画、漫画与小说的 id。
+    pub async fn web_user_profile_all(
+        &self,
+        user_id: u64,
+    ) -> Result<WebUserProfileAll, PixivError> {
+        self.web_call(
+            HttpMethod::GET,
+            &format!("/user/{user_id}/profile/all"),
+            None,
+            None,
+        )
+        .await
+    }
+
+    /// Page `page` (starting at 1) of new illusts from followed users, as on the web site; it
+    /// includes works the app's `illust_follow` sometimes leaves out. `mode` is `all` (the
+    /// default) or `r18`. Needs a [web session](Self::set_web_session).
+    ///
+    /// 网站上关注用户新作的第 `page` 页（从 1 开始），包含 App 的 `illust_follow` 有时不返回的作品。`mode` 为
+    /// `all`（默认）或 `r18`。需要 [Web 会话](Self::set_web_session)。
+    pub async fn web_follow_latest_illust(
+        &self,
+        page: u32,
+        mode: Option<&str>,
+    ) -> Result<WebFollowLatest, PixivError> {
+        let params = kv_pairs![
+            "p" => page,
+            "mode" => mode.unwrap_or("all"),
+        ];
+        self.web_call(HttpMethod::GET, "/follow_latest/illust", Some(params), None)
+            .await
+    }
+
+    /// Iterate over the illusts of [`Self::web_follow_latest_illust`], page after page until the
+    /// last one.
+    ///
+    /// [`Self::web_follow_latest_illust`] 的迭代版本，逐页获取直到最后一页。
+    #[cfg(feature = "stream")]
+    pub fn web_follow_latest_illust_iter<'a>(
+        &'a self,
+        mode: Option<&'a str>,
+    ) -> impl futures_core::stream::Stream<Item = Result<WebIllustThumbnail, PixivError>> + 'a {
+        async_stream::try_stream! {
+            let mut page = 1;
+            loop {
+                if page > 1 {
+                    self.pace_page().await;
+                }
+                let result = self
+                    .fetch_page(|| self.web_follow_latest_illust(page, mode))
+                    .await?;
+                for illust in result.thumbnails.illust {
+                    yield illust;
+                }
+                if result.page.is_last_page {
+                    break;
+                }
+                page += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_profile_ids() {
+        let envelope: WebEnvelope = serde_json::from_str(
+            r#"{"error": false, "message": "", "body": {
+                "illusts": {"100": null, "300": null, "200": null},
+                "manga": [],
+                "novels": {"7": null}
+            }}"#,
+        )
+        .unwrap();
+        let profile: WebUserProfileAll = envelope.into_body().unwrap();
+        assert_eq!(profile.illusts, [300, 200, 100]);
+        assert!(profile.manga.is_empty());
+        assert_eq!(profile.novels, [7]);
+    }
+
+    #[test]
+    fn parses_follow_latest() {
+        let envelope: WebEnvelope = serde_json::from_str(
+            r#"{"error": false, "message": "", "body": {
+                "page": {"ids": [123], "isLastPage": true, "tags": []},
+                "tagTranslation": [],
+                "thumbnails": {"illust": [{
+                    "id": "123", "title": "t", "illustType": 0,
+                    "url": "https://i.pximg.net/c/250x250_80_a2/img-master/img/123_p0_square1200.jpg",
+                    "tags": ["a"], "userId": "11", "userName": "u", "width": 800, "height": 600,
+                    "pageCount": 1, "createDate": "2024-01-01T00:00:00+09:00", "xRestrict": 0, "aiType": 1
+                }], "novel": []}
+            }}"#,
+        )
+        .unwrap();
+        let latest: WebFollowLatest = envelope.into_body().unwrap();
+        assert!(latest.page.is_last_page);
+        let illust = &latest.thumbnails.illust[0];
+        assert_eq!((illust.id(), illust.user_id), (123, 11));
+    }
+
+    #[test]
+    fn reports_envelope_errors() {
+        let envelope: WebEnvelope =
+            serde_json::from_str(r#"{"error": true, "message": "not found", "body": []}"#).unwrap();
+        match envelope.into_body::<WebUserProfileAll>() {
+            Err(PixivError::ErrResponse { error, .. }) => {
+                assert_eq!(error.description(), Some("not found"));
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn finds_csrf_token() {
+        let legacy = r#"<meta name="global-data" content='{"token":"0123456789abcdef0123"}'>"#;
+        assert_eq!(extract_csrf_token(legacy), Some("0123456789abcdef0123"));
+        let next = r#"{\"api\":{\"token\":\"fedcba98765432100000\"}}"#;
+        assert_eq!(extract_csrf_token(next), Some("fedcba98765432100000"));
+        assert_eq!(extract_csrf_token("<html></html>"), None);
+    }
+
+    #[tokio::test]
+    async fn post_needs_session() {
+        let api = AppPixivAPI::new_no_auth();
+        let result = api
+            .web_call::<serde_json::Value>(HttpMethod::POST, "/illusts/like", None, None)
+            .await;
+        assert!(matches!(result, Err(PixivError::NoAuth)));
+        assert!(api.set_web_session(Some("a\nb")).is_err());
+        api.set_web_session(Some("12345_abc")).unwrap();
+        assert_eq!(api.web_headers()[COOKIE], "PHPSESSID=12345_abc");
+    }
+}